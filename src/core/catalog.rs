@@ -0,0 +1,256 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use super::data_source::DataSource;
+use super::error::{QrustError, Result};
+
+/// The catalog that unqualified and partially qualified table names are resolved in.
+pub const DEFAULT_CATALOG: &str = "qrust";
+/// The schema that unqualified table names are resolved in.
+pub const DEFAULT_SCHEMA: &str = "public";
+
+/// A named collection of schemas.
+pub trait CatalogProvider: Send + Sync {
+    /// Returns the catalog as `Any` so that it can be downcast to its concrete type.
+    fn as_any(&self) -> &dyn Any;
+    /// Returns the names of every schema in the catalog, in sorted order.
+    fn schema_names(&self) -> Vec<String>;
+    fn schema(&self, name: &str) -> Option<Arc<dyn SchemaProvider>>;
+
+    /// Adds a schema to the catalog, returning the schema it replaced if there was one.
+    fn register_schema(
+        &self,
+        name: &str,
+        _schema: Arc<dyn SchemaProvider>,
+    ) -> Result<Option<Arc<dyn SchemaProvider>>> {
+        Err(QrustError::NotImplemented(format!(
+            "Registering schema {} is not supported by this catalog",
+            name
+        )))
+    }
+}
+
+/// A named collection of tables.
+pub trait SchemaProvider: Send + Sync {
+    /// Returns the schema as `Any` so that it can be downcast to its concrete type.
+    fn as_any(&self) -> &dyn Any;
+    /// Returns the names of every table in the schema, in sorted order.
+    fn table_names(&self) -> Vec<String>;
+    fn table(&self, name: &str) -> Option<Arc<dyn DataSource>>;
+
+    fn table_exists(&self, name: &str) -> bool {
+        self.table(name).is_some()
+    }
+
+    /// Adds a table to the schema, returning the table it replaced if there was one.
+    fn register_table(
+        &self,
+        name: &str,
+        _table: Arc<dyn DataSource>,
+    ) -> Result<Option<Arc<dyn DataSource>>> {
+        Err(QrustError::NotImplemented(format!(
+            "Registering table {} is not supported by this schema",
+            name
+        )))
+    }
+
+    /// Removes a table from the schema, returning it if it existed.
+    fn deregister_table(&self, name: &str) -> Result<Option<Arc<dyn DataSource>>> {
+        Err(QrustError::NotImplemented(format!(
+            "Deregistering table {} is not supported by this schema",
+            name
+        )))
+    }
+}
+
+/// A catalog that keeps its schemas in memory.
+#[derive(Default)]
+pub struct MemoryCatalogProvider {
+    schemas: RwLock<HashMap<String, Arc<dyn SchemaProvider>>>,
+}
+
+impl MemoryCatalogProvider {
+    pub fn new() -> MemoryCatalogProvider {
+        MemoryCatalogProvider {
+            schemas: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl CatalogProvider for MemoryCatalogProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.schemas.read().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    fn schema(&self, name: &str) -> Option<Arc<dyn SchemaProvider>> {
+        self.schemas.read().unwrap().get(name).cloned()
+    }
+
+    fn register_schema(
+        &self,
+        name: &str,
+        schema: Arc<dyn SchemaProvider>,
+    ) -> Result<Option<Arc<dyn SchemaProvider>>> {
+        Ok(self
+            .schemas
+            .write()
+            .unwrap()
+            .insert(name.to_owned(), schema))
+    }
+}
+
+/// A schema that keeps its tables in memory.
+#[derive(Default)]
+pub struct MemorySchemaProvider {
+    tables: RwLock<HashMap<String, Arc<dyn DataSource>>>,
+}
+
+impl MemorySchemaProvider {
+    pub fn new() -> MemorySchemaProvider {
+        MemorySchemaProvider {
+            tables: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl SchemaProvider for MemorySchemaProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn table_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.tables.read().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    fn table(&self, name: &str) -> Option<Arc<dyn DataSource>> {
+        self.tables.read().unwrap().get(name).cloned()
+    }
+
+    fn register_table(
+        &self,
+        name: &str,
+        table: Arc<dyn DataSource>,
+    ) -> Result<Option<Arc<dyn DataSource>>> {
+        Ok(self.tables.write().unwrap().insert(name.to_owned(), table))
+    }
+
+    fn deregister_table(&self, name: &str) -> Result<Option<Arc<dyn DataSource>>> {
+        Ok(self.tables.write().unwrap().remove(name))
+    }
+}
+
+/// The fully qualified name of a table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedTableReference {
+    pub catalog: String,
+    pub schema: String,
+    pub table: String,
+}
+
+impl ResolvedTableReference {
+    /// Resolves a name made of one to three parts, `[[catalog.]schema.]table`, filling in
+    /// the default catalog and schema for the parts that are missing.
+    pub fn try_from_parts(parts: &[String]) -> Result<ResolvedTableReference> {
+        let (catalog, schema, table) = match parts {
+            [table] => (DEFAULT_CATALOG, DEFAULT_SCHEMA, table),
+            [schema, table] => (DEFAULT_CATALOG, schema.as_str(), table),
+            [catalog, schema, table] => (catalog.as_str(), schema.as_str(), table),
+            _ => {
+                return Err(QrustError::Plan(format!(
+                    "Invalid table name {}",
+                    parts.join(".")
+                )))
+            }
+        };
+        Ok(ResolvedTableReference {
+            catalog: catalog.to_owned(),
+            schema: schema.to_owned(),
+            table: table.clone(),
+        })
+    }
+
+    /// Resolves a name written with dots between its parts, e.g. `analytics.events`.
+    pub fn try_from_name(name: &str) -> Result<ResolvedTableReference> {
+        let parts: Vec<String> = name.split('.').map(|p| p.to_owned()).collect();
+        ResolvedTableReference::try_from_parts(&parts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data_source::CsvDataSource;
+    use crate::core::data_type::ArrowType;
+    use crate::core::helper::schema;
+
+    #[test]
+    fn memory_providers() {
+        let catalog = MemoryCatalogProvider::new();
+        let analytics = Arc::new(MemorySchemaProvider::new());
+        let source = Arc::new(CsvDataSource::new(
+            schema(vec![("a", ArrowType::Int64Type, false)]),
+            "events.csv".to_owned(),
+        ));
+
+        assert!(catalog
+            .register_schema("analytics", Arc::clone(&analytics) as _)
+            .unwrap()
+            .is_none());
+        assert!(analytics
+            .register_table("events", source)
+            .unwrap()
+            .is_none());
+        analytics
+            .register_table(
+                "clicks",
+                Arc::new(CsvDataSource::new(
+                    schema(vec![("b", ArrowType::StringType, false)]),
+                    "clicks.csv".to_owned(),
+                )),
+            )
+            .unwrap();
+
+        assert_eq!(catalog.schema_names(), vec!["analytics"]);
+        let schema = catalog.schema("analytics").unwrap();
+        assert_eq!(schema.table_names(), vec!["clicks", "events"]);
+        assert!(schema.table_exists("events"));
+
+        assert!(schema.deregister_table("events").unwrap().is_some());
+        assert!(!schema.table_exists("events"));
+        assert!(schema.deregister_table("events").unwrap().is_none());
+    }
+
+    #[test]
+    fn resolve_names() {
+        assert_eq!(
+            ResolvedTableReference::try_from_name("events").unwrap(),
+            ResolvedTableReference {
+                catalog: "qrust".to_owned(),
+                schema: "public".to_owned(),
+                table: "events".to_owned(),
+            }
+        );
+        assert_eq!(
+            ResolvedTableReference::try_from_name("analytics.events")
+                .unwrap()
+                .schema,
+            "analytics"
+        );
+        assert_eq!(
+            ResolvedTableReference::try_from_name("other.analytics.events")
+                .unwrap()
+                .catalog,
+            "other"
+        );
+        assert!(ResolvedTableReference::try_from_name("a.b.c.d").is_err());
+    }
+}
@@ -1,31 +1,45 @@
 use std::sync::Arc;
 
 use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
 
+use super::error::{QrustError, Result};
+use super::execution_context::ExecutionContext;
 use crate::logical_plan::aggregate::*;
+use crate::logical_plan::explain::*;
 use crate::logical_plan::expression::*;
+use crate::logical_plan::join::*;
+use crate::logical_plan::limit::*;
 use crate::logical_plan::projection::*;
 use crate::logical_plan::selection::*;
+use crate::logical_plan::sort::*;
+use crate::logical_plan::subquery_alias::*;
 use crate::logical_plan::LogicalPlan;
+use crate::physical_plan::BatchIterator;
+use crate::sql::unparser::plan_to_sql;
 
 pub struct DataFrame {
+    ctx: ExecutionContext,
     plan: Arc<dyn LogicalPlan>,
 }
 
 impl DataFrame {
-    pub fn new(plan: Arc<dyn LogicalPlan>) -> DataFrame {
-        DataFrame { plan }
+    pub fn new(ctx: ExecutionContext, plan: Arc<dyn LogicalPlan>) -> DataFrame {
+        DataFrame { ctx, plan }
     }
 
     pub fn select(&self, expr: Vec<Arc<dyn LogicalExpression>>) -> DataFrame {
+        let expr = expr.into_iter().map(|e| self.resolve(e)).collect();
         DataFrame {
+            ctx: self.ctx.clone(),
             plan: Arc::new(Projection::new(Arc::clone(&self.plan), expr)),
         }
     }
 
     pub fn filter(&self, expr: Arc<dyn LogicalExpression>) -> DataFrame {
         DataFrame {
-            plan: Arc::new(Selection::new(Arc::clone(&self.plan), expr)),
+            ctx: self.ctx.clone(),
+            plan: Arc::new(Selection::new(Arc::clone(&self.plan), self.resolve(expr))),
         }
     }
 
@@ -35,10 +49,95 @@ impl DataFrame {
         aggregate: Vec<Arc<AggregateExpression>>,
     ) -> DataFrame {
         DataFrame {
+            ctx: self.ctx.clone(),
             plan: Arc::new(Aggregate::new(Arc::clone(&self.plan), group_by, aggregate)),
         }
     }
 
+    /// Orders the rows by `expr`, the first expression the most significant, for example
+    /// `df.sort(vec![asc(col("a")), desc(col("b")).nulls_first()])`.
+    pub fn sort(&self, expr: Vec<Arc<SortExpression>>) -> DataFrame {
+        DataFrame {
+            ctx: self.ctx.clone(),
+            plan: Arc::new(Sort::new(Arc::clone(&self.plan), expr)),
+        }
+    }
+
+    /// Skips the first `skip` rows and keeps at most `fetch` of the rest, or all of them
+    /// if `fetch` is `None`. Inputs stop being read once enough rows are produced.
+    pub fn limit(&self, skip: usize, fetch: Option<usize>) -> DataFrame {
+        DataFrame {
+            ctx: self.ctx.clone(),
+            plan: Arc::new(Limit::new(Arc::clone(&self.plan), skip, fetch)),
+        }
+    }
+
+    /// Joins the rows of this dataframe with the rows of `right` whose columns
+    /// `right_keys` are equal to the columns `left_keys` of this one.
+    pub fn join(
+        &self,
+        right: &DataFrame,
+        join_type: JoinType,
+        left_keys: &[&str],
+        right_keys: &[&str],
+    ) -> Result<DataFrame> {
+        self.join_with_filter(right, join_type, left_keys, right_keys, None)
+    }
+
+    /// Like `join`, but pairs of rows only match if `filter` also holds for them. The
+    /// filter refers to the columns of the joined rows.
+    pub fn join_with_filter(
+        &self,
+        right: &DataFrame,
+        join_type: JoinType,
+        left_keys: &[&str],
+        right_keys: &[&str],
+        filter: Option<Arc<dyn LogicalExpression>>,
+    ) -> Result<DataFrame> {
+        if left_keys.len() != right_keys.len() {
+            return Err(QrustError::Plan(format!(
+                "Expected as many left keys as right keys, got {} and {}",
+                left_keys.len(),
+                right_keys.len()
+            )));
+        }
+        let on = left_keys
+            .iter()
+            .zip(right_keys)
+            .map(|(l, r)| (l.to_string(), r.to_string()))
+            .collect();
+        let join = Join::try_new(
+            Arc::clone(&self.plan),
+            Arc::clone(&right.plan),
+            join_type,
+            on,
+            filter,
+        )?;
+        Ok(DataFrame {
+            ctx: self.ctx.clone(),
+            plan: Arc::new(join),
+        })
+    }
+
+    /// Names the relation of this dataframe, which its columns can be qualified with,
+    /// e.g. `col("u.name")` after `alias("u")`. A join qualifies the names of the columns
+    /// that both of its inputs have with it.
+    pub fn alias(&self, alias: &str) -> DataFrame {
+        DataFrame {
+            ctx: self.ctx.clone(),
+            plan: Arc::new(SubqueryAlias::new(Arc::clone(&self.plan), alias.to_owned())),
+        }
+    }
+
+    /// Returns a dataframe describing how this one would be run. With `analyze` set,
+    /// collecting the result runs the query and reports what each operator did.
+    pub fn explain(&self, analyze: bool) -> DataFrame {
+        DataFrame {
+            ctx: self.ctx.clone(),
+            plan: Arc::new(Explain::new(Arc::clone(&self.plan), analyze)),
+        }
+    }
+
     pub fn schema(&self) -> Arc<Schema> {
         Arc::clone(&self.plan.schema())
     }
@@ -46,14 +145,40 @@ impl DataFrame {
     pub fn plan(&self) -> Arc<dyn LogicalPlan> {
         Arc::clone(&self.plan)
     }
+
+    /// Returns SQL that produces the same rows as this dataframe.
+    pub fn to_sql(&self) -> Result<String> {
+        plan_to_sql(&self.plan)
+    }
+
+    /// Rewrites the columns of `expr` that are qualified with their relation, e.g.
+    /// `u.name` after `alias("u")`, to the fields they refer to.
+    fn resolve(&self, expr: Arc<dyn LogicalExpression>) -> Arc<dyn LogicalExpression> {
+        resolve_columns(&expr, &self.plan.schema())
+    }
+
+    /// Runs the query described by this dataframe and returns the resulting batches.
+    pub fn collect(&self) -> Result<Vec<RecordBatch>> {
+        self.ctx.execute(self.plan())
+    }
+
+    /// Runs the query described by this dataframe, producing batches as the returned
+    /// iterator is advanced.
+    pub fn execute_stream(&self) -> Result<BatchIterator> {
+        self.ctx.execute_stream(self.plan())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::data_source::CsvReadOptions;
     use crate::core::data_type::ArrowType;
     use crate::core::execution_context::ExecutionContext;
     use crate::core::helper::*;
+    use crate::logical_plan::subquery_alias::qualifier;
+    use crate::test_util::{rows, sorted_rows, write_csv};
+    use arrow::array::Int64Array;
 
     fn generate_df() -> Arc<DataFrame> {
         let context = ExecutionContext::new();
@@ -152,4 +277,237 @@ mod tests {
                     Scan: test.csv, projection=None",
         );
     }
+
+    #[test]
+    fn data_frame_sort() {
+        let df = generate_df().sort(vec![
+            asc(col("column1")),
+            desc(add(col("column2"), col("column3"))).nulls_first(),
+        ]);
+        check_plan(
+            df,
+            "Sort: column1 ASC NULLS LAST, column2 + column3 DESC NULLS FIRST
+                    Scan: test.csv, projection=None",
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_csv(&dir, "input.csv", &["2,a", ",b", "1,c", ",d", "2,", "1,e"]);
+        let df = ExecutionContext::new()
+            .csv(
+                schema(vec![
+                    ("n", ArrowType::Int64Type, true),
+                    ("s", ArrowType::StringType, true),
+                ]),
+                &path,
+            )
+            .sort(vec![desc(col("n")), asc(col("s")).nulls_first()]);
+        assert_eq!(
+            rows(&df.collect().unwrap()),
+            ["2,", "2,a", "1,c", "1,e", ",b", ",d"]
+        );
+    }
+
+    #[test]
+    fn data_frame_limit() {
+        let df = generate_df()
+            .sort(vec![asc(col("column1"))])
+            .limit(1, Some(2));
+        check_plan(
+            df,
+            "Limit: skip=1, fetch=2
+                    Sort: column1 ASC NULLS LAST
+                      Scan: test.csv, projection=None",
+        );
+
+        // Previewing a file only reads the batches the preview needs, so the malformed
+        // last line is never decoded.
+        let dir = tempfile::tempdir().unwrap();
+        let mut lines: Vec<String> = (0..100_000).map(|i| format!("{},{}", i, i % 7)).collect();
+        lines.push("x,y".to_owned());
+        let lines: Vec<&str> = lines.iter().map(|l| l.as_str()).collect();
+        let path = write_csv(&dir, "big.csv", &lines);
+        let ctx = ExecutionContext::new();
+        ctx.register_csv(
+            "big",
+            &path,
+            CsvReadOptions::new()
+                .schema(schema(vec![
+                    ("a", ArrowType::Int64Type, false),
+                    ("b", ArrowType::Int64Type, false),
+                ]))
+                .batch_size(64),
+        )
+        .unwrap();
+        let big = ctx.table("big").unwrap();
+        assert!(big.collect().is_err());
+        let batches = big.limit(0, Some(10)).collect().unwrap();
+        let values: Vec<i64> = batches
+            .iter()
+            .flat_map(|b| {
+                let a = b.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+                a.values().to_vec()
+            })
+            .collect();
+        assert_eq!(values, (0..10).collect::<Vec<i64>>());
+
+        let batches = ctx
+            .sql("SELECT a FROM big WHERE b = 3 LIMIT 2 OFFSET 100")
+            .unwrap()
+            .collect()
+            .unwrap();
+        let values: Vec<i64> = batches
+            .iter()
+            .flat_map(|b| {
+                let a = b.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+                a.values().to_vec()
+            })
+            .collect();
+        assert_eq!(values, vec![703, 710]);
+
+        // A sort followed by a limit keeps only the rows it needs.
+        let small = write_csv(&dir, "small.csv", &["1,5", "2,3", "3,9", "4,1"]);
+        let top = ctx
+            .csv(
+                schema(vec![
+                    ("a", ArrowType::Int64Type, false),
+                    ("b", ArrowType::Int64Type, false),
+                ]),
+                &small,
+            )
+            .sort(vec![desc(col("b"))])
+            .limit(1, Some(2));
+        let physical = ctx.create_physical_plan(top.plan()).unwrap();
+        assert_eq!(
+            physical.format(),
+            format!(
+                "LimitExec: skip=1, fetch=2
+  TopKExec: fetch=3, #1 DESC NULLS LAST
+    ScanExec: {}, projection=None
+",
+                small
+            )
+        );
+        let batches = top.collect().unwrap();
+        let a = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(a.values(), &[1, 2]);
+    }
+
+    #[test]
+    fn data_frame_collect() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_csv(&dir, "input.csv", &["1,2,3", "4,5,6", "7,8,9"]);
+        let context = ExecutionContext::new();
+        let schema = schema(vec![
+            ("column1", ArrowType::Int64Type, false),
+            ("column2", ArrowType::Int64Type, false),
+            ("column3", ArrowType::Int64Type, false),
+        ]);
+
+        let batches = context
+            .csv(schema, &path)
+            .select(vec![col("column2")])
+            .collect()
+            .unwrap();
+
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].num_columns(), 1);
+        let c = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(c.values(), &[2, 5, 8]);
+    }
+
+    #[test]
+    fn qualified_columns_after_alias() {
+        let dir = tempfile::tempdir().unwrap();
+        let users = ExecutionContext::new()
+            .csv(
+                schema(vec![
+                    ("id", ArrowType::Int64Type, false),
+                    ("name", ArrowType::StringType, false),
+                ]),
+                &write_csv(&dir, "users.csv", &["1,ann", "2,bob", "3,cat"]),
+            )
+            .alias("u");
+        // The fields of an alias keep their names, and belong to the relation `u`.
+        let field = users.schema().field(1).clone();
+        assert_eq!(
+            (field.name().as_str(), qualifier(&field)),
+            ("name", Some("u"))
+        );
+
+        let df = users
+            .filter(gt(col("u.id"), lit(&1)))
+            .select(vec![col("u.name")]);
+        assert_eq!(df.schema().field(0).name(), "name");
+        assert_eq!(rows(&df.collect().unwrap()), vec!["bob", "cat"]);
+
+        // Aliasing again replaces the relation.
+        let df = users.alias("v").select(vec![col("v.id")]);
+        assert_eq!(rows(&df.collect().unwrap()), vec!["1", "2", "3"]);
+    }
+
+    #[test]
+    fn data_frame_join() {
+        let dir = tempfile::tempdir().unwrap();
+        let context = ExecutionContext::new();
+        let unaliased_users = context.csv(
+            schema(vec![
+                ("id", ArrowType::Int64Type, false),
+                ("name", ArrowType::StringType, false),
+            ]),
+            &write_csv(&dir, "users.csv", &["1,ann", "2,bob", "3,cat"]),
+        );
+        let unaliased_orders = context.csv(
+            schema(vec![
+                ("id", ArrowType::Int64Type, false),
+                ("user_id", ArrowType::Int64Type, false),
+            ]),
+            &write_csv(&dir, "orders.csv", &["10,1", "11,1", "12,3"]),
+        );
+        let users = unaliased_users.alias("u");
+        let orders = unaliased_orders.alias("o");
+
+        let df = users
+            .join(&orders, JoinType::Left, &["id"], &["user_id"])
+            .unwrap()
+            .filter(neq(col("u.name"), lit(&"cat")))
+            .select(vec![col("name"), col("o.id")]);
+        let fields = df.schema();
+        let fields = fields
+            .fields()
+            .iter()
+            .map(|f| (f.name().as_str(), f.is_nullable()))
+            .collect::<Vec<_>>();
+        assert_eq!(fields, vec![("name", false), ("o.id", true)]);
+        assert_eq!(
+            sorted_rows(&df.collect().unwrap()),
+            vec!["ann,10", "ann,11", "bob,"]
+        );
+
+        let err = users
+            .join(&orders, JoinType::Inner, &["id", "name"], &["user_id"])
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Planning error: Expected as many left keys as right keys, got 2 and 1"
+        );
+
+        // Without aliases, the columns both sides have can't be told apart.
+        let err = unaliased_users
+            .join(&unaliased_orders, JoinType::Inner, &["id"], &["user_id"])
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Planning error: Both sides of the join have a column named id"
+        );
+    }
 }
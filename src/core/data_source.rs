@@ -1,4 +1,3 @@
-use std::error::Error;
 use std::fs::File;
 use std::sync::Arc;
 
@@ -6,14 +5,118 @@ use arrow::csv;
 use arrow::datatypes::Schema;
 use arrow::record_batch::RecordBatch;
 
-pub trait DataSource {
+use super::error::{QrustError, Result};
+use crate::logical_plan::expression::LogicalExpression;
+use crate::optimizer::utils::{conjunction, exprlist_columns};
+use crate::physical_plan::planner::PhysicalPlanner;
+use crate::physical_plan::selection::filter_batch;
+use crate::physical_plan::BatchIterator;
+
+/// How completely a data source can apply a filter while it is being scanned.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterPushdown {
+    /// Every row the source returns satisfies the filter.
+    Exact,
+    /// The source uses the filter to skip some rows, but the filter still has to be
+    /// applied to the rows it returns.
+    Inexact,
+    /// The source ignores the filter.
+    Unsupported,
+}
+
+pub trait DataSource: Send + Sync {
     fn schema(&self) -> Arc<Schema>;
-    fn scan(&self, projection: Vec<String>) -> Result<Vec<RecordBatch>, Box<dyn Error>>;
+
+    /// Reports how the source would handle `filter` if it were passed to `scan`.
+    fn supports_filter(&self, _filter: &Arc<dyn LogicalExpression>) -> FilterPushdown {
+        FilterPushdown::Unsupported
+    }
+
+    /// Returns an iterator that reads the source one batch at a time. `filters` are
+    /// predicates the source reported as `Exact` or `Inexact` through `supports_filter`.
+    fn scan(
+        &self,
+        projection: Vec<String>,
+        filters: Vec<Arc<dyn LogicalExpression>>,
+    ) -> Result<BatchIterator>;
+
+    /// The columns the rows of the source are sorted on in ascending order, most
+    /// significant first. Rows with a null in one of them can appear anywhere.
+    fn output_ordering(&self) -> Vec<String> {
+        vec![]
+    }
+}
+
+/// Options that describe how a CSV file is laid out.
+#[derive(Clone)]
+pub struct CsvReadOptions {
+    /// Whether the first line of the file holds the column names.
+    pub has_header: bool,
+    pub delimiter: u8,
+    /// The schema of the file. When this is `None` the schema is inferred from the
+    /// first `schema_infer_max_records` rows.
+    pub schema: Option<Arc<Schema>>,
+    pub schema_infer_max_records: usize,
+    /// The number of rows decoded into each batch.
+    pub batch_size: usize,
+    /// The columns the rows of the file are known to be sorted on, in ascending order.
+    pub sort_order: Vec<String>,
+}
+
+impl Default for CsvReadOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CsvReadOptions {
+    pub fn new() -> CsvReadOptions {
+        CsvReadOptions {
+            has_header: false,
+            delimiter: b',',
+            schema: None,
+            schema_infer_max_records: 1000,
+            batch_size: 1024,
+            sort_order: vec![],
+        }
+    }
+
+    pub fn has_header(self, has_header: bool) -> CsvReadOptions {
+        CsvReadOptions { has_header, ..self }
+    }
+
+    pub fn delimiter(self, delimiter: u8) -> CsvReadOptions {
+        CsvReadOptions { delimiter, ..self }
+    }
+
+    pub fn schema(self, schema: Arc<Schema>) -> CsvReadOptions {
+        CsvReadOptions {
+            schema: Some(schema),
+            ..self
+        }
+    }
+
+    pub fn batch_size(self, batch_size: usize) -> CsvReadOptions {
+        CsvReadOptions { batch_size, ..self }
+    }
+
+    /// Declares that the rows of the file are sorted on `columns` in ascending order,
+    /// most significant first. The order isn't checked when the file is read.
+    pub fn sorted_by(self, columns: &[&str]) -> CsvReadOptions {
+        CsvReadOptions {
+            sort_order: columns.iter().map(|c| c.to_string()).collect(),
+            ..self
+        }
+    }
 }
 
 pub struct CsvDataSource {
     schema: Arc<Schema>,
     path: String,
+    has_header: bool,
+    delimiter: u8,
+    batch_size: usize,
+    sort_order: Vec<String>,
 }
 
 impl DataSource for CsvDataSource {
@@ -21,50 +124,164 @@ impl DataSource for CsvDataSource {
         Arc::clone(&self.schema)
     }
 
-    // :TODO: Return an iterator instead of parsing the entire file into memory.
-    fn scan(&self, projection: Vec<String>) -> Result<Vec<RecordBatch>, Box<dyn Error>> {
-        // Convert the projection string input into a Vec<usize>, where each element
-        // corresponds to the index of the relevant column in the schema.
-        let proj: Vec<usize> = self
-            .schema
-            .fields()
-            .iter()
-            .enumerate()
-            .filter(|(_, f)| projection.contains(f.name()))
-            .map(|(i, _)| i)
-            .collect();
+    /// Any filter that only uses expressions Qrust can evaluate is applied exactly
+    /// while the file is decoded.
+    fn supports_filter(&self, filter: &Arc<dyn LogicalExpression>) -> FilterPushdown {
+        match PhysicalPlanner::new().create_physical_expression(filter, &self.schema) {
+            Ok(_) => FilterPushdown::Exact,
+            Err(_) => FilterPushdown::Unsupported,
+        }
+    }
+
+    fn scan(
+        &self,
+        projection: Vec<String>,
+        filters: Vec<Arc<dyn LogicalExpression>>,
+    ) -> Result<BatchIterator> {
+        // Columns used by the filters have to be decoded too, even if they are dropped
+        // once the rows have been filtered.
+        let mut read = projection.clone();
+        if !read.is_empty() {
+            for column in exprlist_columns(&filters) {
+                if !read.contains(&column) {
+                    read.push(column);
+                }
+            }
+        }
 
-        // Build the CSV reader and iterate over the resulting record batches.
+        // Convert the columns to read into a Vec<usize>, where each element
+        // corresponds to the index of the relevant column in the schema. An empty
+        // projection means every column should be read.
+        let proj: Option<Vec<usize>> = if read.is_empty() {
+            None
+        } else {
+            Some(
+                self.schema
+                    .fields()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, f)| read.contains(f.name()))
+                    .map(|(i, _)| i)
+                    .collect(),
+            )
+        };
+        let read_schema = match &proj {
+            Some(proj) => Arc::new(Schema::new(
+                proj.iter().map(|i| self.schema.field(*i).clone()).collect(),
+            )),
+            None => Arc::clone(&self.schema),
+        };
+
+        // Build the CSV reader. Batches are decoded as the caller pulls them.
         let file = File::open(self.path.clone())?;
-        let header = false;
         let csv = csv::Reader::new(
             file,
             Arc::clone(&self.schema),
-            header,
-            None,
-            1024,
+            self.has_header,
+            Some(self.delimiter),
+            self.batch_size,
             None,
-            Some(proj),
+            proj,
         );
 
-        let mut out: Vec<RecordBatch> = vec![];
-        for result in csv {
-            out.push(result?);
-        }
+        let batches = csv.map(|batch| batch.map_err(QrustError::from));
+
+        let predicate = match conjunction(filters) {
+            Some(predicate) => {
+                PhysicalPlanner::new().create_physical_expression(&predicate, &read_schema)?
+            }
+            None => return Ok(Box::new(batches)),
+        };
+
+        // Drop the columns that were only read to evaluate the filters.
+        let output: Vec<usize> = read_schema
+            .fields()
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| projection.is_empty() || projection.contains(f.name()))
+            .map(|(i, _)| i)
+            .collect();
+        let output_schema = Arc::new(Schema::new(
+            output
+                .iter()
+                .map(|i| read_schema.field(*i).clone())
+                .collect(),
+        ));
+
+        Ok(Box::new(batches.map(move |batch| {
+            let batch = filter_batch(&batch?, &predicate)?;
+            let columns = output
+                .iter()
+                .map(|i| Arc::clone(batch.column(*i)))
+                .collect();
+            Ok(RecordBatch::try_new(Arc::clone(&output_schema), columns)?)
+        })))
+    }
 
-        Ok(out)
+    fn output_ordering(&self) -> Vec<String> {
+        self.sort_order.clone()
     }
 }
 
 impl CsvDataSource {
+    /// Creates a source for a headerless, comma separated file with the given schema.
     pub fn new(schema: Arc<Schema>, path: String) -> CsvDataSource {
-        CsvDataSource { schema, path }
+        let options = CsvReadOptions::new();
+        CsvDataSource {
+            schema,
+            path,
+            has_header: options.has_header,
+            delimiter: options.delimiter,
+            batch_size: options.batch_size,
+            sort_order: options.sort_order,
+        }
+    }
+
+    /// Creates a source for the file at `path`, inferring its schema from the file
+    /// unless `options` provides one.
+    pub fn try_new(path: &str, options: CsvReadOptions) -> Result<CsvDataSource> {
+        let schema = match options.schema {
+            Some(schema) => schema,
+            None => {
+                let mut file = File::open(path)?;
+                let (schema, _) = csv::reader::infer_file_schema(
+                    &mut file,
+                    options.delimiter,
+                    Some(options.schema_infer_max_records),
+                    options.has_header,
+                )?;
+                Arc::new(schema)
+            }
+        };
+        if let Some(column) = options
+            .sort_order
+            .iter()
+            .find(|c| schema.field_with_name(c).is_err())
+        {
+            return Err(QrustError::Plan(format!(
+                "Can't sort {} by {}, which is not one of its columns",
+                path, column
+            )));
+        }
+        Ok(CsvDataSource {
+            schema,
+            path: path.to_owned(),
+            has_header: options.has_header,
+            delimiter: options.delimiter,
+            batch_size: options.batch_size,
+            sort_order: options.sort_order,
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::write_csv;
     use arrow::{
         array::Int64Array,
         datatypes::{DataType, Field, Schema},
@@ -86,18 +303,19 @@ mod tests {
             Field::new("column3", DataType::Int64, false),
         ]);
 
-        let source = CsvDataSource {
-            schema: Arc::new(schema),
-            path: dir
-                .path()
+        let source = CsvDataSource::new(
+            Arc::new(schema),
+            dir.path()
                 .join("input.csv")
                 .into_os_string()
                 .into_string()
                 .unwrap(),
-        };
+        );
 
         let batches = source
-            .scan(vec!["column1".to_string(), "column3".to_string()])
+            .scan(vec!["column1".to_string(), "column3".to_string()], vec![])
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
             .unwrap();
         assert_eq!(batches.len(), 1);
 
@@ -122,4 +340,117 @@ mod tests {
             .unwrap();
         assert_eq!(c2.values(), &[654, 987]);
     }
+
+    #[test]
+    fn csv_source_streams_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        let rows: Vec<String> = (0..3000).map(|i| format!("{},{}", i, i * 2)).collect();
+        let rows: Vec<&str> = rows.iter().map(|r| r.as_str()).collect();
+        let path = write_csv(&dir, "input.csv", &rows);
+
+        let schema = Schema::new(vec![
+            Field::new("column1", DataType::Int64, false),
+            Field::new("column2", DataType::Int64, false),
+        ]);
+        let source = CsvDataSource::new(Arc::new(schema), path);
+
+        let mut batches = source.scan(vec![], vec![]).unwrap();
+        let first = batches.next().unwrap().unwrap();
+        assert_eq!(first.num_columns(), 2);
+        assert_eq!(first.num_rows(), 1024);
+
+        let remaining: Vec<usize> = batches.map(|b| b.unwrap().num_rows()).collect();
+        assert_eq!(remaining, vec![1024, 952]);
+    }
+
+    #[test]
+    fn csv_source_filters() {
+        use crate::core::helper::*;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_csv(&dir, "input.csv", &["1,a,10", "2,b,20", "3,a,30"]);
+        let schema = Schema::new(vec![
+            Field::new("column1", DataType::Int64, false),
+            Field::new("column2", DataType::Utf8, false),
+            Field::new("column3", DataType::Int64, false),
+        ]);
+        let source = CsvDataSource::new(Arc::new(schema), path);
+
+        let filter: Arc<dyn LogicalExpression> = eq(col("column2"), lit(&"a"));
+        assert_eq!(source.supports_filter(&filter), FilterPushdown::Exact);
+        assert_eq!(
+            source.supports_filter(&(sum(col("column1")) as Arc<dyn LogicalExpression>)),
+            FilterPushdown::Unsupported
+        );
+
+        // The filter column isn't part of the projection, so it is read and then dropped.
+        let batches = source
+            .scan(
+                vec!["column3".to_string()],
+                vec![filter, gt(col("column1"), lit(&1))],
+            )
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].num_columns(), 1);
+        assert_eq!(batches[0].schema().field(0).name(), "column3");
+        let c = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(c.values(), &[30]);
+    }
+
+    #[test]
+    fn csv_source_with_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_csv(&dir, "input.csv", &["id|name", "1|a", "2|b", "3|c"]);
+
+        let options = CsvReadOptions::new()
+            .has_header(true)
+            .delimiter(b'|')
+            .batch_size(2);
+        let source = CsvDataSource::try_new(&path, options).unwrap();
+        assert_eq!(
+            source.schema().as_ref(),
+            &Schema::new(vec![
+                Field::new("id", DataType::Int64, false),
+                Field::new("name", DataType::Utf8, false),
+            ])
+        );
+
+        let batches = source
+            .scan(vec![], vec![])
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>(),
+            vec![2, 1]
+        );
+        let ids = batches[1]
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(ids.values(), &[3]);
+
+        assert!(CsvDataSource::try_new("missing.csv", CsvReadOptions::new()).is_err());
+
+        let options = CsvReadOptions::new().has_header(true).delimiter(b'|');
+        let source = CsvDataSource::try_new(&path, options.clone().sorted_by(&["id"])).unwrap();
+        assert_eq!(source.output_ordering(), vec!["id"]);
+        let err = CsvDataSource::try_new(&path, options.sorted_by(&["ts"]))
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Planning error: Can't sort {} by ts, which is not one of its columns",
+                path
+            )
+        );
+    }
 }
@@ -0,0 +1,73 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::result;
+
+use arrow::error::ArrowError;
+
+/// Result type used throughout Qrust.
+pub type Result<T> = result::Result<T, QrustError>;
+
+/// Errors that can occur while planning or running a query.
+#[derive(Debug)]
+pub enum QrustError {
+    /// An error raised by the underlying Arrow library.
+    ArrowError(ArrowError),
+    /// An error raised while reading from or writing to disk.
+    IoError(io::Error),
+    /// The SQL text could not be parsed. Lines and columns start at 1.
+    Parse {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+    /// The query could not be turned into a valid plan.
+    Plan(String),
+    /// The query failed while it was running.
+    Execution(String),
+    /// The query uses a feature that is not supported yet.
+    NotImplemented(String),
+}
+
+impl fmt::Display for QrustError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QrustError::ArrowError(e) => write!(f, "Arrow error: {}", e),
+            QrustError::IoError(e) => write!(f, "IO error: {}", e),
+            QrustError::Parse {
+                message,
+                line,
+                column,
+            } => write!(
+                f,
+                "Parse error at line {}, column {}: {}",
+                line, column, message
+            ),
+            QrustError::Plan(s) => write!(f, "Planning error: {}", s),
+            QrustError::Execution(s) => write!(f, "Execution error: {}", s),
+            QrustError::NotImplemented(s) => write!(f, "Not implemented: {}", s),
+        }
+    }
+}
+
+impl Error for QrustError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            QrustError::ArrowError(e) => Some(e),
+            QrustError::IoError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ArrowError> for QrustError {
+    fn from(e: ArrowError) -> QrustError {
+        QrustError::ArrowError(e)
+    }
+}
+
+impl From<io::Error> for QrustError {
+    fn from(e: io::Error) -> QrustError {
+        QrustError::IoError(e)
+    }
+}
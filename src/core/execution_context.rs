@@ -1,24 +1,856 @@
 use arrow::datatypes::Schema;
-use std::sync::Arc;
+use arrow::record_batch::RecordBatch;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
 
+use super::catalog::{
+    CatalogProvider, MemoryCatalogProvider, MemorySchemaProvider, ResolvedTableReference,
+    SchemaProvider, DEFAULT_CATALOG, DEFAULT_SCHEMA,
+};
 use super::data_frame::DataFrame;
-use super::data_source::CsvDataSource;
+use super::data_source::{CsvDataSource, CsvReadOptions, DataSource};
+use super::error::{QrustError, Result};
+use super::information_schema::{InformationSchemaProvider, INFORMATION_SCHEMA};
+use super::prepared_statement::PreparedStatement;
+use crate::logical_plan::ddl::{CreateExternalTable, DropTable};
+use crate::logical_plan::empty_relation::EmptyRelation;
 use crate::logical_plan::scan::*;
+use crate::logical_plan::LogicalPlan;
+use crate::optimizer::decorrelate_subqueries::DecorrelateSubqueries;
+use crate::optimizer::predicate_pushdown::PredicatePushdown;
+use crate::optimizer::projection_pushdown::ProjectionPushdown;
+use crate::optimizer::simplify_expressions::SimplifyExpressions;
+use crate::optimizer::sort_limit_fusion::SortLimitFusion;
+use crate::optimizer::{Optimizer, OptimizerRule};
+use crate::physical_plan::planner::PhysicalPlanner;
+use crate::physical_plan::sort::DEFAULT_SORT_MEMORY_LIMIT;
+use crate::physical_plan::{collect, BatchIterator, PhysicalPlan};
+use crate::sql::parser::Parser;
+use crate::sql::planner::{ContextProvider, SqlPlanner};
 
-pub struct ExecutionContext {}
+/// State shared between a context and every dataframe created from it.
+struct ExecutionContextState {
+    optimizer_rules: Vec<Arc<dyn OptimizerRule>>,
+    catalogs: HashMap<String, Arc<dyn CatalogProvider>>,
+    sort_memory_limit: usize,
+}
+
+#[derive(Clone)]
+pub struct ExecutionContext {
+    state: Arc<RwLock<ExecutionContextState>>,
+}
+
+impl Default for ExecutionContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl ExecutionContext {
     pub fn new() -> ExecutionContext {
-        ExecutionContext {}
+        let catalog = MemoryCatalogProvider::new();
+        catalog
+            .register_schema(DEFAULT_SCHEMA, Arc::new(MemorySchemaProvider::new()))
+            .unwrap();
+        let mut catalogs: HashMap<String, Arc<dyn CatalogProvider>> = HashMap::new();
+        catalogs.insert(DEFAULT_CATALOG.to_owned(), Arc::new(catalog));
+
+        ExecutionContext {
+            state: Arc::new(RwLock::new(ExecutionContextState {
+                optimizer_rules: ExecutionContext::default_optimizer_rules(),
+                catalogs,
+                sort_memory_limit: DEFAULT_SORT_MEMORY_LIMIT,
+            })),
+        }
+    }
+
+    /// The rules every new context starts with, in the order they are applied.
+    pub fn default_optimizer_rules() -> Vec<Arc<dyn OptimizerRule>> {
+        vec![
+            Arc::new(DecorrelateSubqueries::new()),
+            Arc::new(SimplifyExpressions::new()),
+            Arc::new(PredicatePushdown::new()),
+            Arc::new(ProjectionPushdown::new()),
+            Arc::new(SortLimitFusion::new()),
+        ]
+    }
+
+    /// Registers an additional rule, applied after the rules already registered.
+    pub fn add_optimizer_rule(&self, rule: Arc<dyn OptimizerRule>) {
+        self.state.write().unwrap().optimizer_rules.push(rule);
+    }
+
+    /// Returns the rules used when optimizing plans, in the order they are applied.
+    pub fn optimizer_rules(&self) -> Vec<Arc<dyn OptimizerRule>> {
+        self.state.read().unwrap().optimizer_rules.clone()
+    }
+
+    /// Sets the bytes of input each sort may buffer before it spills sorted runs to
+    /// temporary files.
+    pub fn set_sort_memory_limit(&self, limit: usize) {
+        self.state.write().unwrap().sort_memory_limit = limit;
     }
 
     pub fn csv(&self, schema: Arc<Schema>, path: &str) -> DataFrame {
         let source = CsvDataSource::new(Arc::clone(&schema), String::from(path));
-        DataFrame::new(Arc::new(Scan::new(
-            String::from(path),
-            Arc::clone(&schema),
-            Box::new(source),
-            vec![],
-        )))
+        DataFrame::new(
+            self.clone(),
+            Arc::new(Scan::new(
+                String::from(path),
+                Arc::clone(&schema),
+                Arc::new(source),
+                vec![],
+            )),
+        )
+    }
+
+    /// Adds a catalog, returning the catalog it replaced if there was one.
+    pub fn register_catalog(
+        &self,
+        name: &str,
+        catalog: Arc<dyn CatalogProvider>,
+    ) -> Option<Arc<dyn CatalogProvider>> {
+        self.state
+            .write()
+            .unwrap()
+            .catalogs
+            .insert(name.to_owned(), catalog)
+    }
+
+    pub fn catalog(&self, name: &str) -> Option<Arc<dyn CatalogProvider>> {
+        self.state.read().unwrap().catalogs.get(name).cloned()
+    }
+
+    /// Returns the names of every registered catalog, in sorted order.
+    pub fn catalog_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .state
+            .read()
+            .unwrap()
+            .catalogs
+            .keys()
+            .cloned()
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Makes `source` available as a table. `name` may be qualified with a schema and a
+    /// catalog, e.g. `analytics.events`; schemas that don't exist yet are created. Returns
+    /// the table that was registered under the name before, if any.
+    pub fn register_table(
+        &self,
+        name: &str,
+        source: Arc<dyn DataSource>,
+    ) -> Result<Option<Arc<dyn DataSource>>> {
+        let reference = ResolvedTableReference::try_from_name(name)?;
+        if reference.schema == INFORMATION_SCHEMA {
+            return Err(QrustError::Plan(format!(
+                "Tables can't be registered in {}",
+                INFORMATION_SCHEMA
+            )));
+        }
+        let catalog = self
+            .catalog(&reference.catalog)
+            .ok_or_else(|| QrustError::Plan(format!("Catalog not found: {}", reference.catalog)))?;
+        let schema = match catalog.schema(&reference.schema) {
+            Some(schema) => schema,
+            None => {
+                let schema: Arc<dyn SchemaProvider> = Arc::new(MemorySchemaProvider::new());
+                catalog.register_schema(&reference.schema, Arc::clone(&schema))?;
+                schema
+            }
+        };
+        schema.register_table(&reference.table, source)
+    }
+
+    /// Registers the CSV file at `path` as a table named `name`.
+    pub fn register_csv(&self, name: &str, path: &str, options: CsvReadOptions) -> Result<()> {
+        let source = CsvDataSource::try_new(path, options)?;
+        self.register_table(name, Arc::new(source))?;
+        Ok(())
+    }
+
+    /// Removes a table, returning it if it was registered.
+    pub fn deregister_table(&self, name: &str) -> Result<Option<Arc<dyn DataSource>>> {
+        let reference = ResolvedTableReference::try_from_name(name)?;
+        match self.schema_provider(&reference.catalog, &reference.schema) {
+            Some(schema) => schema.deregister_table(&reference.table),
+            None => Ok(None),
+        }
+    }
+
+    /// Looks up a registered table, e.g. `events` or `analytics.events`.
+    pub fn table_source(&self, name: &str) -> Result<Arc<dyn DataSource>> {
+        let reference = ResolvedTableReference::try_from_name(name)?;
+        self.schema_provider(&reference.catalog, &reference.schema)
+            .and_then(|s| s.table(&reference.table))
+            .ok_or_else(|| QrustError::Plan(format!("Table not found: {}", name)))
+    }
+
+    /// Looks up a schema of a catalog. Every catalog has an `information_schema` that
+    /// describes the tables of all catalogs.
+    fn schema_provider(&self, catalog: &str, schema: &str) -> Option<Arc<dyn SchemaProvider>> {
+        let catalog = self.catalog(catalog)?;
+        if schema == INFORMATION_SCHEMA {
+            let catalogs = self
+                .state
+                .read()
+                .unwrap()
+                .catalogs
+                .iter()
+                .map(|(name, catalog)| (name.clone(), Arc::clone(catalog)))
+                .collect();
+            return Some(Arc::new(InformationSchemaProvider::new(catalogs)));
+        }
+        catalog.schema(schema)
+    }
+
+    /// Creates a dataframe that reads a registered table.
+    pub fn table(&self, name: &str) -> Result<DataFrame> {
+        let source = self.table_source(name)?;
+        Ok(DataFrame::new(
+            self.clone(),
+            Arc::new(Scan::new(name.to_owned(), source.schema(), source, vec![])),
+        ))
+    }
+
+    /// Plans a SQL query against the tables registered with this context. Statements
+    /// that change the catalog, like `CREATE EXTERNAL TABLE`, are run right away and
+    /// return an empty dataframe.
+    pub fn sql(&self, sql: &str) -> Result<DataFrame> {
+        let plan = self.sql_to_plan(sql)?;
+        let plan = self.execute_ddl(plan)?;
+        Ok(DataFrame::new(self.clone(), plan))
+    }
+
+    /// Plans and optimizes a SQL query once, so that it can be run many times. The query
+    /// may use positional parameters like `$1` or named parameters like `$id`, whose
+    /// values are passed each time the statement is executed.
+    pub fn prepare(&self, sql: &str) -> Result<PreparedStatement> {
+        let plan = self.sql_to_plan(sql)?;
+        if plan.as_any().is::<CreateExternalTable>() || plan.as_any().is::<DropTable>() {
+            return Err(QrustError::Plan("Only queries can be prepared".to_owned()));
+        }
+        PreparedStatement::try_new(self, plan)
+    }
+
+    fn sql_to_plan(&self, sql: &str) -> Result<Arc<dyn LogicalPlan>> {
+        let statements = Parser::parse_sql(sql)?;
+        if statements.len() != 1 {
+            return Err(QrustError::Plan(format!(
+                "Expected exactly one statement but found {}",
+                statements.len()
+            )));
+        }
+        SqlPlanner::new(self).statement_to_plan(&statements[0])
+    }
+
+    /// Runs `plan` if it changes the catalog, returning a plan for its (empty) result.
+    /// Any other plan is returned unchanged.
+    fn execute_ddl(&self, plan: Arc<dyn LogicalPlan>) -> Result<Arc<dyn LogicalPlan>> {
+        let any = plan.as_any();
+
+        if let Some(create) = any.downcast_ref::<CreateExternalTable>() {
+            if self.table_source(create.name()).is_ok() {
+                if create.if_not_exists() {
+                    return Ok(Arc::new(EmptyRelation::new(plan.schema())));
+                }
+                return Err(QrustError::Plan(format!(
+                    "Table {} already exists",
+                    create.name()
+                )));
+            }
+            let options = CsvReadOptions::new()
+                .schema(create.table_schema())
+                .has_header(create.has_header())
+                .delimiter(create.delimiter());
+            self.register_csv(create.name(), create.location(), options)?;
+            return Ok(Arc::new(EmptyRelation::new(plan.schema())));
+        }
+
+        if let Some(drop) = any.downcast_ref::<DropTable>() {
+            if self.deregister_table(drop.name())?.is_none() && !drop.if_exists() {
+                return Err(QrustError::Plan(format!(
+                    "Table not found: {}",
+                    drop.name()
+                )));
+            }
+            return Ok(Arc::new(EmptyRelation::new(plan.schema())));
+        }
+
+        Ok(plan)
+    }
+
+    /// Rewrites a logical plan into an equivalent plan that is cheaper to run.
+    pub fn optimize(&self, plan: Arc<dyn LogicalPlan>) -> Result<Arc<dyn LogicalPlan>> {
+        self.optimize_with_observer(plan, |_, _| {})
+    }
+
+    /// Optimizes a logical plan, calling `observer` with the rule and the resulting plan
+    /// every time a rule changes the plan.
+    pub fn optimize_with_observer<F>(
+        &self,
+        plan: Arc<dyn LogicalPlan>,
+        observer: F,
+    ) -> Result<Arc<dyn LogicalPlan>>
+    where
+        F: FnMut(&dyn OptimizerRule, &Arc<dyn LogicalPlan>),
+    {
+        Optimizer::new(self.optimizer_rules()).optimize(plan, observer)
+    }
+
+    /// Optimizes a logical plan and converts it into a physical plan that can be executed.
+    pub fn create_physical_plan(
+        &self,
+        plan: Arc<dyn LogicalPlan>,
+    ) -> Result<Arc<dyn PhysicalPlan>> {
+        let plan = self.optimize(plan)?;
+        self.physical_planner().create_physical_plan(&plan)
+    }
+
+    /// Returns a physical planner with the settings of this context.
+    pub(crate) fn physical_planner(&self) -> PhysicalPlanner {
+        let sort_memory_limit = self.state.read().unwrap().sort_memory_limit;
+        PhysicalPlanner::new().with_sort_memory_limit(sort_memory_limit)
+    }
+
+    /// Plans and runs a logical plan, returning every batch it produces.
+    pub fn execute(&self, plan: Arc<dyn LogicalPlan>) -> Result<Vec<RecordBatch>> {
+        collect(self.create_physical_plan(plan)?)
+    }
+
+    /// Plans a logical plan and returns an iterator that runs it one batch at a time.
+    pub fn execute_stream(&self, plan: Arc<dyn LogicalPlan>) -> Result<BatchIterator> {
+        self.create_physical_plan(plan)?.execute()
+    }
+}
+
+impl ContextProvider for ExecutionContext {
+    fn get_table_source(&self, name: &[String]) -> Option<Arc<dyn DataSource>> {
+        let reference = ResolvedTableReference::try_from_parts(name).ok()?;
+        self.schema_provider(&reference.catalog, &reference.schema)?
+            .table(&reference.table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data_type::ArrowType;
+    use crate::core::helper::*;
+    use arrow::array::Array;
+
+    /// Leaves the plan untouched but counts how often it runs.
+    struct CountingRule {
+        runs: RwLock<usize>,
+    }
+
+    impl OptimizerRule for CountingRule {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn optimize(&self, plan: Arc<dyn LogicalPlan>) -> Result<Arc<dyn LogicalPlan>> {
+            *self.runs.write().unwrap() += 1;
+            Ok(plan)
+        }
+    }
+
+    #[test]
+    fn custom_optimizer_rule() {
+        let ctx = ExecutionContext::new();
+        let df = ctx.csv(
+            schema(vec![
+                ("column1", ArrowType::Int64Type, false),
+                ("column2", ArrowType::Int64Type, false),
+            ]),
+            "test.csv",
+        );
+        let rule = Arc::new(CountingRule {
+            runs: RwLock::new(0),
+        });
+        // Rules registered after a dataframe was created still apply to it.
+        ctx.add_optimizer_rule(Arc::clone(&rule) as Arc<dyn OptimizerRule>);
+        assert_eq!(ctx.optimizer_rules().len(), 6);
+
+        let mut trace = vec![];
+        let plan = ctx
+            .optimize_with_observer(df.select(vec![col("column2")]).plan(), |rule, _| {
+                trace.push(rule.name().to_owned())
+            })
+            .unwrap();
+
+        assert_eq!(
+            plan.format(),
+            "Projection: column2\n  Scan: test.csv, projection=[\"column2\"]\n"
+        );
+        assert_eq!(trace, vec!["projection_pushdown"]);
+        // One pass changes the plan and a second pass confirms nothing else changes.
+        assert_eq!(*rule.runs.read().unwrap(), 2);
+    }
+
+    #[test]
+    fn sql_query() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = crate::test_util::write_csv(
+            &dir,
+            "sales.csv",
+            &["north,10", "south,5", "north,7", "east,1"],
+        );
+        let ctx = ExecutionContext::new();
+        let schema = schema(vec![
+            ("region", ArrowType::StringType, false),
+            ("amount", ArrowType::Int64Type, false),
+        ]);
+        ctx.register_table("sales", Arc::new(CsvDataSource::new(schema, path)))
+            .unwrap();
+
+        let df = ctx
+            .sql("SELECT region, sum(amount) AS total FROM sales WHERE amount > 1 GROUP BY region")
+            .unwrap();
+        assert_eq!(
+            df.plan().format(),
+            "Projection: region, sum(amount) AS total
+  Aggregate: groupExpr=region, aggregateExpr=sum(amount)
+    Filter: amount>1
+      Scan: sales, projection=None
+"
+        );
+
+        let batches = df.collect().unwrap();
+        assert_eq!(batches[0].schema().field(1).name(), "total");
+        let totals = batches[0]
+            .column(1)
+            .as_any()
+            .downcast_ref::<arrow::array::Int64Array>()
+            .unwrap();
+        assert_eq!(totals.values(), &[17, 5]);
+
+        assert!(ctx
+            .sql("SELECT region FROM sales; SELECT amount FROM sales")
+            .is_err());
+    }
+
+    #[test]
+    fn catalog_tables() {
+        let dir = tempfile::tempdir().unwrap();
+        let path =
+            crate::test_util::write_csv(&dir, "events.csv", &["user,clicks", "a,3", "b,5", "a,4"]);
+        let ctx = ExecutionContext::new();
+        ctx.register_csv(
+            "analytics.events",
+            &path,
+            CsvReadOptions::new().has_header(true),
+        )
+        .unwrap();
+
+        assert_eq!(ctx.catalog_names(), vec!["qrust"]);
+        let catalog = ctx.catalog("qrust").unwrap();
+        assert_eq!(catalog.schema_names(), vec!["analytics", "public"]);
+        assert_eq!(
+            catalog.schema("analytics").unwrap().table_names(),
+            vec!["events"]
+        );
+
+        // The table can be referred to with any level of qualification.
+        for name in ["analytics.events", "qrust.analytics.events"] {
+            let df = ctx
+                .sql(&format!(
+                    "SELECT user, sum(clicks) FROM {} GROUP BY user",
+                    name
+                ))
+                .unwrap();
+            assert_eq!(df.collect().unwrap()[0].num_rows(), 2);
+        }
+        let df = ctx
+            .table("analytics.events")
+            .unwrap()
+            .filter(gt(col("clicks"), lit(&3)));
+        assert_eq!(df.collect().unwrap()[0].num_rows(), 2);
+
+        assert!(ctx.table("events").is_err());
+        assert_eq!(
+            ctx.sql("SELECT user FROM events")
+                .err()
+                .unwrap()
+                .to_string(),
+            "Planning error: Table not found: events"
+        );
+
+        assert!(ctx.deregister_table("analytics.events").unwrap().is_some());
+        assert!(ctx.deregister_table("analytics.events").unwrap().is_none());
+        assert!(ctx.table("analytics.events").is_err());
+        assert!(ctx
+            .register_table(
+                "missing.analytics.events",
+                Arc::new(CsvDataSource::new(Arc::new(Schema::empty()), path))
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn create_and_drop_tables() {
+        let dir = tempfile::tempdir().unwrap();
+        let path =
+            crate::test_util::write_csv(&dir, "events.csv", &["user;clicks", "a;3", "b;5", "a;4"]);
+        let ctx = ExecutionContext::new();
+
+        let df = ctx
+            .sql(&format!(
+                "CREATE EXTERNAL TABLE analytics.events (user VARCHAR, clicks BIGINT NOT NULL) \
+                 STORED AS CSV WITH HEADER ROW DELIMITER ';' LOCATION '{}'",
+                path
+            ))
+            .unwrap();
+        assert!(df.collect().unwrap().is_empty());
+        assert_eq!(
+            ctx.table("analytics.events").unwrap().schema(),
+            schema(vec![
+                ("user", ArrowType::StringType, true),
+                ("clicks", ArrowType::Int64Type, false),
+            ])
+        );
+
+        let batches = ctx
+            .sql("SELECT sum(clicks) AS total FROM analytics.events WHERE user = 'a'")
+            .unwrap()
+            .collect()
+            .unwrap();
+        let total = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<arrow::array::Int64Array>()
+            .unwrap();
+        assert_eq!(total.value(0), 7);
+
+        let create = format!(
+            "CREATE EXTERNAL TABLE analytics.events (a BIGINT) STORED AS CSV LOCATION '{}'",
+            path
+        );
+        assert_eq!(
+            ctx.sql(&create).err().unwrap().to_string(),
+            "Planning error: Table analytics.events already exists"
+        );
+        ctx.sql(&create.replace("TABLE", "TABLE IF NOT EXISTS"))
+            .unwrap();
+        assert_eq!(
+            ctx.table("analytics.events")
+                .unwrap()
+                .schema()
+                .fields()
+                .len(),
+            2
+        );
+
+        ctx.sql("DROP TABLE analytics.events").unwrap();
+        assert!(ctx.table("analytics.events").is_err());
+        assert_eq!(
+            ctx.sql("DROP TABLE analytics.events")
+                .err()
+                .unwrap()
+                .to_string(),
+            "Planning error: Table not found: analytics.events"
+        );
+        ctx.sql("DROP TABLE IF EXISTS analytics.events").unwrap();
+
+        assert_eq!(
+            ctx.sql("CREATE EXTERNAL TABLE t (a BIGINT) STORED AS PARQUET LOCATION 'x'")
+                .err()
+                .unwrap()
+                .to_string(),
+            "Not implemented: Unsupported file type PARQUET"
+        );
+    }
+
+    fn plan_rows(batches: &[RecordBatch]) -> Vec<(String, String)> {
+        let batch = &batches[0];
+        let column = |i: usize| {
+            batch
+                .column(i)
+                .as_any()
+                .downcast_ref::<arrow::array::StringArray>()
+                .unwrap()
+        };
+        let (types, plans) = (column(0), column(1));
+        (0..batch.num_rows())
+            .map(|i| (types.value(i).to_owned(), plans.value(i).to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn explain_query() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = crate::test_util::write_csv(&dir, "sales.csv", &["a,1", "b,2", "a,3"]);
+        let ctx = ExecutionContext::new();
+        ctx.register_csv(
+            "sales",
+            &path,
+            CsvReadOptions::new().schema(schema(vec![
+                ("region", ArrowType::StringType, false),
+                ("amount", ArrowType::Int64Type, false),
+            ])),
+        )
+        .unwrap();
+
+        let df = ctx
+            .sql("EXPLAIN SELECT region FROM sales WHERE amount > 1")
+            .unwrap();
+        assert_eq!(
+            df.schema(),
+            schema(vec![
+                ("plan_type", ArrowType::StringType, false),
+                ("plan", ArrowType::StringType, false),
+            ])
+        );
+        assert_eq!(
+            plan_rows(&df.collect().unwrap()),
+            vec![
+                (
+                    "logical_plan".to_owned(),
+                    "Projection: region\n  Filter: amount>1\n    Scan: sales, projection=None\n"
+                        .to_owned()
+                ),
+                (
+                    "optimized_logical_plan".to_owned(),
+                    "Projection: region\n  Scan: sales, projection=[\"region\"], filters=[amount>1]\n"
+                        .to_owned()
+                ),
+                (
+                    "physical_plan".to_owned(),
+                    "ProjectionExec: #0\n  ScanExec: sales, projection=[\"region\"], filters=[amount>1]\n"
+                        .to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn explain_analyze() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = crate::test_util::write_csv(&dir, "sales.csv", &["a,1", "b,2", "a,3"]);
+        let ctx = ExecutionContext::new();
+        let df = ctx
+            .csv(
+                schema(vec![
+                    ("region", ArrowType::StringType, false),
+                    ("amount", ArrowType::Int64Type, false),
+                ]),
+                &path,
+            )
+            .aggregate(vec![col("region")], vec![sum(col("amount"))])
+            .explain(true);
+
+        let rows = plan_rows(&df.collect().unwrap());
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[2].0, "physical_plan");
+        let lines: Vec<&str> = rows[2].1.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("HashAggregateExec: "));
+        assert!(lines[0].contains("metrics=[output_rows=2, output_batches=1, elapsed="));
+        assert!(lines[1].starts_with("  ScanExec: "));
+        assert!(lines[1].contains("metrics=[output_rows=3, output_batches=1, elapsed="));
+    }
+
+    #[test]
+    fn cte_query() {
+        let dir = tempfile::tempdir().unwrap();
+        let path =
+            crate::test_util::write_csv(&dir, "sales.csv", &["a,x,1", "b,y,2", "a,y,3", "c,x,10"]);
+        let ctx = ExecutionContext::new();
+        ctx.register_csv(
+            "sales",
+            &path,
+            CsvReadOptions::new().schema(schema(vec![
+                ("region", ArrowType::StringType, false),
+                ("product", ArrowType::StringType, false),
+                ("amount", ArrowType::Int64Type, false),
+            ])),
+        )
+        .unwrap();
+
+        let df = ctx
+            .sql(
+                "WITH totals AS (SELECT region, sum(amount) AS total FROM sales GROUP BY region) \
+                 SELECT t.total FROM (SELECT total FROM totals WHERE region = 'a') AS t",
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.optimize(df.plan()).unwrap().format(),
+            "SubqueryAlias: t
+  Projection: total
+    SubqueryAlias: totals
+      Projection: region, sum(amount) AS total
+        Aggregate: groupExpr=region, aggregateExpr=sum(amount)
+          Scan: sales, projection=[\"region\", \"amount\"], filters=[region='a']
+"
+        );
+        let batches = df.collect().unwrap();
+        let total = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<arrow::array::Int64Array>()
+            .unwrap();
+        assert_eq!(total.values(), &[4]);
+    }
+
+    #[test]
+    fn subqueries() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = ExecutionContext::new();
+        ctx.register_csv(
+            "customers",
+            &crate::test_util::write_csv(&dir, "customers.csv", &["1,ann", "2,bob", "3,cy"]),
+            CsvReadOptions::new().schema(schema(vec![
+                ("cid", ArrowType::Int64Type, false),
+                ("name", ArrowType::StringType, false),
+            ])),
+        )
+        .unwrap();
+        ctx.register_csv(
+            "orders",
+            &crate::test_util::write_csv(&dir, "orders.csv", &["1,10", "1,30", "2,5"]),
+            CsvReadOptions::new().schema(schema(vec![
+                ("customer", ArrowType::Int64Type, false),
+                ("amount", ArrowType::Int64Type, false),
+            ])),
+        )
+        .unwrap();
+
+        let names = |sql: &str| {
+            let batches = ctx.sql(sql).unwrap().collect().unwrap();
+            let mut names = vec![];
+            for batch in &batches {
+                let column = batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<arrow::array::StringArray>()
+                    .unwrap();
+                names.extend((0..batch.num_rows()).map(|i| column.value(i).to_owned()));
+            }
+            names
+        };
+        assert_eq!(
+            names("SELECT name FROM customers WHERE cid IN (SELECT customer FROM orders)"),
+            vec!["ann", "bob"]
+        );
+        assert_eq!(
+            names(
+                "SELECT name FROM customers c \
+                 WHERE NOT EXISTS (SELECT amount FROM orders WHERE customer = c.cid)"
+            ),
+            vec!["cy"]
+        );
+        assert_eq!(
+            names(
+                "SELECT name FROM customers \
+                 WHERE EXISTS (SELECT amount FROM orders WHERE customer = cid AND amount > 20)"
+            ),
+            vec!["ann"]
+        );
+        assert_eq!(
+            names(
+                "SELECT name FROM customers \
+                 WHERE (SELECT sum(amount) FROM orders WHERE customer = cid) > 4"
+            ),
+            vec!["ann", "bob"]
+        );
+
+        let batches = ctx
+            .sql(
+                "SELECT cid, (SELECT sum(amount) FROM orders WHERE customer = cid) AS total \
+                 FROM customers",
+            )
+            .unwrap()
+            .collect()
+            .unwrap();
+        assert_eq!(batches[0].schema().field(1).name(), "total");
+        let total = batches[0]
+            .column(1)
+            .as_any()
+            .downcast_ref::<arrow::array::Int64Array>()
+            .unwrap();
+        assert_eq!(total.value(0), 40);
+        assert_eq!(total.value(1), 5);
+        assert!(total.is_null(2));
+    }
+
+    /// Reads batches whose columns are all strings into rows.
+    fn string_rows(batches: &[RecordBatch]) -> Vec<Vec<String>> {
+        let mut rows = vec![];
+        for batch in batches {
+            for row in 0..batch.num_rows() {
+                rows.push(
+                    batch
+                        .columns()
+                        .iter()
+                        .map(|c| {
+                            let c = c.as_any().downcast_ref::<arrow::array::StringArray>();
+                            c.unwrap().value(row).to_owned()
+                        })
+                        .collect(),
+                );
+            }
+        }
+        rows
+    }
+
+    #[test]
+    fn show_tables_and_describe() {
+        let ctx = ExecutionContext::new();
+        ctx.register_csv(
+            "users",
+            "users.csv",
+            CsvReadOptions::new().schema(schema(vec![
+                ("id", ArrowType::Int64Type, false),
+                ("name", ArrowType::StringType, true),
+            ])),
+        )
+        .unwrap();
+        ctx.register_csv(
+            "analytics.events",
+            "events.csv",
+            CsvReadOptions::new().schema(schema(vec![("kind", ArrowType::StringType, false)])),
+        )
+        .unwrap();
+        let rows = |sql: &str| string_rows(&ctx.sql(sql).unwrap().collect().unwrap());
+
+        assert_eq!(
+            rows("SHOW TABLES"),
+            vec![
+                vec!["qrust", "analytics", "events", "BASE TABLE"],
+                vec!["qrust", "public", "users", "BASE TABLE"],
+                vec!["qrust", "information_schema", "columns", "VIEW"],
+                vec!["qrust", "information_schema", "tables", "VIEW"],
+            ]
+        );
+        assert_eq!(
+            rows("DESCRIBE users"),
+            vec![vec!["id", "Int64", "NO"], vec!["name", "Utf8", "YES"]]
+        );
+        assert_eq!(
+            rows("DESCRIBE analytics.events"),
+            vec![vec!["kind", "Utf8", "NO"]]
+        );
+        assert_eq!(
+            rows(
+                "SELECT table_name, column_name FROM information_schema.columns \
+                 WHERE table_schema = 'analytics' OR column_name = 'name'"
+            ),
+            vec![vec!["events", "kind"], vec!["users", "name"]]
+        );
+
+        assert_eq!(
+            ctx.sql("DESCRIBE missing").err().unwrap().to_string(),
+            "Planning error: Table not found: missing"
+        );
+        let source = ctx.table_source("users").unwrap();
+        assert_eq!(
+            ctx.register_table("information_schema.users", source)
+                .err()
+                .unwrap()
+                .to_string(),
+            "Planning error: Tables can't be registered in information_schema"
+        );
     }
 }
@@ -9,22 +9,24 @@ use crate::logical_plan::expression::*;
 /// # Overview
 /// Each tuple in the input corresponds to one field in the schema, and
 /// each tuple contains the following fields:
-/// ```
+/// ```text
 /// (name, datatype, nullable)
 /// ```
 ///
 /// # Example
 /// ```
+/// # use qrust::core::data_type::ArrowType;
+/// # use qrust::core::helper::schema;
 /// let schema = schema(vec![
-///   ("col1", ArrowType::Int64, false),
-///   ("col2", ArrowType::Int64, false),
+///   ("col1", ArrowType::Int64Type, false),
+///   ("col2", ArrowType::Int64Type, false),
 /// ]);
 /// ```
 pub fn schema(fields: Vec<(&str, ArrowType, bool)>) -> Arc<Schema> {
     Arc::new(Schema::new(
         fields
             .iter()
-            .map(move |(n, t, z)| Field::new(n, DataType::from(*t), z.clone()))
+            .map(move |(n, t, z)| Field::new(n, DataType::from(*t), *z))
             .collect(),
     ))
 }
@@ -34,9 +36,15 @@ pub fn col(name: &str) -> Arc<ColumnExpression> {
     Arc::new(ColumnExpression::new(String::from(name)))
 }
 
+/// Generate an expression that renames the result of the input.
+pub fn alias(expr: Arc<dyn LogicalExpression>, name: &str) -> Arc<AliasExpression> {
+    Arc::new(AliasExpression::new(expr, String::from(name)))
+}
+
 /// Helper trait for converting values into literal expressions.
-/// :TODO: Implement for more types than string/i64.
+/// :TODO: Implement for more types than string/i64/bool.
 pub trait IntoLit {
+    #[allow(clippy::wrong_self_convention)]
     fn into_lit(&self) -> Arc<dyn LogicalExpression>;
 }
 
@@ -52,6 +60,12 @@ impl IntoLit for &str {
     }
 }
 
+impl IntoLit for bool {
+    fn into_lit(&self) -> Arc<dyn LogicalExpression> {
+        Arc::new(LiteralBooleanExpression::new(*self))
+    }
+}
+
 /// Generate a literal expression from a value reference that implements the `IntoLit` trait.
 pub fn lit(val: &impl IntoLit) -> Arc<dyn LogicalExpression> {
     val.into_lit()
@@ -95,12 +109,17 @@ pub fn lteq(
 
 /// Generate a boolean expression that evaluates to true when both inputs are "truthy".
 pub fn and(l: Arc<dyn LogicalExpression>, r: Arc<dyn LogicalExpression>) -> Arc<BooleanExpression> {
-    Arc::new(BooleanExpression::neq(l, r))
+    Arc::new(BooleanExpression::and(l, r))
 }
 
 /// Generate a boolean expression that evaluates to true when at least one of the input is "truthy".
 pub fn or(l: Arc<dyn LogicalExpression>, r: Arc<dyn LogicalExpression>) -> Arc<BooleanExpression> {
-    Arc::new(BooleanExpression::neq(l, r))
+    Arc::new(BooleanExpression::or(l, r))
+}
+
+/// Generate a boolean expression that negates the input.
+pub fn not(expr: Arc<dyn LogicalExpression>) -> Arc<NotExpression> {
+    Arc::new(NotExpression::new(expr))
 }
 
 /// Generate a math expression that sums the inputs.
@@ -156,3 +175,13 @@ pub fn max(input: Arc<dyn LogicalExpression>) -> Arc<AggregateExpression> {
 pub fn avg(input: Arc<dyn LogicalExpression>) -> Arc<AggregateExpression> {
     Arc::new(AggregateExpression::avg(input))
 }
+
+/// Generate a sort expression that orders rows by the input in ascending order.
+pub fn asc(expr: Arc<dyn LogicalExpression>) -> Arc<SortExpression> {
+    Arc::new(SortExpression::new(expr, true, false))
+}
+
+/// Generate a sort expression that orders rows by the input in descending order.
+pub fn desc(expr: Arc<dyn LogicalExpression>) -> Arc<SortExpression> {
+    Arc::new(SortExpression::new(expr, false, false))
+}
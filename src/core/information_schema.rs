@@ -0,0 +1,281 @@
+use arrow::array::{ArrayRef, Int64Array, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use std::any::Any;
+use std::sync::Arc;
+
+use super::catalog::{CatalogProvider, SchemaProvider};
+use super::data_source::DataSource;
+use super::error::Result;
+use crate::logical_plan::expression::LogicalExpression;
+use crate::physical_plan::BatchIterator;
+
+/// The schema that describes the tables of a context. It can be read from every
+/// catalog, and lists the tables of all of them.
+pub const INFORMATION_SCHEMA: &str = "information_schema";
+
+const TABLES: &str = "tables";
+const COLUMNS: &str = "columns";
+
+/// Exposes the tables `tables` and `columns`, which list what is registered in a set of
+/// catalogs. Their contents are read when they are scanned, so they reflect tables
+/// registered after the provider was created.
+pub struct InformationSchemaProvider {
+    /// The catalogs to describe, sorted by name.
+    catalogs: Vec<(String, Arc<dyn CatalogProvider>)>,
+}
+
+impl InformationSchemaProvider {
+    pub fn new(mut catalogs: Vec<(String, Arc<dyn CatalogProvider>)>) -> InformationSchemaProvider {
+        catalogs.sort_by(|(a, _), (b, _)| a.cmp(b));
+        InformationSchemaProvider { catalogs }
+    }
+}
+
+impl SchemaProvider for InformationSchemaProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn table_names(&self) -> Vec<String> {
+        vec![COLUMNS.to_owned(), TABLES.to_owned()]
+    }
+
+    fn table(&self, name: &str) -> Option<Arc<dyn DataSource>> {
+        let kind = match name {
+            TABLES => Kind::Tables,
+            COLUMNS => Kind::Columns,
+            _ => return None,
+        };
+        Some(Arc::new(InformationSchemaTable {
+            kind,
+            catalogs: self.catalogs.clone(),
+        }))
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Tables,
+    Columns,
+}
+
+/// One of the tables of `information_schema`.
+struct InformationSchemaTable {
+    kind: Kind,
+    catalogs: Vec<(String, Arc<dyn CatalogProvider>)>,
+}
+
+/// A table as listed by `information_schema`.
+struct TableEntry {
+    catalog: String,
+    schema: String,
+    name: String,
+    table_type: &'static str,
+    fields: Vec<Field>,
+}
+
+impl InformationSchemaTable {
+    /// Lists every table, including the ones of `information_schema` itself.
+    fn entries(&self) -> Vec<TableEntry> {
+        let mut entries = vec![];
+        for (catalog_name, catalog) in &self.catalogs {
+            for schema_name in catalog.schema_names() {
+                let schema = match catalog.schema(&schema_name) {
+                    Some(schema) => schema,
+                    None => continue,
+                };
+                for table_name in schema.table_names() {
+                    if let Some(table) = schema.table(&table_name) {
+                        entries.push(TableEntry {
+                            catalog: catalog_name.clone(),
+                            schema: schema_name.clone(),
+                            name: table_name,
+                            table_type: "BASE TABLE",
+                            fields: table.schema().fields().clone(),
+                        });
+                    }
+                }
+            }
+            for (name, kind) in [(COLUMNS, Kind::Columns), (TABLES, Kind::Tables)] {
+                entries.push(TableEntry {
+                    catalog: catalog_name.clone(),
+                    schema: INFORMATION_SCHEMA.to_owned(),
+                    name: name.to_owned(),
+                    table_type: "VIEW",
+                    fields: table_schema(kind).fields().clone(),
+                });
+            }
+        }
+        entries
+    }
+
+    fn batch(&self) -> Result<RecordBatch> {
+        let entries = self.entries();
+        let strings = |values: Vec<&str>| Arc::new(StringArray::from(values)) as ArrayRef;
+        let columns = match self.kind {
+            Kind::Tables => vec![
+                strings(entries.iter().map(|e| e.catalog.as_str()).collect()),
+                strings(entries.iter().map(|e| e.schema.as_str()).collect()),
+                strings(entries.iter().map(|e| e.name.as_str()).collect()),
+                strings(entries.iter().map(|e| e.table_type).collect()),
+            ],
+            Kind::Columns => {
+                let columns: Vec<(&TableEntry, usize, &Field)> = entries
+                    .iter()
+                    .flat_map(|e| e.fields.iter().enumerate().map(move |(i, f)| (e, i, f)))
+                    .collect();
+                let data_types: Vec<String> = columns
+                    .iter()
+                    .map(|(_, _, f)| f.data_type().to_string())
+                    .collect();
+                vec![
+                    strings(columns.iter().map(|(e, _, _)| e.catalog.as_str()).collect()),
+                    strings(columns.iter().map(|(e, _, _)| e.schema.as_str()).collect()),
+                    strings(columns.iter().map(|(e, _, _)| e.name.as_str()).collect()),
+                    strings(columns.iter().map(|(_, _, f)| f.name().as_str()).collect()),
+                    Arc::new(Int64Array::from(
+                        columns
+                            .iter()
+                            .map(|(_, i, _)| *i as i64 + 1)
+                            .collect::<Vec<i64>>(),
+                    )),
+                    strings(data_types.iter().map(|t| t.as_str()).collect()),
+                    strings(
+                        columns
+                            .iter()
+                            .map(|(_, _, f)| if f.is_nullable() { "YES" } else { "NO" })
+                            .collect(),
+                    ),
+                ]
+            }
+        };
+        Ok(RecordBatch::try_new(table_schema(self.kind), columns)?)
+    }
+}
+
+impl DataSource for InformationSchemaTable {
+    fn schema(&self) -> Arc<Schema> {
+        table_schema(self.kind)
+    }
+
+    fn scan(
+        &self,
+        projection: Vec<String>,
+        _filters: Vec<Arc<dyn LogicalExpression>>,
+    ) -> Result<BatchIterator> {
+        let batch = self.batch()?;
+        let batch = if projection.is_empty() {
+            batch
+        } else {
+            let schema = batch.schema();
+            let indices: Vec<usize> = (0..schema.fields().len())
+                .filter(|i| projection.contains(schema.field(*i).name()))
+                .collect();
+            let fields = indices.iter().map(|i| schema.field(*i).clone()).collect();
+            let columns = indices
+                .iter()
+                .map(|i| Arc::clone(batch.column(*i)))
+                .collect();
+            RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?
+        };
+        Ok(Box::new(std::iter::once(Ok(batch))))
+    }
+}
+
+fn table_schema(kind: Kind) -> Arc<Schema> {
+    let utf8 = |name: &str| Field::new(name, DataType::Utf8, false);
+    let fields = match kind {
+        Kind::Tables => vec![
+            utf8("table_catalog"),
+            utf8("table_schema"),
+            utf8("table_name"),
+            utf8("table_type"),
+        ],
+        Kind::Columns => vec![
+            utf8("table_catalog"),
+            utf8("table_schema"),
+            utf8("table_name"),
+            utf8("column_name"),
+            Field::new("ordinal_position", DataType::Int64, false),
+            utf8("data_type"),
+            utf8("is_nullable"),
+        ],
+    };
+    Arc::new(Schema::new(fields))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::catalog::{MemoryCatalogProvider, MemorySchemaProvider};
+    use crate::core::data_source::CsvDataSource;
+    use crate::core::data_type::ArrowType;
+    use crate::core::helper::schema;
+
+    fn catalog(table: &str) -> Arc<dyn CatalogProvider> {
+        let schema_provider = Arc::new(MemorySchemaProvider::new());
+        schema_provider
+            .register_table(
+                table,
+                Arc::new(CsvDataSource::new(
+                    schema(vec![
+                        ("a", ArrowType::Int64Type, false),
+                        ("b", ArrowType::StringType, true),
+                    ]),
+                    format!("{}.csv", table),
+                )),
+            )
+            .unwrap();
+        let catalog = MemoryCatalogProvider::new();
+        catalog.register_schema("s", schema_provider).unwrap();
+        Arc::new(catalog)
+    }
+
+    #[test]
+    fn lists_tables_of_every_catalog() {
+        let provider = InformationSchemaProvider::new(vec![
+            ("y".to_owned(), catalog("t2")),
+            ("x".to_owned(), catalog("t1")),
+        ]);
+        assert_eq!(provider.table_names(), vec!["columns", "tables"]);
+        assert!(provider.table("views").is_none());
+
+        let tables = provider.table("tables").unwrap();
+        let batches: Vec<RecordBatch> = tables
+            .scan(
+                vec!["table_catalog".to_owned(), "table_name".to_owned()],
+                vec![],
+            )
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(batches[0].num_columns(), 2);
+        let names = batches[0]
+            .column(1)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        let names: Vec<&str> = (0..batches[0].num_rows()).map(|i| names.value(i)).collect();
+        assert_eq!(
+            names,
+            vec!["t1", "columns", "tables", "t2", "columns", "tables"]
+        );
+
+        let columns = provider.table("columns").unwrap();
+        let batch = columns
+            .scan(vec![], vec![])
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        // Two columns for each user table, plus those of the two views in each catalog.
+        assert_eq!(batch.num_rows(), 2 * (2 + 4 + 7));
+        let positions = batch
+            .column(4)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(&positions.values()[0..3], &[1, 2, 1]);
+    }
+}
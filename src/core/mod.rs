@@ -1,5 +1,10 @@
+pub mod catalog;
 pub mod data_frame;
 pub mod data_source;
 pub mod data_type;
+pub mod error;
 pub mod execution_context;
 pub mod helper;
+pub mod information_schema;
+pub mod prepared_statement;
+pub mod scalar_value;
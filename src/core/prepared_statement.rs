@@ -0,0 +1,504 @@
+use arrow::datatypes::{DataType, Schema};
+use arrow::record_batch::RecordBatch;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::error::{QrustError, Result};
+use super::execution_context::ExecutionContext;
+use super::scalar_value::ScalarValue;
+use crate::logical_plan::expression::{
+    ExistsExpression, InSubqueryExpression, LiteralBooleanExpression, LiteralI64Expression,
+    LiteralStringExpression, LogicalExpression, PlaceholderExpression, ScalarSubqueryExpression,
+};
+use crate::logical_plan::selection::Selection;
+use crate::logical_plan::LogicalPlan;
+use crate::optimizer::predicate_pushdown::PredicatePushdown;
+use crate::optimizer::OptimizerRule;
+use crate::physical_plan::planner::PhysicalPlanner;
+use crate::physical_plan::{collect, BatchIterator, PhysicalPlan};
+
+/// A parameter of a prepared statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    /// The name of the parameter without the `$`, e.g. `1` for `$1` or `id` for `$id`.
+    pub name: String,
+    pub data_type: DataType,
+}
+
+/// A query that is planned and optimized once and can then be run any number of times
+/// with different values for its parameters.
+///
+/// Parameters stand for values in expressions. The row counts of `LIMIT` and `OFFSET`
+/// shape the plan, so they can't be parameters.
+pub struct PreparedStatement {
+    plan: Arc<dyn LogicalPlan>,
+    parameters: Vec<Parameter>,
+    /// Plans with the settings the context had when the statement was prepared.
+    planner: PhysicalPlanner,
+}
+
+impl PreparedStatement {
+    /// Prepares `plan` by finding its parameters and optimizing it with the rules of
+    /// `ctx`. The statement is planned with the settings `ctx` has now.
+    pub fn try_new(
+        ctx: &ExecutionContext,
+        plan: Arc<dyn LogicalPlan>,
+    ) -> Result<PreparedStatement> {
+        // Parameters are found before optimizing, which may remove some of them.
+        let parameters = find_parameters(&plan)?;
+        let plan = ctx.optimize(plan)?;
+        Ok(PreparedStatement {
+            plan,
+            parameters,
+            planner: ctx.physical_planner(),
+        })
+    }
+
+    /// The optimized plan, in which parameters haven't been replaced yet.
+    pub fn plan(&self) -> Arc<dyn LogicalPlan> {
+        Arc::clone(&self.plan)
+    }
+
+    pub fn schema(&self) -> Arc<Schema> {
+        self.plan.schema()
+    }
+
+    /// The parameters of the statement. Positional parameters are listed by number and
+    /// named parameters by name.
+    pub fn parameters(&self) -> &[Parameter] {
+        &self.parameters
+    }
+
+    /// Runs the statement with `values` for its parameters, in the order of
+    /// `parameters`.
+    pub fn execute(&self, values: Vec<ScalarValue>) -> Result<Vec<RecordBatch>> {
+        collect(self.create_physical_plan(values)?)
+    }
+
+    /// Runs the statement with `values` for its parameters, producing batches as the
+    /// returned iterator is advanced.
+    pub fn execute_stream(&self, values: Vec<ScalarValue>) -> Result<BatchIterator> {
+        self.create_physical_plan(values)?.execute()
+    }
+
+    /// Runs the statement with the value for each parameter stored under its name,
+    /// without the `$`.
+    pub fn execute_named(&self, values: HashMap<String, ScalarValue>) -> Result<Vec<RecordBatch>> {
+        if let Some(name) = values
+            .keys()
+            .find(|name| !self.parameters.iter().any(|p| p.name == **name))
+        {
+            return Err(QrustError::Plan(format!("Unknown parameter ${}", name)));
+        }
+        let mut values = values;
+        let values = self
+            .parameters
+            .iter()
+            .map(|p| {
+                values
+                    .remove(&p.name)
+                    .ok_or_else(|| QrustError::Plan(format!("No value for parameter ${}", p.name)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.execute(values)
+    }
+
+    /// Creates a physical plan in which every parameter is replaced by its value.
+    pub fn create_physical_plan(&self, values: Vec<ScalarValue>) -> Result<Arc<dyn PhysicalPlan>> {
+        if values.len() != self.parameters.len() {
+            return Err(QrustError::Plan(format!(
+                "Expected {} parameter values but got {}",
+                self.parameters.len(),
+                values.len()
+            )));
+        }
+        let mut param_values = HashMap::new();
+        for (parameter, value) in self.parameters.iter().zip(values) {
+            if value.data_type() != parameter.data_type {
+                return Err(QrustError::Plan(format!(
+                    "Expected a value of type {} for parameter ${} but got {}",
+                    parameter.data_type,
+                    parameter.name,
+                    value.data_type()
+                )));
+            }
+            param_values.insert(format!("${}", parameter.name), value);
+        }
+        // Filters on parameters could not be handed to the data sources while the
+        // values were unknown, so they are pushed down again once they are bound.
+        let plan = bind_filters(&self.plan, &param_values)?;
+        let plan = PredicatePushdown::new().optimize(plan)?;
+        self.planner
+            .clone()
+            .with_param_values(param_values)
+            .create_physical_plan(&plan)
+    }
+}
+
+/// Replaces the parameters in the filters of `plan` with literals of their values.
+/// Values that have no literal, such as nulls, are left for the physical planner.
+fn bind_filters(
+    plan: &Arc<dyn LogicalPlan>,
+    values: &HashMap<String, ScalarValue>,
+) -> Result<Arc<dyn LogicalPlan>> {
+    let children = plan
+        .children()
+        .iter()
+        .map(|child| bind_filters(child, values))
+        .collect::<Result<Vec<_>>>()?;
+    let plan = if children.is_empty() {
+        Arc::clone(plan)
+    } else {
+        plan.try_with_new_children(children)?
+    };
+    Ok(match plan.as_any().downcast_ref::<Selection>() {
+        Some(selection) => Arc::new(Selection::new(
+            selection.input(),
+            bind_expr(&selection.expr(), values),
+        )),
+        None => plan,
+    })
+}
+
+fn bind_expr(
+    expr: &Arc<dyn LogicalExpression>,
+    values: &HashMap<String, ScalarValue>,
+) -> Arc<dyn LogicalExpression> {
+    if let Some(placeholder) = expr.as_any().downcast_ref::<PlaceholderExpression>() {
+        return match values.get(placeholder.id()) {
+            Some(ScalarValue::Boolean(Some(v))) => Arc::new(LiteralBooleanExpression::new(*v)),
+            Some(ScalarValue::Int64(Some(v))) => Arc::new(LiteralI64Expression::new(*v)),
+            Some(ScalarValue::Utf8(Some(v))) => Arc::new(LiteralStringExpression::new(v.clone())),
+            _ => Arc::clone(expr),
+        };
+    }
+    let children = expr.children();
+    if children.is_empty() {
+        return Arc::clone(expr);
+    }
+    expr.with_new_children(
+        children
+            .iter()
+            .map(|child| bind_expr(child, values))
+            .collect(),
+    )
+}
+
+/// Finds the parameters used in `plan`, including inside subqueries, and checks that
+/// each has a single known type.
+fn find_parameters(plan: &Arc<dyn LogicalPlan>) -> Result<Vec<Parameter>> {
+    let mut found: Vec<(String, Option<DataType>)> = vec![];
+    visit_plan(plan, &mut found)?;
+
+    let positional = found
+        .iter()
+        .filter(|(name, _)| name.parse::<usize>().is_ok())
+        .count();
+    if positional != 0 && positional != found.len() {
+        return Err(QrustError::Plan(
+            "Can't mix positional and named parameters".to_owned(),
+        ));
+    }
+    if positional != 0 {
+        found.sort_by_key(|(name, _)| name.parse::<usize>().unwrap());
+        for (i, (name, _)) in found.iter().enumerate() {
+            if name.parse::<usize>().unwrap() != i + 1 {
+                return Err(QrustError::Plan(format!(
+                    "Positional parameters must be numbered from $1 without gaps, but found ${}",
+                    name
+                )));
+            }
+        }
+    } else {
+        found.sort_by(|(a, _), (b, _)| a.cmp(b));
+    }
+
+    found
+        .into_iter()
+        .map(|(name, data_type)| match data_type {
+            Some(data_type) => Ok(Parameter { name, data_type }),
+            None => Err(QrustError::Plan(format!(
+                "Can't infer the type of parameter ${}",
+                name
+            ))),
+        })
+        .collect()
+}
+
+fn visit_plan(
+    plan: &Arc<dyn LogicalPlan>,
+    found: &mut Vec<(String, Option<DataType>)>,
+) -> Result<()> {
+    for expr in plan.expressions() {
+        visit_expr(&expr, found)?;
+    }
+    for child in plan.children() {
+        visit_plan(&child, found)?;
+    }
+    Ok(())
+}
+
+fn visit_expr(
+    expr: &Arc<dyn LogicalExpression>,
+    found: &mut Vec<(String, Option<DataType>)>,
+) -> Result<()> {
+    let any = expr.as_any();
+    if let Some(placeholder) = any.downcast_ref::<PlaceholderExpression>() {
+        let name = placeholder.id().trim_start_matches('$').to_owned();
+        let data_type = placeholder.data_type().cloned();
+        match found.iter_mut().find(|(n, _)| *n == name) {
+            None => found.push((name, data_type)),
+            Some((_, known @ None)) => *known = data_type,
+            Some((_, Some(known))) => {
+                if let Some(data_type) = data_type {
+                    if *known != data_type {
+                        return Err(QrustError::Plan(format!(
+                            "Parameter ${} is used as both {} and {}",
+                            name, known, data_type
+                        )));
+                    }
+                }
+            }
+        }
+    }
+
+    let subquery = if let Some(e) = any.downcast_ref::<InSubqueryExpression>() {
+        Some(e.subquery())
+    } else if let Some(e) = any.downcast_ref::<ExistsExpression>() {
+        Some(e.subquery())
+    } else {
+        any.downcast_ref::<ScalarSubqueryExpression>()
+            .map(|e| e.subquery())
+    };
+    if let Some(subquery) = subquery {
+        visit_plan(&subquery, found)?;
+    }
+
+    for child in expr.children() {
+        visit_expr(&child, found)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data_source::CsvReadOptions;
+    use crate::core::data_type::ArrowType;
+    use crate::core::helper::*;
+    use crate::test_util::csv_context;
+    use arrow::array::StringArray;
+
+    fn context(dir: &tempfile::TempDir) -> ExecutionContext {
+        csv_context(
+            dir,
+            vec![(
+                "users",
+                CsvReadOptions::new().schema(schema(vec![
+                    ("id", ArrowType::Int64Type, false),
+                    ("name", ArrowType::StringType, false),
+                    ("age", ArrowType::Int64Type, false),
+                ])),
+                &["1,ann,30", "2,bob,25", "3,cy,41"],
+            )],
+        )
+    }
+
+    fn names(batches: &[RecordBatch]) -> Vec<String> {
+        let mut names = vec![];
+        for batch in batches {
+            let column = batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap();
+            names.extend((0..batch.num_rows()).map(|i| column.value(i).to_owned()));
+        }
+        names
+    }
+
+    #[test]
+    fn positional_parameters() {
+        let dir = tempfile::tempdir().unwrap();
+        let statement = context(&dir)
+            .prepare("SELECT name FROM users WHERE id = $1 OR age > $2 + 10")
+            .unwrap();
+        assert_eq!(
+            statement.parameters(),
+            &[
+                Parameter {
+                    name: "1".to_owned(),
+                    data_type: DataType::Int64
+                },
+                Parameter {
+                    name: "2".to_owned(),
+                    data_type: DataType::Int64
+                },
+            ]
+        );
+        assert_eq!(
+            statement.plan().format(),
+            "Projection: name
+  Filter: id=$1 OR age>$2 + 10
+    Scan: users, projection=[\"id\", \"name\", \"age\"]
+"
+        );
+
+        let batches = statement
+            .execute(vec![
+                ScalarValue::Int64(Some(1)),
+                ScalarValue::Int64(Some(30)),
+            ])
+            .unwrap();
+        assert_eq!(names(&batches), vec!["ann", "cy"]);
+        let batches = statement
+            .execute(vec![ScalarValue::Int64(Some(2)), ScalarValue::Int64(None)])
+            .unwrap();
+        assert_eq!(names(&batches), vec!["bob"]);
+    }
+
+    #[test]
+    fn filters_on_parameters_reach_the_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let statement = context(&dir)
+            .prepare("SELECT name FROM users WHERE id = $1 AND name != $2")
+            .unwrap();
+        let plan = statement
+            .create_physical_plan(vec![
+                ScalarValue::Int64(Some(3)),
+                ScalarValue::Utf8(Some("ann".to_owned())),
+            ])
+            .unwrap();
+        assert_eq!(
+            plan.format(),
+            "ProjectionExec: #1
+  ScanExec: users, projection=[\"id\", \"name\"], filters=[id=3, name!='ann']
+"
+        );
+        assert_eq!(names(&collect(plan).unwrap()), vec!["cy"]);
+
+        // A null has no literal, so its filter stays above the scan.
+        let plan = statement
+            .create_physical_plan(vec![ScalarValue::Int64(Some(3)), ScalarValue::Utf8(None)])
+            .unwrap();
+        assert_eq!(
+            plan.format(),
+            "ProjectionExec: #1
+  SelectionExec: #1!=NULL
+    ScanExec: users, projection=[\"id\", \"name\"], filters=[id=3]
+"
+        );
+        assert!(names(&collect(plan).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn named_parameters() {
+        let dir = tempfile::tempdir().unwrap();
+        let statement = context(&dir)
+            .prepare("SELECT name FROM users WHERE name = $name OR age < $max_age")
+            .unwrap();
+        let parameters: Vec<&str> = statement
+            .parameters()
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(parameters, vec!["max_age", "name"]);
+
+        let mut values = HashMap::new();
+        values.insert("name".to_owned(), ScalarValue::Utf8(Some("cy".to_owned())));
+        values.insert("max_age".to_owned(), ScalarValue::Int64(Some(26)));
+        let batches = statement.execute_named(values.clone()).unwrap();
+        assert_eq!(names(&batches), vec!["bob", "cy"]);
+
+        values.remove("name");
+        let err = statement.execute_named(values.clone()).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Planning error: No value for parameter $name"
+        );
+        values.insert("nme".to_owned(), ScalarValue::Utf8(None));
+        let err = statement.execute_named(values).err().unwrap();
+        assert_eq!(err.to_string(), "Planning error: Unknown parameter $nme");
+    }
+
+    #[test]
+    fn explain_analyze_with_parameters() {
+        let dir = tempfile::tempdir().unwrap();
+        let statement = context(&dir)
+            .prepare("EXPLAIN ANALYZE SELECT name FROM users WHERE age > $1")
+            .unwrap();
+        let batches = statement
+            .execute(vec![ScalarValue::Int64(Some(26))])
+            .unwrap();
+        let plans = batches[0]
+            .column(1)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        let physical_plan = plans.value(batches[0].num_rows() - 1);
+        assert!(physical_plan.starts_with("ProjectionExec: "));
+        assert!(physical_plan.contains("metrics=[output_rows=2, output_batches=1, elapsed="));
+    }
+
+    #[test]
+    fn sort_memory_limit_of_context() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = context(&dir);
+        ctx.set_sort_memory_limit(1);
+        let statement = ctx
+            .prepare("SELECT name FROM users WHERE age > $1 ORDER BY age DESC")
+            .unwrap();
+        ctx.set_sort_memory_limit(1 << 20);
+        assert_eq!(statement.planner.sort_memory_limit(), 1);
+        let batches = statement
+            .execute(vec![ScalarValue::Int64(Some(0))])
+            .unwrap();
+        assert_eq!(names(&batches), vec!["cy", "ann", "bob"]);
+    }
+
+    #[test]
+    fn parameter_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = context(&dir);
+        let statement = ctx.prepare("SELECT name FROM users WHERE id = $1").unwrap();
+        let err = statement
+            .execute(vec![ScalarValue::Utf8(Some("1".to_owned()))])
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Planning error: Expected a value of type Int64 for parameter $1 but got Utf8"
+        );
+        let err = statement.execute(vec![]).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Planning error: Expected 1 parameter values but got 0"
+        );
+
+        let err = |sql: &str| ctx.prepare(sql).err().unwrap().to_string();
+        assert_eq!(
+            err("SELECT name FROM users WHERE id = $1 AND age = $age"),
+            "Planning error: Can't mix positional and named parameters"
+        );
+        assert_eq!(
+            err("SELECT name FROM users WHERE id = $2"),
+            "Planning error: Positional parameters must be numbered from $1 without gaps, but found $2"
+        );
+        assert_eq!(
+            err("SELECT name FROM users WHERE id = $1 AND name = $1"),
+            "Planning error: Parameter $1 is used as both Int64 and Utf8"
+        );
+        assert_eq!(
+            err("SELECT $1 FROM users"),
+            "Planning error: Can't infer the type of parameter $1"
+        );
+        assert_eq!(
+            err("SELECT name FROM users LIMIT $1"),
+            "Planning error: LIMIT must be a non-negative integer, got $1"
+        );
+        assert_eq!(
+            err("DROP TABLE users"),
+            "Planning error: Only queries can be prepared"
+        );
+    }
+}
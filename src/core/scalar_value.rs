@@ -0,0 +1,260 @@
+use arrow::array::{ArrayRef, BooleanArray, Float64Array, Int64Array, StringArray};
+use arrow::datatypes::DataType;
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use super::error::{QrustError, Result};
+
+/// A single value of one of the data types supported by Qrust. `None` represents null.
+#[derive(Debug, Clone)]
+pub enum ScalarValue {
+    Boolean(Option<bool>),
+    Int64(Option<i64>),
+    Float64(Option<f64>),
+    Utf8(Option<String>),
+}
+
+/// Maps the zeros to one value and the NaNs to another, so that floats can be compared
+/// and hashed by their bit patterns and scalars can be used as hash keys.
+fn normalize(v: f64) -> f64 {
+    if v.is_nan() {
+        f64::NAN
+    } else if v == 0.0 {
+        0.0
+    } else {
+        v
+    }
+}
+
+impl PartialEq for ScalarValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (ScalarValue::Boolean(l), ScalarValue::Boolean(r)) => l == r,
+            (ScalarValue::Int64(l), ScalarValue::Int64(r)) => l == r,
+            (ScalarValue::Float64(l), ScalarValue::Float64(r)) => {
+                l.map(|v| normalize(v).to_bits()) == r.map(|v| normalize(v).to_bits())
+            }
+            (ScalarValue::Utf8(l), ScalarValue::Utf8(r)) => l == r,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for ScalarValue {}
+
+impl Hash for ScalarValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            ScalarValue::Boolean(v) => v.hash(state),
+            ScalarValue::Int64(v) => v.hash(state),
+            ScalarValue::Float64(v) => v.map(|v| normalize(v).to_bits()).hash(state),
+            ScalarValue::Utf8(v) => v.hash(state),
+        }
+    }
+}
+
+impl PartialOrd for ScalarValue {
+    /// Compares two scalars of the same type. Scalars of different types are unordered.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (ScalarValue::Boolean(l), ScalarValue::Boolean(r)) => l.partial_cmp(r),
+            (ScalarValue::Int64(l), ScalarValue::Int64(r)) => l.partial_cmp(r),
+            // NaN is greater than every other float, as it is equal to itself.
+            (ScalarValue::Float64(Some(l)), ScalarValue::Float64(Some(r))) => {
+                Some(normalize(*l).total_cmp(&normalize(*r)))
+            }
+            (ScalarValue::Float64(l), ScalarValue::Float64(r)) => {
+                l.is_some().partial_cmp(&r.is_some())
+            }
+            (ScalarValue::Utf8(l), ScalarValue::Utf8(r)) => l.partial_cmp(r),
+            _ => None,
+        }
+    }
+}
+
+impl ScalarValue {
+    pub fn data_type(&self) -> DataType {
+        match self {
+            ScalarValue::Boolean(_) => DataType::Boolean,
+            ScalarValue::Int64(_) => DataType::Int64,
+            ScalarValue::Float64(_) => DataType::Float64,
+            ScalarValue::Utf8(_) => DataType::Utf8,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        match self {
+            ScalarValue::Boolean(v) => v.is_none(),
+            ScalarValue::Int64(v) => v.is_none(),
+            ScalarValue::Float64(v) => v.is_none(),
+            ScalarValue::Utf8(v) => v.is_none(),
+        }
+    }
+
+    /// Returns a null scalar of the given type.
+    pub fn try_null(data_type: &DataType) -> Result<ScalarValue> {
+        match data_type {
+            DataType::Boolean => Ok(ScalarValue::Boolean(None)),
+            DataType::Int64 => Ok(ScalarValue::Int64(None)),
+            DataType::Float64 => Ok(ScalarValue::Float64(None)),
+            DataType::Utf8 => Ok(ScalarValue::Utf8(None)),
+            other => Err(QrustError::NotImplemented(format!(
+                "Unsupported scalar type {}",
+                other
+            ))),
+        }
+    }
+
+    /// Reads the value at `index` of `array`.
+    pub fn try_from_array(array: &ArrayRef, index: usize) -> Result<ScalarValue> {
+        let null = array.is_null(index);
+        Ok(match array.data_type() {
+            DataType::Boolean => {
+                let a = array.as_any().downcast_ref::<BooleanArray>().unwrap();
+                ScalarValue::Boolean(if null { None } else { Some(a.value(index)) })
+            }
+            DataType::Int64 => {
+                let a = array.as_any().downcast_ref::<Int64Array>().unwrap();
+                ScalarValue::Int64(if null { None } else { Some(a.value(index)) })
+            }
+            DataType::Float64 => {
+                let a = array.as_any().downcast_ref::<Float64Array>().unwrap();
+                ScalarValue::Float64(if null { None } else { Some(a.value(index)) })
+            }
+            DataType::Utf8 => {
+                let a = array.as_any().downcast_ref::<StringArray>().unwrap();
+                ScalarValue::Utf8(if null {
+                    None
+                } else {
+                    Some(a.value(index).to_owned())
+                })
+            }
+            other => {
+                return Err(QrustError::NotImplemented(format!(
+                    "Unsupported scalar type {}",
+                    other
+                )))
+            }
+        })
+    }
+
+    /// Builds an array of type `data_type` from a list of scalars.
+    pub fn iter_to_array(values: &[ScalarValue], data_type: &DataType) -> Result<ArrayRef> {
+        fn mismatch(v: &ScalarValue, data_type: &DataType) -> QrustError {
+            QrustError::Execution(format!(
+                "Cannot build an array of type {} from {:?}",
+                data_type, v
+            ))
+        }
+
+        Ok(match data_type {
+            DataType::Boolean => Arc::new(
+                values
+                    .iter()
+                    .map(|v| match v {
+                        ScalarValue::Boolean(b) => Ok(*b),
+                        _ => Err(mismatch(v, data_type)),
+                    })
+                    .collect::<Result<BooleanArray>>()?,
+            ),
+            DataType::Int64 => Arc::new(
+                values
+                    .iter()
+                    .map(|v| match v {
+                        ScalarValue::Int64(i) => Ok(*i),
+                        _ => Err(mismatch(v, data_type)),
+                    })
+                    .collect::<Result<Int64Array>>()?,
+            ),
+            DataType::Float64 => Arc::new(
+                values
+                    .iter()
+                    .map(|v| match v {
+                        ScalarValue::Float64(f) => Ok(*f),
+                        _ => Err(mismatch(v, data_type)),
+                    })
+                    .collect::<Result<Float64Array>>()?,
+            ),
+            DataType::Utf8 => Arc::new(
+                values
+                    .iter()
+                    .map(|v| match v {
+                        ScalarValue::Utf8(s) => Ok(s.clone()),
+                        _ => Err(mismatch(v, data_type)),
+                    })
+                    .collect::<Result<StringArray>>()?,
+            ),
+            other => {
+                return Err(QrustError::NotImplemented(format!(
+                    "Unsupported scalar type {}",
+                    other
+                )))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let array: ArrayRef = Arc::new(StringArray::from(vec![Some("a"), None, Some("c")]));
+        let values = (0..array.len())
+            .map(|i| ScalarValue::try_from_array(&array, i))
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            values,
+            vec![
+                ScalarValue::Utf8(Some("a".to_owned())),
+                ScalarValue::Utf8(None),
+                ScalarValue::Utf8(Some("c".to_owned())),
+            ]
+        );
+
+        let result = ScalarValue::iter_to_array(&values, &DataType::Utf8).unwrap();
+        assert_eq!(result.data(), array.data());
+    }
+
+    #[test]
+    fn ordering() {
+        assert!(ScalarValue::Int64(Some(1)) < ScalarValue::Int64(Some(2)));
+        assert!(ScalarValue::Utf8(Some("b".to_owned())) > ScalarValue::Utf8(Some("a".to_owned())));
+        assert_eq!(
+            ScalarValue::Int64(Some(1)).partial_cmp(&ScalarValue::Utf8(None)),
+            None
+        );
+    }
+
+    #[test]
+    fn float_equality_agrees_with_hashing() {
+        fn hash(v: &ScalarValue) -> u64 {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            v.hash(&mut hasher);
+            hasher.finish()
+        }
+        let zero = ScalarValue::Float64(Some(0.0));
+        let negative_zero = ScalarValue::Float64(Some(-0.0));
+        assert_eq!(zero, negative_zero);
+        assert_eq!(hash(&zero), hash(&negative_zero));
+        assert_eq!(zero.partial_cmp(&negative_zero), Some(Ordering::Equal));
+
+        let nan = ScalarValue::Float64(Some(f64::NAN));
+        let other_nan = ScalarValue::Float64(Some(-f64::NAN));
+        assert_eq!(nan, other_nan);
+        assert_eq!(hash(&nan), hash(&other_nan));
+        assert_eq!(nan.partial_cmp(&other_nan), Some(Ordering::Equal));
+        assert!(nan > ScalarValue::Float64(Some(f64::INFINITY)));
+        assert!(ScalarValue::Float64(None) < ScalarValue::Float64(Some(f64::NEG_INFINITY)));
+        assert_ne!(nan, ScalarValue::Float64(None));
+    }
+
+    #[test]
+    fn type_mismatch() {
+        let values = vec![ScalarValue::Int64(Some(1))];
+        assert!(ScalarValue::iter_to_array(&values, &DataType::Utf8).is_err());
+    }
+}
@@ -1,2 +1,8 @@
 pub mod core;
 pub mod logical_plan;
+pub mod optimizer;
+pub mod physical_plan;
+pub mod sql;
+
+#[cfg(test)]
+mod test_util;
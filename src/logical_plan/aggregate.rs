@@ -1,4 +1,5 @@
 use arrow::datatypes::Field;
+use std::any::Any;
 
 use super::expression::*;
 use super::*;
@@ -10,6 +11,10 @@ pub struct Aggregate {
 }
 
 impl LogicalPlan for Aggregate {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn schema(&self) -> Arc<Schema> {
         let mut fields: Vec<Field> = self
             .groupexpr
@@ -28,6 +33,20 @@ impl LogicalPlan for Aggregate {
         vec![Arc::clone(&self.input)]
     }
 
+    fn with_new_children(&self, children: Vec<Arc<dyn LogicalPlan>>) -> Arc<dyn LogicalPlan> {
+        Arc::new(Aggregate::new(
+            Arc::clone(&children[0]),
+            self.groupexpr.clone(),
+            self.aggregateexpr.clone(),
+        ))
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn LogicalExpression>> {
+        let mut expr = self.groupexpr.clone();
+        expr.extend(self.aggregateexpr.iter().map(|a| Arc::clone(a) as _));
+        expr
+    }
+
     fn to_string(&self) -> String {
         format!(
             "Aggregate: groupExpr={}, aggregateExpr={}",
@@ -57,6 +76,18 @@ impl Aggregate {
             aggregateexpr,
         }
     }
+
+    pub fn input(&self) -> Arc<dyn LogicalPlan> {
+        Arc::clone(&self.input)
+    }
+
+    pub fn group_expr(&self) -> &[Arc<dyn LogicalExpression>] {
+        &self.groupexpr
+    }
+
+    pub fn aggregate_expr(&self) -> &[Arc<AggregateExpression>] {
+        &self.aggregateexpr
+    }
 }
 
 #[cfg(test)]
@@ -88,8 +119,26 @@ mod tests {
             expr.schema(),
             schema(vec![
                 ("abc", ArrowType::StringType, false),
-                ("values", ArrowType::Int64Type, false),
+                ("sum", ArrowType::Int64Type, false),
             ])
         )
     }
+
+    #[test]
+    fn test_avg_type() {
+        let ctx = ExecutionContext::new();
+        let input = ctx.csv(
+            schema(vec![
+                ("abc", ArrowType::StringType, false),
+                ("values", ArrowType::Int64Type, false),
+            ]),
+            "path.csv",
+        );
+        let expr = Aggregate::new(input.plan(), vec![], vec![avg(col("values"))]);
+
+        assert_eq!(
+            expr.schema().field(0).data_type(),
+            &arrow::datatypes::DataType::Float64
+        );
+    }
 }
@@ -0,0 +1,131 @@
+use std::any::Any;
+
+use super::*;
+
+/// Registers a file as a table. Statements like this are run as soon as they are
+/// planned and don't produce any rows.
+pub struct CreateExternalTable {
+    name: String,
+    schema: Arc<Schema>,
+    location: String,
+    has_header: bool,
+    delimiter: u8,
+    if_not_exists: bool,
+}
+
+impl LogicalPlan for CreateExternalTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> Arc<Schema> {
+        Arc::new(Schema::empty())
+    }
+
+    fn children(&self) -> Vec<Arc<dyn LogicalPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(&self, _children: Vec<Arc<dyn LogicalPlan>>) -> Arc<dyn LogicalPlan> {
+        Arc::new(CreateExternalTable {
+            name: self.name.clone(),
+            schema: Arc::clone(&self.schema),
+            location: self.location.clone(),
+            ..*self
+        })
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "CreateExternalTable: {}, location={}",
+            self.name, self.location
+        )
+    }
+}
+
+impl CreateExternalTable {
+    pub fn new(
+        name: String,
+        schema: Arc<Schema>,
+        location: String,
+        has_header: bool,
+        delimiter: u8,
+        if_not_exists: bool,
+    ) -> CreateExternalTable {
+        CreateExternalTable {
+            name,
+            schema,
+            location,
+            has_header,
+            delimiter,
+            if_not_exists,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The schema of the table being created.
+    pub fn table_schema(&self) -> Arc<Schema> {
+        Arc::clone(&self.schema)
+    }
+
+    pub fn location(&self) -> &str {
+        &self.location
+    }
+
+    pub fn has_header(&self) -> bool {
+        self.has_header
+    }
+
+    pub fn delimiter(&self) -> u8 {
+        self.delimiter
+    }
+
+    pub fn if_not_exists(&self) -> bool {
+        self.if_not_exists
+    }
+}
+
+/// Removes a table from the catalog.
+pub struct DropTable {
+    name: String,
+    if_exists: bool,
+}
+
+impl LogicalPlan for DropTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> Arc<Schema> {
+        Arc::new(Schema::empty())
+    }
+
+    fn children(&self) -> Vec<Arc<dyn LogicalPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(&self, _children: Vec<Arc<dyn LogicalPlan>>) -> Arc<dyn LogicalPlan> {
+        Arc::new(DropTable::new(self.name.clone(), self.if_exists))
+    }
+
+    fn to_string(&self) -> String {
+        format!("DropTable: {}", self.name)
+    }
+}
+
+impl DropTable {
+    pub fn new(name: String, if_exists: bool) -> DropTable {
+        DropTable { name, if_exists }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn if_exists(&self) -> bool {
+        self.if_exists
+    }
+}
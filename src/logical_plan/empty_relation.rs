@@ -0,0 +1,36 @@
+use std::any::Any;
+
+use super::*;
+
+/// A relation that produces no rows, e.g. the result of a filter that can never match.
+pub struct EmptyRelation {
+    schema: Arc<Schema>,
+}
+
+impl LogicalPlan for EmptyRelation {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> Arc<Schema> {
+        Arc::clone(&self.schema)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn LogicalPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(&self, _children: Vec<Arc<dyn LogicalPlan>>) -> Arc<dyn LogicalPlan> {
+        Arc::new(EmptyRelation::new(Arc::clone(&self.schema)))
+    }
+
+    fn to_string(&self) -> String {
+        "EmptyRelation".to_owned()
+    }
+}
+
+impl EmptyRelation {
+    pub fn new(schema: Arc<Schema>) -> EmptyRelation {
+        EmptyRelation { schema }
+    }
+}
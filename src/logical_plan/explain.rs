@@ -0,0 +1,93 @@
+use arrow::datatypes::{DataType, Field};
+use std::any::Any;
+
+use super::*;
+
+/// The text of a plan at one stage of planning, e.g. before or after optimization.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StringifiedPlan {
+    pub plan_type: String,
+    pub plan: String,
+}
+
+impl StringifiedPlan {
+    pub fn new(plan_type: &str, plan: String) -> StringifiedPlan {
+        StringifiedPlan {
+            plan_type: plan_type.to_owned(),
+            plan,
+        }
+    }
+}
+
+/// Describes how its input would be run instead of running it. The output has one row
+/// per stage of planning, with the stage in `plan_type` and the plan text in `plan`.
+///
+/// The input is kept as a child so that the optimizer rewrites it like any other plan,
+/// while `stringified_plans` remembers what it looked like when the node was created.
+/// With `analyze` set the input is run as well and the physical plan is annotated
+/// with what each operator did.
+pub struct Explain {
+    input: Arc<dyn LogicalPlan>,
+    analyze: bool,
+    stringified_plans: Vec<StringifiedPlan>,
+    schema: Arc<Schema>,
+}
+
+impl LogicalPlan for Explain {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> Arc<Schema> {
+        Arc::clone(&self.schema)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn LogicalPlan>> {
+        vec![Arc::clone(&self.input)]
+    }
+
+    fn with_new_children(&self, children: Vec<Arc<dyn LogicalPlan>>) -> Arc<dyn LogicalPlan> {
+        Arc::new(Explain {
+            input: Arc::clone(&children[0]),
+            analyze: self.analyze,
+            stringified_plans: self.stringified_plans.clone(),
+            schema: Arc::clone(&self.schema),
+        })
+    }
+
+    fn to_string(&self) -> String {
+        if self.analyze {
+            "Explain: analyze".to_owned()
+        } else {
+            "Explain".to_owned()
+        }
+    }
+}
+
+impl Explain {
+    /// Explains `input`, recording its current form as the `logical_plan` stage.
+    pub fn new(input: Arc<dyn LogicalPlan>, analyze: bool) -> Explain {
+        let stringified_plans = vec![StringifiedPlan::new("logical_plan", input.format())];
+        Explain {
+            input,
+            analyze,
+            stringified_plans,
+            schema: Arc::new(Schema::new(vec![
+                Field::new("plan_type", DataType::Utf8, false),
+                Field::new("plan", DataType::Utf8, false),
+            ])),
+        }
+    }
+
+    pub fn input(&self) -> Arc<dyn LogicalPlan> {
+        Arc::clone(&self.input)
+    }
+
+    pub fn analyze(&self) -> bool {
+        self.analyze
+    }
+
+    pub fn stringified_plans(&self) -> &[StringifiedPlan] {
+        &self.stringified_plans
+    }
+}
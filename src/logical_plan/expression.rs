@@ -1,18 +1,33 @@
 use super::*;
 
 use arrow::datatypes::{DataType, Field};
-use std::{panic, sync::Arc};
+use std::{any::Any, panic, sync::Arc};
 
-pub trait LogicalExpression {
+pub trait LogicalExpression: Send + Sync {
+    /// Returns the expression as `Any` so that it can be downcast to its concrete type.
+    fn as_any(&self) -> &dyn Any;
     fn to_field(&self, input: Arc<dyn LogicalPlan>) -> Field;
+    /// Returns the expressions this expression is computed from.
+    fn children(&self) -> Vec<Arc<dyn LogicalExpression>>;
+    /// Returns a copy of this expression computed from `children` instead of its current
+    /// inputs.
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn LogicalExpression>>,
+    ) -> Arc<dyn LogicalExpression>;
     fn to_string(&self) -> String;
 }
 
+#[derive(Clone)]
 pub struct ColumnExpression {
     name: String,
 }
 
 impl LogicalExpression for ColumnExpression {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn to_field(&self, input: Arc<dyn LogicalPlan>) -> Field {
         let schema = input.schema();
         let matching_fields: Vec<&Field> = schema
@@ -28,6 +43,17 @@ impl LogicalExpression for ColumnExpression {
         (*matching_fields[0]).clone()
     }
 
+    fn children(&self) -> Vec<Arc<dyn LogicalExpression>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        &self,
+        _children: Vec<Arc<dyn LogicalExpression>>,
+    ) -> Arc<dyn LogicalExpression> {
+        Arc::new(self.clone())
+    }
+
     fn to_string(&self) -> String {
         self.name.clone()
     }
@@ -37,17 +63,37 @@ impl ColumnExpression {
     pub fn new(name: String) -> ColumnExpression {
         ColumnExpression { name }
     }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
 }
 
+#[derive(Clone)]
 pub struct LiteralStringExpression {
     val: String,
 }
 
 impl LogicalExpression for LiteralStringExpression {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn to_field(&self, _: Arc<dyn LogicalPlan>) -> Field {
         Field::new(self.val.as_str(), DataType::Utf8, false)
     }
 
+    fn children(&self) -> Vec<Arc<dyn LogicalExpression>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        &self,
+        _children: Vec<Arc<dyn LogicalExpression>>,
+    ) -> Arc<dyn LogicalExpression> {
+        Arc::new(self.clone())
+    }
+
     fn to_string(&self) -> String {
         format!("'{}'", self.val)
     }
@@ -57,17 +103,37 @@ impl LiteralStringExpression {
     pub fn new(val: String) -> LiteralStringExpression {
         LiteralStringExpression { val }
     }
+
+    pub fn val(&self) -> &str {
+        &self.val
+    }
 }
 
+#[derive(Clone)]
 pub struct LiteralI64Expression {
     val: i64,
 }
 
 impl LogicalExpression for LiteralI64Expression {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn to_field(&self, _: Arc<dyn LogicalPlan>) -> Field {
         Field::new(format!("{}", self.val).as_str(), DataType::Int64, false)
     }
 
+    fn children(&self) -> Vec<Arc<dyn LogicalExpression>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        &self,
+        _children: Vec<Arc<dyn LogicalExpression>>,
+    ) -> Arc<dyn LogicalExpression> {
+        Arc::new(self.clone())
+    }
+
     fn to_string(&self) -> String {
         format!("{}", self.val)
     }
@@ -77,6 +143,275 @@ impl LiteralI64Expression {
     pub fn new(val: i64) -> LiteralI64Expression {
         LiteralI64Expression { val }
     }
+
+    pub fn val(&self) -> i64 {
+        self.val
+    }
+}
+
+#[derive(Clone)]
+pub struct LiteralBooleanExpression {
+    val: bool,
+}
+
+impl LogicalExpression for LiteralBooleanExpression {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn to_field(&self, _: Arc<dyn LogicalPlan>) -> Field {
+        Field::new(format!("{}", self.val).as_str(), DataType::Boolean, false)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn LogicalExpression>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        &self,
+        _children: Vec<Arc<dyn LogicalExpression>>,
+    ) -> Arc<dyn LogicalExpression> {
+        Arc::new(self.clone())
+    }
+
+    fn to_string(&self) -> String {
+        format!("{}", self.val)
+    }
+}
+
+impl LiteralBooleanExpression {
+    pub fn new(val: bool) -> LiteralBooleanExpression {
+        LiteralBooleanExpression { val }
+    }
+
+    pub fn val(&self) -> bool {
+        self.val
+    }
+}
+
+/// A parameter of a prepared statement, such as `$1` or `$id`, that is replaced by a
+/// value each time the statement runs. The type is `None` until it has been inferred
+/// from the expression the parameter is used in.
+#[derive(Clone)]
+pub struct PlaceholderExpression {
+    id: String,
+    data_type: Option<DataType>,
+}
+
+impl LogicalExpression for PlaceholderExpression {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn to_field(&self, _: Arc<dyn LogicalPlan>) -> Field {
+        let data_type = self.data_type.clone().unwrap_or(DataType::Null);
+        Field::new(self.id.as_str(), data_type, true)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn LogicalExpression>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        &self,
+        _children: Vec<Arc<dyn LogicalExpression>>,
+    ) -> Arc<dyn LogicalExpression> {
+        Arc::new(self.clone())
+    }
+
+    fn to_string(&self) -> String {
+        self.id.clone()
+    }
+}
+
+impl PlaceholderExpression {
+    pub fn new(id: String, data_type: Option<DataType>) -> PlaceholderExpression {
+        PlaceholderExpression { id, data_type }
+    }
+
+    /// The name of the parameter, including the `$`.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn data_type(&self) -> Option<&DataType> {
+        self.data_type.as_ref()
+    }
+}
+
+/// Negates a boolean expression.
+#[derive(Clone)]
+pub struct NotExpression {
+    expr: Arc<dyn LogicalExpression>,
+}
+
+impl LogicalExpression for NotExpression {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn to_field(&self, _: Arc<dyn LogicalPlan>) -> Field {
+        Field::new("not", DataType::Boolean, false)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn LogicalExpression>> {
+        vec![Arc::clone(&self.expr)]
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn LogicalExpression>>,
+    ) -> Arc<dyn LogicalExpression> {
+        Arc::new(NotExpression::new(Arc::clone(&children[0])))
+    }
+
+    fn to_string(&self) -> String {
+        format!("NOT {}", self.expr.to_string())
+    }
+}
+
+impl NotExpression {
+    pub fn new(expr: Arc<dyn LogicalExpression>) -> NotExpression {
+        NotExpression { expr }
+    }
+
+    pub fn expr(&self) -> Arc<dyn LogicalExpression> {
+        Arc::clone(&self.expr)
+    }
+}
+
+/// Gives the result of an expression a new name.
+#[derive(Clone)]
+pub struct AliasExpression {
+    expr: Arc<dyn LogicalExpression>,
+    alias: String,
+}
+
+impl LogicalExpression for AliasExpression {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn to_field(&self, input: Arc<dyn LogicalPlan>) -> Field {
+        let field = self.expr.to_field(input);
+        Field::new(
+            self.alias.as_str(),
+            field.data_type().clone(),
+            field.is_nullable(),
+        )
+    }
+
+    fn children(&self) -> Vec<Arc<dyn LogicalExpression>> {
+        vec![Arc::clone(&self.expr)]
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn LogicalExpression>>,
+    ) -> Arc<dyn LogicalExpression> {
+        Arc::new(AliasExpression {
+            expr: Arc::clone(&children[0]),
+            alias: self.alias.clone(),
+        })
+    }
+
+    fn to_string(&self) -> String {
+        format!("{} AS {}", self.expr.to_string(), self.alias)
+    }
+}
+
+impl AliasExpression {
+    pub fn new(expr: Arc<dyn LogicalExpression>, alias: String) -> AliasExpression {
+        AliasExpression { expr, alias }
+    }
+
+    pub fn expr(&self) -> Arc<dyn LogicalExpression> {
+        Arc::clone(&self.expr)
+    }
+
+    pub fn alias(&self) -> &str {
+        &self.alias
+    }
+}
+
+/// Orders rows by the value of an expression, for a `Sort`. Nulls come last unless
+/// `nulls_first` is set.
+#[derive(Clone)]
+pub struct SortExpression {
+    expr: Arc<dyn LogicalExpression>,
+    asc: bool,
+    nulls_first: bool,
+}
+
+impl LogicalExpression for SortExpression {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn to_field(&self, input: Arc<dyn LogicalPlan>) -> Field {
+        self.expr.to_field(input)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn LogicalExpression>> {
+        vec![Arc::clone(&self.expr)]
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn LogicalExpression>>,
+    ) -> Arc<dyn LogicalExpression> {
+        Arc::new(SortExpression {
+            expr: Arc::clone(&children[0]),
+            ..self.clone()
+        })
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{} {} NULLS {}",
+            self.expr.to_string(),
+            if self.asc { "ASC" } else { "DESC" },
+            if self.nulls_first { "FIRST" } else { "LAST" }
+        )
+    }
+}
+
+impl SortExpression {
+    pub fn new(expr: Arc<dyn LogicalExpression>, asc: bool, nulls_first: bool) -> SortExpression {
+        SortExpression {
+            expr,
+            asc,
+            nulls_first,
+        }
+    }
+
+    /// Returns a copy of this expression that puts nulls before every other value.
+    pub fn nulls_first(&self) -> Arc<SortExpression> {
+        Arc::new(SortExpression {
+            nulls_first: true,
+            ..self.clone()
+        })
+    }
+
+    /// Returns a copy of this expression that puts nulls after every other value.
+    pub fn nulls_last(&self) -> Arc<SortExpression> {
+        Arc::new(SortExpression {
+            nulls_first: false,
+            ..self.clone()
+        })
+    }
+
+    pub fn expr(&self) -> Arc<dyn LogicalExpression> {
+        Arc::clone(&self.expr)
+    }
+
+    pub fn is_asc(&self) -> bool {
+        self.asc
+    }
+
+    pub fn is_nulls_first(&self) -> bool {
+        self.nulls_first
+    }
 }
 
 pub trait BinaryExpression: LogicalExpression {
@@ -86,6 +421,7 @@ pub trait BinaryExpression: LogicalExpression {
     fn r(&self) -> Arc<dyn LogicalExpression>;
 }
 
+#[derive(Clone)]
 pub struct BooleanExpression {
     name: String,
     op: String,
@@ -94,17 +430,36 @@ pub struct BooleanExpression {
 }
 
 impl LogicalExpression for BooleanExpression {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn to_field(&self, _: Arc<dyn LogicalPlan>) -> Field {
-        Field::new(format!("{}", self.name).as_str(), DataType::Boolean, false)
+        Field::new(self.name.as_str(), DataType::Boolean, false)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn LogicalExpression>> {
+        vec![Arc::clone(&self.l), Arc::clone(&self.r)]
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn LogicalExpression>>,
+    ) -> Arc<dyn LogicalExpression> {
+        Arc::new(BooleanExpression {
+            l: Arc::clone(&children[0]),
+            r: Arc::clone(&children[1]),
+            ..self.clone()
+        })
     }
 
     fn to_string(&self) -> String {
-        format!(
-            "{}{}{}",
-            self.l().to_string(),
-            self.op(),
-            self.r().to_string()
-        )
+        // Keyword operators need surrounding spaces to stay readable.
+        let op = match self.name.as_str() {
+            "and" | "or" => format!(" {} ", self.op),
+            _ => self.op.clone(),
+        };
+        format!("{}{}{}", self.l().to_string(), op, self.r().to_string())
     }
 }
 
@@ -190,6 +545,7 @@ impl BooleanExpression {
     }
 }
 
+#[derive(Clone)]
 pub struct MathExpression {
     name: String,
     op: String,
@@ -198,14 +554,33 @@ pub struct MathExpression {
 }
 
 impl LogicalExpression for MathExpression {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn to_field(&self, input: Arc<dyn LogicalPlan>) -> Field {
         Field::new(
-            format!("{}", self.name).as_str(),
+            self.name.as_str(),
             self.l.to_field(input).data_type().clone(),
             false,
         )
     }
 
+    fn children(&self) -> Vec<Arc<dyn LogicalExpression>> {
+        vec![Arc::clone(&self.l), Arc::clone(&self.r)]
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn LogicalExpression>>,
+    ) -> Arc<dyn LogicalExpression> {
+        Arc::new(MathExpression {
+            l: Arc::clone(&children[0]),
+            r: Arc::clone(&children[1]),
+            ..self.clone()
+        })
+    }
+
     fn to_string(&self) -> String {
         format!(
             "{} {} {}",
@@ -280,22 +655,51 @@ impl MathExpression {
     }
 }
 
+#[derive(Clone)]
 pub struct AggregateExpression {
     name: String,
     expr: Arc<dyn LogicalExpression>,
+    alias: Option<String>,
 }
 
 impl LogicalExpression for AggregateExpression {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn to_field(&self, input: Arc<dyn LogicalPlan>) -> Field {
+        // Averages are always fractional, so they don't share the type of their input.
+        let data_type = match self.name.as_str() {
+            "avg" => DataType::Float64,
+            _ => self.expr.to_field(input).data_type().clone(),
+        };
         Field::new(
-            self.name.clone().as_str(),
-            self.expr.to_field(input).data_type().clone(),
+            self.alias.as_deref().unwrap_or(self.name.as_str()),
+            data_type,
             false,
         )
     }
 
+    fn children(&self) -> Vec<Arc<dyn LogicalExpression>> {
+        vec![Arc::clone(&self.expr)]
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn LogicalExpression>>,
+    ) -> Arc<dyn LogicalExpression> {
+        Arc::new(AggregateExpression {
+            expr: Arc::clone(&children[0]),
+            ..self.clone()
+        })
+    }
+
     fn to_string(&self) -> String {
-        format!("{}({})", self.name, self.expr.to_string())
+        let s = format!("{}({})", self.name, self.expr.to_string());
+        match &self.alias {
+            Some(alias) if *alias != s => format!("{} AS {}", s, alias),
+            _ => s,
+        }
     }
 }
 
@@ -304,24 +708,271 @@ impl AggregateExpression {
         AggregateExpression {
             name: "sum".to_owned(),
             expr: input,
+            alias: None,
         }
     }
     pub fn min(input: Arc<dyn LogicalExpression>) -> AggregateExpression {
         AggregateExpression {
             name: "min".to_owned(),
             expr: input,
+            alias: None,
         }
     }
     pub fn max(input: Arc<dyn LogicalExpression>) -> AggregateExpression {
         AggregateExpression {
             name: "max".to_owned(),
             expr: input,
+            alias: None,
         }
     }
     pub fn avg(input: Arc<dyn LogicalExpression>) -> AggregateExpression {
         AggregateExpression {
             name: "avg".to_owned(),
             expr: input,
+            alias: None,
+        }
+    }
+    /// The only value of `input`, failing when there is more than one. Scalar
+    /// subqueries that don't aggregate are planned with it.
+    pub fn single(input: Arc<dyn LogicalExpression>) -> AggregateExpression {
+        AggregateExpression {
+            name: "single".to_owned(),
+            expr: input,
+            alias: None,
+        }
+    }
+
+    /// Names the output of this aggregate `alias` instead of the name of its function.
+    pub fn with_alias(self, alias: String) -> AggregateExpression {
+        AggregateExpression {
+            alias: Some(alias),
+            ..self
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn expr(&self) -> Arc<dyn LogicalExpression> {
+        Arc::clone(&self.expr)
+    }
+}
+
+/// A column of the query that encloses a subquery, referenced from inside the
+/// subquery. The column can't be looked up in the subquery's own input, so the
+/// expression carries its field.
+#[derive(Clone)]
+pub struct OuterColumnExpression {
+    field: Field,
+}
+
+impl LogicalExpression for OuterColumnExpression {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn to_field(&self, _: Arc<dyn LogicalPlan>) -> Field {
+        self.field.clone()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn LogicalExpression>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        &self,
+        _children: Vec<Arc<dyn LogicalExpression>>,
+    ) -> Arc<dyn LogicalExpression> {
+        Arc::new(self.clone())
+    }
+
+    fn to_string(&self) -> String {
+        format!("outer({})", self.field.name())
+    }
+}
+
+impl OuterColumnExpression {
+    pub fn new(field: Field) -> OuterColumnExpression {
+        OuterColumnExpression { field }
+    }
+
+    pub fn name(&self) -> &str {
+        self.field.name()
+    }
+}
+
+/// Formats a subquery on a single line, listing its nodes from the top down.
+fn subquery_to_string(plan: &Arc<dyn LogicalPlan>) -> String {
+    fn visit(plan: &Arc<dyn LogicalPlan>, nodes: &mut Vec<String>) {
+        nodes.push(LogicalPlan::to_string(plan.as_ref()));
+        for child in plan.children() {
+            visit(&child, nodes);
+        }
+    }
+    let mut nodes = vec![];
+    visit(plan, &mut nodes);
+    nodes.join(" -> ")
+}
+
+/// Tests whether the value of `expr` is one of the values produced by a subquery with a
+/// single column.
+#[derive(Clone)]
+pub struct InSubqueryExpression {
+    expr: Arc<dyn LogicalExpression>,
+    subquery: Arc<dyn LogicalPlan>,
+    negated: bool,
+}
+
+impl LogicalExpression for InSubqueryExpression {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn to_field(&self, _: Arc<dyn LogicalPlan>) -> Field {
+        Field::new("in", DataType::Boolean, false)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn LogicalExpression>> {
+        vec![Arc::clone(&self.expr)]
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn LogicalExpression>>,
+    ) -> Arc<dyn LogicalExpression> {
+        Arc::new(InSubqueryExpression {
+            expr: Arc::clone(&children[0]),
+            ..self.clone()
+        })
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{} {}IN ({})",
+            self.expr.to_string(),
+            if self.negated { "NOT " } else { "" },
+            subquery_to_string(&self.subquery)
+        )
+    }
+}
+
+impl InSubqueryExpression {
+    pub fn new(
+        expr: Arc<dyn LogicalExpression>,
+        subquery: Arc<dyn LogicalPlan>,
+        negated: bool,
+    ) -> InSubqueryExpression {
+        InSubqueryExpression {
+            expr,
+            subquery,
+            negated,
         }
     }
+
+    pub fn expr(&self) -> Arc<dyn LogicalExpression> {
+        Arc::clone(&self.expr)
+    }
+
+    pub fn subquery(&self) -> Arc<dyn LogicalPlan> {
+        Arc::clone(&self.subquery)
+    }
+
+    pub fn negated(&self) -> bool {
+        self.negated
+    }
+}
+
+/// Tests whether a subquery produces any rows.
+#[derive(Clone)]
+pub struct ExistsExpression {
+    subquery: Arc<dyn LogicalPlan>,
+    negated: bool,
+}
+
+impl LogicalExpression for ExistsExpression {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn to_field(&self, _: Arc<dyn LogicalPlan>) -> Field {
+        Field::new("exists", DataType::Boolean, false)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn LogicalExpression>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        &self,
+        _children: Vec<Arc<dyn LogicalExpression>>,
+    ) -> Arc<dyn LogicalExpression> {
+        Arc::new(self.clone())
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{}EXISTS ({})",
+            if self.negated { "NOT " } else { "" },
+            subquery_to_string(&self.subquery)
+        )
+    }
+}
+
+impl ExistsExpression {
+    pub fn new(subquery: Arc<dyn LogicalPlan>, negated: bool) -> ExistsExpression {
+        ExistsExpression { subquery, negated }
+    }
+
+    pub fn subquery(&self) -> Arc<dyn LogicalPlan> {
+        Arc::clone(&self.subquery)
+    }
+
+    pub fn negated(&self) -> bool {
+        self.negated
+    }
+}
+
+/// The value of a subquery that produces a single column and at most one row, or null
+/// when it produces no rows.
+#[derive(Clone)]
+pub struct ScalarSubqueryExpression {
+    subquery: Arc<dyn LogicalPlan>,
+}
+
+impl LogicalExpression for ScalarSubqueryExpression {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn to_field(&self, _: Arc<dyn LogicalPlan>) -> Field {
+        let schema = self.subquery.schema();
+        let field = schema.field(0);
+        Field::new(field.name(), field.data_type().clone(), true)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn LogicalExpression>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        &self,
+        _children: Vec<Arc<dyn LogicalExpression>>,
+    ) -> Arc<dyn LogicalExpression> {
+        Arc::new(self.clone())
+    }
+
+    fn to_string(&self) -> String {
+        format!("({})", subquery_to_string(&self.subquery))
+    }
+}
+
+impl ScalarSubqueryExpression {
+    pub fn new(subquery: Arc<dyn LogicalPlan>) -> ScalarSubqueryExpression {
+        ScalarSubqueryExpression { subquery }
+    }
+
+    pub fn subquery(&self) -> Arc<dyn LogicalPlan> {
+        Arc::clone(&self.subquery)
+    }
 }
@@ -0,0 +1,511 @@
+use arrow::datatypes::Field;
+use std::any::Any;
+
+use super::expression::{ColumnExpression, LogicalExpression};
+use super::subquery_alias::{field_name, relation_name, resolve_columns};
+use super::*;
+use crate::core::error::{QrustError, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JoinType {
+    /// Pairs of matching rows.
+    Inner,
+    /// Pairs of matching rows, plus the left rows without a match paired with nulls.
+    Left,
+    /// Pairs of matching rows, plus the right rows without a match paired with nulls.
+    Right,
+    /// Pairs of matching rows, plus the rows of either side without a match paired
+    /// with nulls.
+    Full,
+    /// The left rows that have at least one match.
+    Semi,
+    /// The left rows that have no match.
+    Anti,
+}
+
+impl JoinType {
+    /// Whether the output includes the columns of the right input.
+    pub fn includes_right(&self) -> bool {
+        !matches!(self, JoinType::Semi | JoinType::Anti)
+    }
+}
+
+impl fmt::Display for JoinType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Combines the rows of two inputs whose key columns are equal and for which the
+/// optional filter holds. Rows with a null in any key column never match.
+///
+/// A column that both inputs have is qualified in the joined rows with the relation of
+/// its input, e.g. `users.id` and `orders.id` when the inputs are aliased as `users` and
+/// `orders`.
+pub struct Join {
+    left: Arc<dyn LogicalPlan>,
+    right: Arc<dyn LogicalPlan>,
+    join_type: JoinType,
+    on: Vec<(String, String)>,
+    /// Evaluated on the joined rows, with the columns of `joined_schema`.
+    filter: Option<Arc<dyn LogicalExpression>>,
+    /// The names of the columns that both inputs had when the join was created. They
+    /// stay qualified when the inputs are rewritten, so that the output keeps its names.
+    ambiguous: Vec<String>,
+    left_relation: Option<String>,
+    right_relation: Option<String>,
+    joined_schema: Arc<Schema>,
+    schema: Arc<Schema>,
+}
+
+impl LogicalPlan for Join {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> Arc<Schema> {
+        Arc::clone(&self.schema)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn LogicalPlan>> {
+        vec![Arc::clone(&self.left), Arc::clone(&self.right)]
+    }
+
+    fn with_new_children(&self, children: Vec<Arc<dyn LogicalPlan>>) -> Arc<dyn LogicalPlan> {
+        self.try_with_new_children(children)
+            .unwrap_or_else(|e| panic!("Can't replace the inputs of a join: {}", e))
+    }
+
+    fn try_with_new_children(
+        &self,
+        children: Vec<Arc<dyn LogicalPlan>>,
+    ) -> Result<Arc<dyn LogicalPlan>> {
+        // The new inputs must still have the key columns and the columns of the filter.
+        let join = Join::build(
+            Arc::clone(&children[0]),
+            Arc::clone(&children[1]),
+            self.join_type,
+            self.on.clone(),
+            self.filter.clone(),
+            self.ambiguous.clone(),
+            (self.left_relation.clone(), self.right_relation.clone()),
+        )?;
+        Ok(Arc::new(join))
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn LogicalExpression>> {
+        self.filter.iter().cloned().collect()
+    }
+
+    fn to_string(&self) -> String {
+        let mut s = format!(
+            "Join: joinType={}, on=[{}]",
+            self.join_type,
+            self.on
+                .iter()
+                .map(|(l, r)| format!("{}={}", l, r))
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+        if let Some(filter) = &self.filter {
+            s.push_str(&format!(", filter={}", filter.to_string()));
+        }
+        s
+    }
+}
+
+impl Join {
+    /// Joins `left` and `right` on the pairs of columns in `on`, each made of a column of
+    /// `left` and a column of `right`. `filter` is evaluated on the joined rows, which
+    /// only match when it holds.
+    pub fn try_new(
+        left: Arc<dyn LogicalPlan>,
+        right: Arc<dyn LogicalPlan>,
+        join_type: JoinType,
+        on: Vec<(String, String)>,
+        filter: Option<Arc<dyn LogicalExpression>>,
+    ) -> Result<Join> {
+        let right_schema = right.schema();
+        let ambiguous = left
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .filter(|name| right_schema.field_with_name(name).is_ok())
+            .collect();
+        let relations = (relation_name(&left.schema()), relation_name(&right_schema));
+        Join::build(left, right, join_type, on, filter, ambiguous, relations)
+    }
+
+    fn build(
+        left: Arc<dyn LogicalPlan>,
+        right: Arc<dyn LogicalPlan>,
+        join_type: JoinType,
+        on: Vec<(String, String)>,
+        filter: Option<Arc<dyn LogicalExpression>>,
+        ambiguous: Vec<String>,
+        (left_relation, right_relation): (Option<String>, Option<String>),
+    ) -> Result<Join> {
+        let left_schema = left.schema();
+        let right_schema = right.schema();
+        // Keys may be qualified with the relation of their side.
+        let on: Vec<(String, String)> = on
+            .into_iter()
+            .map(|(l, r)| {
+                (
+                    field_name(&left_schema, &l).unwrap_or(l),
+                    field_name(&right_schema, &r).unwrap_or(r),
+                )
+            })
+            .collect();
+        for (l, r) in &on {
+            let l = left_schema.field_with_name(l).map_err(|_| {
+                QrustError::Plan(format!("No field named {} on the left side of the join", l))
+            })?;
+            let r = right_schema.field_with_name(r).map_err(|_| {
+                QrustError::Plan(format!(
+                    "No field named {} on the right side of the join",
+                    r
+                ))
+            })?;
+            if l.data_type() != r.data_type() {
+                return Err(QrustError::Plan(format!(
+                    "Can't join {} of type {:?} with {} of type {:?}",
+                    l.name(),
+                    l.data_type(),
+                    r.name(),
+                    r.data_type()
+                )));
+            }
+        }
+
+        // Columns of both sides can only be told apart by the names of their inputs.
+        let needs_qualifiers = join_type.includes_right() || filter.is_some();
+        if needs_qualifiers && !ambiguous.is_empty() {
+            match (&left_relation, &right_relation) {
+                (Some(l), Some(r)) if l != r => {}
+                _ => {
+                    return Err(QrustError::Plan(format!(
+                        "Both sides of the join have a column named {}",
+                        ambiguous[0]
+                    )))
+                }
+            }
+        }
+        // Fields stay columns of the relation of their side.
+        let qualify = |relation: &Option<String>, field: &Field, nullable: bool| {
+            let name = match relation {
+                Some(relation) if ambiguous.contains(field.name()) => {
+                    format!("{}.{}", relation, field.name())
+                }
+                _ => field.name().clone(),
+            };
+            let mut qualified = Field::new(
+                &name,
+                field.data_type().clone(),
+                field.is_nullable() || nullable,
+            );
+            qualified.set_metadata(field.metadata().clone());
+            qualified
+        };
+
+        let (left_nullable, right_nullable) = match join_type {
+            JoinType::Left => (false, true),
+            JoinType::Right => (true, false),
+            JoinType::Full => (true, true),
+            JoinType::Inner | JoinType::Semi | JoinType::Anti => (false, false),
+        };
+        let mut joined: Vec<Field> = vec![];
+        let mut fields: Vec<Field> = vec![];
+        for field in left_schema.fields() {
+            joined.push(qualify(&left_relation, field, false));
+            fields.push(if join_type.includes_right() {
+                qualify(&left_relation, field, left_nullable)
+            } else {
+                field.clone()
+            });
+        }
+        for field in right_schema.fields() {
+            joined.push(qualify(&right_relation, field, false));
+            if join_type.includes_right() {
+                fields.push(qualify(&right_relation, field, right_nullable));
+            }
+        }
+
+        let joined = Schema::new(joined);
+        let filter = filter.map(|filter| resolve_columns(&filter, &joined));
+        if let Some(filter) = &filter {
+            let mut columns = vec![];
+            find_columns(filter, &mut columns);
+            if let Some(missing) = columns.iter().find(|c| joined.field_with_name(c).is_err()) {
+                return Err(QrustError::Plan(format!(
+                    "No field named {} in the joined rows",
+                    missing
+                )));
+            }
+        }
+
+        Ok(Join {
+            left,
+            right,
+            join_type,
+            on,
+            filter,
+            ambiguous,
+            left_relation,
+            right_relation,
+            joined_schema: Arc::new(joined),
+            schema: Arc::new(Schema::new(fields)),
+        })
+    }
+
+    pub fn left(&self) -> Arc<dyn LogicalPlan> {
+        Arc::clone(&self.left)
+    }
+
+    pub fn right(&self) -> Arc<dyn LogicalPlan> {
+        Arc::clone(&self.right)
+    }
+
+    pub fn join_type(&self) -> JoinType {
+        self.join_type
+    }
+
+    pub fn on(&self) -> &[(String, String)] {
+        &self.on
+    }
+
+    pub fn filter(&self) -> Option<Arc<dyn LogicalExpression>> {
+        self.filter.clone()
+    }
+
+    /// The schema of a left row followed by a right row, which the filter is evaluated
+    /// on. It is the output schema of an inner join.
+    pub fn joined_schema(&self) -> Arc<Schema> {
+        Arc::clone(&self.joined_schema)
+    }
+
+    /// The name the columns of the left input are qualified with when both inputs have
+    /// them.
+    pub fn left_relation(&self) -> Option<&str> {
+        self.left_relation.as_deref()
+    }
+
+    /// The name the columns of the right input are qualified with when both inputs have
+    /// them.
+    pub fn right_relation(&self) -> Option<&str> {
+        self.right_relation.as_deref()
+    }
+
+    /// The name that the column `name` of the left input has in the joined rows.
+    pub fn left_column(&self, name: &str) -> String {
+        self.qualified(&self.left_relation, name)
+    }
+
+    /// The name that the column `name` of the right input has in the joined rows.
+    pub fn right_column(&self, name: &str) -> String {
+        self.qualified(&self.right_relation, name)
+    }
+
+    fn qualified(&self, relation: &Option<String>, name: &str) -> String {
+        match relation {
+            Some(relation) if self.ambiguous.iter().any(|a| a == name) => {
+                format!("{}.{}", relation, name)
+            }
+            _ => name.to_owned(),
+        }
+    }
+}
+
+fn find_columns(expr: &Arc<dyn LogicalExpression>, columns: &mut Vec<String>) {
+    if let Some(column) = expr.as_any().downcast_ref::<ColumnExpression>() {
+        columns.push(column.name().to_owned());
+    }
+    for child in expr.children() {
+        find_columns(&child, columns);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data_type::ArrowType;
+    use crate::core::execution_context::ExecutionContext;
+    use crate::core::helper::*;
+    use crate::logical_plan::subquery_alias::{qualifier, SubqueryAlias};
+
+    fn scan(path: &str, columns: Vec<(&str, ArrowType, bool)>) -> Arc<dyn LogicalPlan> {
+        ExecutionContext::new().csv(schema(columns), path).plan()
+    }
+
+    #[test]
+    fn join_schema() {
+        let left = scan(
+            "l.csv",
+            vec![
+                ("id", ArrowType::Int64Type, false),
+                ("a", ArrowType::StringType, false),
+            ],
+        );
+        let right = scan(
+            "r.csv",
+            vec![
+                ("rid", ArrowType::Int64Type, false),
+                ("b", ArrowType::StringType, false),
+            ],
+        );
+        let on = vec![("id".to_owned(), "rid".to_owned())];
+
+        let join = Join::try_new(
+            Arc::clone(&left),
+            Arc::clone(&right),
+            JoinType::Left,
+            on.clone(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            join.schema(),
+            schema(vec![
+                ("id", ArrowType::Int64Type, false),
+                ("a", ArrowType::StringType, false),
+                ("rid", ArrowType::Int64Type, true),
+                ("b", ArrowType::StringType, true),
+            ])
+        );
+        assert_eq!(join.to_string(), "Join: joinType=Left, on=[id=rid]");
+
+        let join = Join::try_new(
+            Arc::clone(&left),
+            Arc::clone(&right),
+            JoinType::Semi,
+            on,
+            None,
+        )
+        .unwrap();
+        assert_eq!(join.schema(), left.schema());
+
+        let err = Join::try_new(
+            Arc::clone(&left),
+            Arc::clone(&right),
+            JoinType::Inner,
+            vec![("a".to_owned(), "rid".to_owned())],
+            None,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Planning error: Can't join a of type Utf8 with rid of type Int64"
+        );
+        let err = Join::try_new(
+            Arc::clone(&left),
+            Arc::clone(&left),
+            JoinType::Inner,
+            vec![],
+            None,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Planning error: Both sides of the join have a column named id"
+        );
+    }
+
+    #[test]
+    fn qualified_names() {
+        let aliased = |alias: &str, columns| {
+            let path = format!("{}.csv", alias);
+            Arc::new(SubqueryAlias::new(scan(&path, columns), alias.to_owned()))
+                as Arc<dyn LogicalPlan>
+        };
+        let users = aliased(
+            "users",
+            vec![
+                ("id", ArrowType::Int64Type, false),
+                ("name", ArrowType::StringType, false),
+            ],
+        );
+        let orders = aliased(
+            "orders",
+            vec![
+                ("id", ArrowType::Int64Type, false),
+                ("uid", ArrowType::Int64Type, false),
+            ],
+        );
+        let on = vec![("id".to_owned(), "uid".to_owned())];
+
+        // Keys and the filter may refer to columns by their relation.
+        let join = Join::try_new(
+            Arc::clone(&users),
+            Arc::clone(&orders),
+            JoinType::Full,
+            vec![("users.id".to_owned(), "orders.uid".to_owned())],
+            Some(and(
+                gt(col("orders.id"), col("users.id")),
+                neq(col("users.name"), lit(&"")),
+            ) as _),
+        )
+        .unwrap();
+        let fields = join.schema();
+        let fields = fields
+            .fields()
+            .iter()
+            .map(|f| (f.name().as_str(), qualifier(f), f.is_nullable()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            vec![
+                ("users.id", Some("users"), true),
+                ("name", Some("users"), true),
+                ("orders.id", Some("orders"), true),
+                ("uid", Some("orders"), true),
+            ]
+        );
+        assert_eq!(
+            join.to_string(),
+            "Join: joinType=Full, on=[id=uid], filter=orders.id>users.id AND name!=''"
+        );
+        assert_eq!(join.left_column("id"), "users.id");
+        assert_eq!(join.right_column("uid"), "uid");
+
+        // Rewriting the inputs keeps the names of the output.
+        let rewritten = join.with_new_children(vec![Arc::clone(&users), orders.clone()]);
+        assert_eq!(rewritten.schema(), join.schema());
+        // Inputs without the key columns are rejected.
+        let err = join
+            .try_with_new_children(vec![Arc::clone(&users), Arc::clone(&users)])
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Planning error: No field named uid on the right side of the join"
+        );
+
+        // Semi joins only output the left columns, whose names don't change.
+        let join = Join::try_new(
+            Arc::clone(&users),
+            Arc::clone(&orders),
+            JoinType::Semi,
+            on.clone(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(join.schema(), users.schema());
+
+        let err = Join::try_new(
+            users,
+            orders,
+            JoinType::Inner,
+            on,
+            Some(gt(col("id"), lit(&1)) as _),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Planning error: No field named id in the joined rows"
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use std::any::Any;
+
+use super::*;
+
+/// Skips the first `skip` rows of its input and produces at most `fetch` of the rows
+/// after them, or all of them if `fetch` is `None`.
+pub struct Limit {
+    input: Arc<dyn LogicalPlan>,
+    skip: usize,
+    fetch: Option<usize>,
+}
+
+impl LogicalPlan for Limit {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> Arc<Schema> {
+        self.input.schema()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn LogicalPlan>> {
+        vec![Arc::clone(&self.input)]
+    }
+
+    fn with_new_children(&self, children: Vec<Arc<dyn LogicalPlan>>) -> Arc<dyn LogicalPlan> {
+        Arc::new(Limit::new(Arc::clone(&children[0]), self.skip, self.fetch))
+    }
+
+    fn to_string(&self) -> String {
+        match self.fetch {
+            Some(fetch) => format!("Limit: skip={}, fetch={}", self.skip, fetch),
+            None => format!("Limit: skip={}, fetch=None", self.skip),
+        }
+    }
+}
+
+impl Limit {
+    pub fn new(input: Arc<dyn LogicalPlan>, skip: usize, fetch: Option<usize>) -> Limit {
+        Limit { input, skip, fetch }
+    }
+
+    pub fn input(&self) -> Arc<dyn LogicalPlan> {
+        Arc::clone(&self.input)
+    }
+
+    pub fn skip(&self) -> usize {
+        self.skip
+    }
+
+    pub fn fetch(&self) -> Option<usize> {
+        self.fetch
+    }
+}
@@ -1,15 +1,40 @@
 use arrow::datatypes::Schema;
-use std::{fmt, sync::Arc};
+use std::{any::Any, fmt, sync::Arc};
+
+use crate::core::error::Result;
 
 pub mod aggregate;
+pub mod ddl;
+pub mod empty_relation;
+pub mod explain;
 pub mod expression;
+pub mod join;
+pub mod limit;
 pub mod projection;
 pub mod scan;
 pub mod selection;
+pub mod sort;
+pub mod subquery_alias;
 
-pub trait LogicalPlan {
+pub trait LogicalPlan: Send + Sync {
+    /// Returns the plan as `Any` so that it can be downcast to its concrete type.
+    fn as_any(&self) -> &dyn Any;
     fn schema(&self) -> Arc<Schema>;
     fn children(&self) -> Vec<Arc<dyn LogicalPlan>>;
+    /// Returns a copy of this plan that reads from `children` instead of its current inputs.
+    fn with_new_children(&self, children: Vec<Arc<dyn LogicalPlan>>) -> Arc<dyn LogicalPlan>;
+    /// Like `with_new_children`, but returns an error instead of panicking if the node
+    /// refers to columns that `children` don't produce.
+    fn try_with_new_children(
+        &self,
+        children: Vec<Arc<dyn LogicalPlan>>,
+    ) -> Result<Arc<dyn LogicalPlan>> {
+        Ok(self.with_new_children(children))
+    }
+    /// Returns the expressions this node evaluates, not including those of its children.
+    fn expressions(&self) -> Vec<Arc<dyn expression::LogicalExpression>> {
+        vec![]
+    }
     fn to_string(&self) -> String;
 
     fn format_helper(&self, indent: usize) -> String {
@@ -18,7 +43,7 @@ pub trait LogicalPlan {
             builder.push_str("  ");
         }
         builder.push_str(&self.to_string());
-        builder.push_str("\n");
+        builder.push('\n');
         for child in self.children() {
             builder.push_str(&child.format_helper(indent + 1));
         }
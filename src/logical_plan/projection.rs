@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{any::Any, sync::Arc};
 
 use super::expression::*;
 use super::*;
@@ -9,6 +9,10 @@ pub struct Projection {
 }
 
 impl LogicalPlan for Projection {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn schema(&self) -> Arc<Schema> {
         Arc::new(Schema::new(
             self.expr
@@ -22,6 +26,14 @@ impl LogicalPlan for Projection {
         vec![Arc::clone(&self.input)]
     }
 
+    fn with_new_children(&self, children: Vec<Arc<dyn LogicalPlan>>) -> Arc<dyn LogicalPlan> {
+        Arc::new(Projection::new(Arc::clone(&children[0]), self.expr.clone()))
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn LogicalExpression>> {
+        self.expr.clone()
+    }
+
     fn to_string(&self) -> String {
         format!(
             "Projection: {}",
@@ -38,4 +50,12 @@ impl Projection {
     pub fn new(input: Arc<dyn LogicalPlan>, expr: Vec<Arc<dyn LogicalExpression>>) -> Projection {
         Projection { input, expr }
     }
+
+    pub fn input(&self) -> Arc<dyn LogicalPlan> {
+        Arc::clone(&self.input)
+    }
+
+    pub fn expr(&self) -> &[Arc<dyn LogicalExpression>] {
+        &self.expr
+    }
 }
@@ -1,28 +1,67 @@
+use std::any::Any;
+
+use super::expression::LogicalExpression;
 use super::*;
 use crate::core::data_source::DataSource;
 
+#[derive(Clone)]
 pub struct Scan {
     path: String,
     schema: Arc<Schema>,
-    datasource: Box<dyn DataSource>,
+    datasource: Arc<dyn DataSource>,
     projection: Vec<String>,
+    filters: Vec<Arc<dyn LogicalExpression>>,
 }
 
 impl LogicalPlan for Scan {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn schema(&self) -> Arc<Schema> {
-        Arc::clone(&self.schema)
+        if self.projection.is_empty() {
+            return Arc::clone(&self.schema);
+        }
+        // Columns are read in schema order, whatever the order of the projection.
+        Arc::new(Schema::new(
+            self.schema
+                .fields()
+                .iter()
+                .filter(|f| self.projection.contains(f.name()))
+                .cloned()
+                .collect(),
+        ))
     }
 
     fn children(&self) -> Vec<Arc<dyn LogicalPlan>> {
         vec![]
     }
 
+    fn with_new_children(&self, _: Vec<Arc<dyn LogicalPlan>>) -> Arc<dyn LogicalPlan> {
+        Arc::new(self.clone())
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn LogicalExpression>> {
+        self.filters.clone()
+    }
+
     fn to_string(&self) -> String {
-        if self.projection.len() == 0 {
+        let mut s = if self.projection.is_empty() {
             format!("Scan: {}, projection=None", self.path)
         } else {
             format!("Scan: {}, projection={:?}", self.path, self.projection)
+        };
+        if !self.filters.is_empty() {
+            s.push_str(&format!(
+                ", filters=[{}]",
+                self.filters
+                    .iter()
+                    .map(|f| f.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ));
         }
+        s
     }
 }
 
@@ -30,7 +69,7 @@ impl Scan {
     pub fn new(
         path: String,
         schema: Arc<Schema>,
-        datasource: Box<dyn DataSource>,
+        datasource: Arc<dyn DataSource>,
         projection: Vec<String>,
     ) -> Scan {
         Scan {
@@ -38,6 +77,44 @@ impl Scan {
             schema,
             datasource,
             projection,
+            filters: vec![],
         }
     }
+
+    /// Returns a copy of this scan that only reads the given columns.
+    pub fn with_projection(&self, projection: Vec<String>) -> Scan {
+        Scan {
+            projection,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this scan that passes `filters` to its data source.
+    pub fn with_filters(&self, filters: Vec<Arc<dyn LogicalExpression>>) -> Scan {
+        Scan {
+            filters,
+            ..self.clone()
+        }
+    }
+
+    /// The schema of the underlying data source, ignoring the projection.
+    pub fn source_schema(&self) -> Arc<Schema> {
+        Arc::clone(&self.schema)
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn datasource(&self) -> Arc<dyn DataSource> {
+        Arc::clone(&self.datasource)
+    }
+
+    pub fn projection(&self) -> &[String] {
+        &self.projection
+    }
+
+    pub fn filters(&self) -> &[Arc<dyn LogicalExpression>] {
+        &self.filters
+    }
 }
@@ -1,3 +1,5 @@
+use std::any::Any;
+
 use super::expression::*;
 use super::*;
 
@@ -7,6 +9,10 @@ pub struct Selection {
 }
 
 impl LogicalPlan for Selection {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn schema(&self) -> Arc<Schema> {
         self.input.schema()
     }
@@ -15,6 +21,17 @@ impl LogicalPlan for Selection {
         vec![Arc::clone(&self.input)]
     }
 
+    fn with_new_children(&self, children: Vec<Arc<dyn LogicalPlan>>) -> Arc<dyn LogicalPlan> {
+        Arc::new(Selection::new(
+            Arc::clone(&children[0]),
+            Arc::clone(&self.expr),
+        ))
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn LogicalExpression>> {
+        vec![Arc::clone(&self.expr)]
+    }
+
     fn to_string(&self) -> String {
         format!("Filter: {}", self.expr.to_string())
     }
@@ -24,4 +41,12 @@ impl Selection {
     pub fn new(input: Arc<dyn LogicalPlan>, expr: Arc<dyn LogicalExpression>) -> Selection {
         Selection { input, expr }
     }
+
+    pub fn input(&self) -> Arc<dyn LogicalPlan> {
+        Arc::clone(&self.input)
+    }
+
+    pub fn expr(&self) -> Arc<dyn LogicalExpression> {
+        Arc::clone(&self.expr)
+    }
 }
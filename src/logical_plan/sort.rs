@@ -0,0 +1,77 @@
+use std::any::Any;
+
+use super::expression::*;
+use super::*;
+
+/// Orders the rows of its input by a list of expressions, the first one the most
+/// significant.
+pub struct Sort {
+    input: Arc<dyn LogicalPlan>,
+    expr: Vec<Arc<SortExpression>>,
+    /// Only this many of the first rows are needed, if set.
+    fetch: Option<usize>,
+}
+
+impl LogicalPlan for Sort {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> Arc<Schema> {
+        self.input.schema()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn LogicalPlan>> {
+        vec![Arc::clone(&self.input)]
+    }
+
+    fn with_new_children(&self, children: Vec<Arc<dyn LogicalPlan>>) -> Arc<dyn LogicalPlan> {
+        Arc::new(Sort::new(Arc::clone(&children[0]), self.expr.clone()).with_fetch(self.fetch))
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn LogicalExpression>> {
+        self.expr.iter().map(|e| Arc::clone(e) as _).collect()
+    }
+
+    fn to_string(&self) -> String {
+        let mut s = format!(
+            "Sort: {}",
+            self.expr
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+        if let Some(fetch) = self.fetch {
+            s.push_str(&format!(", fetch={}", fetch));
+        }
+        s
+    }
+}
+
+impl Sort {
+    pub fn new(input: Arc<dyn LogicalPlan>, expr: Vec<Arc<SortExpression>>) -> Sort {
+        Sort {
+            input,
+            expr,
+            fetch: None,
+        }
+    }
+
+    /// Returns this sort producing only its first `fetch` rows.
+    pub fn with_fetch(self, fetch: Option<usize>) -> Sort {
+        Sort { fetch, ..self }
+    }
+
+    pub fn input(&self) -> Arc<dyn LogicalPlan> {
+        Arc::clone(&self.input)
+    }
+
+    pub fn expr(&self) -> &[Arc<SortExpression>] {
+        &self.expr
+    }
+
+    pub fn fetch(&self) -> Option<usize> {
+        self.fetch
+    }
+}
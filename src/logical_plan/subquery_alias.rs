@@ -0,0 +1,169 @@
+use arrow::datatypes::Field;
+use std::any::Any;
+
+use super::expression::{ColumnExpression, LogicalExpression};
+use super::*;
+
+/// The key of the field metadata that holds the name of the relation a column belongs
+/// to.
+const QUALIFIER: &str = "qualifier";
+
+/// Gives the output of a subquery a name. Columns of the output are qualified with
+/// `alias` instead of the names of the tables read inside the subquery, which are no
+/// longer visible to the enclosing query. The fields keep their names, and carry the
+/// qualifier in their metadata.
+pub struct SubqueryAlias {
+    input: Arc<dyn LogicalPlan>,
+    alias: String,
+    schema: Arc<Schema>,
+}
+
+impl LogicalPlan for SubqueryAlias {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> Arc<Schema> {
+        Arc::clone(&self.schema)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn LogicalPlan>> {
+        vec![Arc::clone(&self.input)]
+    }
+
+    fn with_new_children(&self, children: Vec<Arc<dyn LogicalPlan>>) -> Arc<dyn LogicalPlan> {
+        Arc::new(SubqueryAlias::new(
+            Arc::clone(&children[0]),
+            self.alias.clone(),
+        ))
+    }
+
+    fn to_string(&self) -> String {
+        format!("SubqueryAlias: {}", self.alias)
+    }
+}
+
+impl SubqueryAlias {
+    pub fn new(input: Arc<dyn LogicalPlan>, alias: String) -> SubqueryAlias {
+        let fields = input
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| qualify(field, &alias))
+            .collect();
+        SubqueryAlias {
+            input,
+            alias,
+            schema: Arc::new(Schema::new(fields)),
+        }
+    }
+
+    pub fn input(&self) -> Arc<dyn LogicalPlan> {
+        Arc::clone(&self.input)
+    }
+
+    /// The name that columns of the output are qualified with.
+    pub fn alias(&self) -> &str {
+        &self.alias
+    }
+}
+
+/// Returns `field` as a column of the relation `qualifier`, replacing the relation it
+/// belonged to before.
+pub fn qualify(field: &Field, qualifier: &str) -> Field {
+    let mut metadata = field.metadata().clone().unwrap_or_default();
+    metadata.insert(QUALIFIER.to_owned(), qualifier.to_owned());
+    let mut field = field.clone();
+    field.set_metadata(Some(metadata));
+    field
+}
+
+/// The name of the relation that `field` is a column of, if it has one.
+pub fn qualifier(field: &Field) -> Option<&str> {
+    field
+        .metadata()
+        .as_ref()
+        .and_then(|m| m.get(QUALIFIER))
+        .map(|q| q.as_str())
+}
+
+/// The name of the relation that every column of `schema` belongs to, if there is one.
+pub fn relation_name(schema: &Schema) -> Option<String> {
+    let mut qualifiers = schema.fields().iter().map(qualifier);
+    let first = qualifiers.next()??;
+    qualifiers
+        .all(|q| q == Some(first))
+        .then(|| first.to_owned())
+}
+
+/// Finds the name of the field of `schema` that the column `name` refers to: the field
+/// of that name, or else a field whose relation `name` is qualified with, e.g. `u.id`
+/// for the field `id` of the relation `u`.
+pub fn field_name(schema: &Schema, name: &str) -> Option<String> {
+    if schema.field_with_name(name).is_ok() {
+        return Some(name.to_owned());
+    }
+    let (relation, column) = name.split_once('.')?;
+    let mut matching = schema
+        .fields()
+        .iter()
+        .filter(|f| f.name() == column && qualifier(f) == Some(relation));
+    match (matching.next(), matching.next()) {
+        (Some(field), None) => Some(field.name().clone()),
+        _ => None,
+    }
+}
+
+/// Rewrites the columns of `expr` that are qualified with the relation of a field of
+/// `schema` to the names of those fields. Other columns are left as they are.
+pub fn resolve_columns(
+    expr: &Arc<dyn LogicalExpression>,
+    schema: &Schema,
+) -> Arc<dyn LogicalExpression> {
+    if let Some(column) = expr.as_any().downcast_ref::<ColumnExpression>() {
+        return match field_name(schema, column.name()) {
+            Some(name) if name != column.name() => Arc::new(ColumnExpression::new(name)),
+            _ => Arc::clone(expr),
+        };
+    }
+    let children = expr.children();
+    if children.is_empty() {
+        return Arc::clone(expr);
+    }
+    expr.with_new_children(
+        children
+            .iter()
+            .map(|child| resolve_columns(child, schema))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data_type::ArrowType;
+    use crate::core::helper::schema;
+    use crate::logical_plan::empty_relation::EmptyRelation;
+
+    #[test]
+    fn qualifies_the_input_schema() {
+        let input = Arc::new(EmptyRelation::new(schema(vec![
+            ("id", ArrowType::Int64Type, false),
+            ("name", ArrowType::StringType, true),
+        ])));
+        let inner = Arc::new(SubqueryAlias::new(input, "u".to_owned()));
+        assert_eq!(relation_name(&inner.schema()), Some("u".to_owned()));
+
+        // An alias over an alias replaces the relation the columns belong to.
+        let outer = SubqueryAlias::new(inner, "t".to_owned());
+        let schema = outer.schema();
+        assert_eq!(schema.field(1).name(), "name");
+        assert_eq!(qualifier(schema.field(1)), Some("t"));
+        assert_eq!(relation_name(&schema), Some("t".to_owned()));
+
+        assert_eq!(field_name(&schema, "t.name"), Some("name".to_owned()));
+        assert_eq!(field_name(&schema, "name"), Some("name".to_owned()));
+        assert_eq!(field_name(&schema, "u.name"), None);
+        assert_eq!(field_name(&schema, "t.age"), None);
+    }
+}
@@ -1,10 +1,6 @@
-use crate::core::data_type::ArrowType;
-use crate::core::execution_context::ExecutionContext;
-use crate::core::helper::*;
-
-mod core;
-mod logical_plan;
-mod physical_plan;
+use qrust::core::data_type::ArrowType;
+use qrust::core::execution_context::ExecutionContext;
+use qrust::core::helper::*;
 
 fn main() {
     // Create a context for running queries.
@@ -0,0 +1,714 @@
+use std::sync::Arc;
+
+use super::utils::{conjunction, split_conjunction, unalias};
+use super::OptimizerRule;
+use crate::core::error::{QrustError, Result};
+use crate::logical_plan::aggregate::Aggregate;
+use crate::logical_plan::expression::{
+    AggregateExpression, AliasExpression, BinaryExpression, BooleanExpression, ColumnExpression,
+    ExistsExpression, InSubqueryExpression, LiteralBooleanExpression, LogicalExpression,
+    OuterColumnExpression, ScalarSubqueryExpression,
+};
+use crate::logical_plan::join::{Join, JoinType};
+use crate::logical_plan::projection::Projection;
+use crate::logical_plan::selection::Selection;
+use crate::logical_plan::subquery_alias::SubqueryAlias;
+use crate::logical_plan::LogicalPlan;
+
+/// Rewrites subquery expressions into joins, so that each subquery runs once rather
+/// than once per row of the enclosing query.
+///
+/// - `x IN (subquery)` and `EXISTS (subquery)` conditions of a filter become semi joins,
+///   and their negations become anti joins. `EXISTS` over an aggregate without grouping
+///   is always true, and is removed instead.
+/// - Scalar subqueries become left joins against the aggregate they compute. Those that
+///   don't aggregate are wrapped in a `single` aggregate, which fails when they return
+///   more than one row. A correlated one fails when it does so for any value of the
+///   columns it is correlated on, even one that no outer row has.
+///
+/// A correlated subquery is joined on its equality conditions with outer columns,
+/// which are removed from the subquery. A correlated scalar subquery is grouped by the
+/// columns those conditions compare, giving one row per value of the outer columns.
+/// Correlated conditions other than equalities can't be turned into join keys, and are
+/// not supported.
+#[derive(Default)]
+pub struct DecorrelateSubqueries {}
+
+impl OptimizerRule for DecorrelateSubqueries {
+    fn name(&self) -> &str {
+        "decorrelate_subqueries"
+    }
+
+    fn optimize(&self, plan: Arc<dyn LogicalPlan>) -> Result<Arc<dyn LogicalPlan>> {
+        self.rewrite(plan, &mut 0)
+    }
+}
+
+impl DecorrelateSubqueries {
+    pub fn new() -> DecorrelateSubqueries {
+        DecorrelateSubqueries {}
+    }
+
+    /// Rewrites every subquery expression in `plan`. `next_id` numbers the columns
+    /// that hold the values of scalar subqueries.
+    fn rewrite(
+        &self,
+        plan: Arc<dyn LogicalPlan>,
+        next_id: &mut usize,
+    ) -> Result<Arc<dyn LogicalPlan>> {
+        let original_children = plan.children();
+        let mut children = original_children
+            .iter()
+            .map(|child| self.rewrite(Arc::clone(child), next_id))
+            .collect::<Result<Vec<_>>>()?;
+        // A filter on scalar subqueries is projected back to its own columns, which is
+        // redundant right below another projection.
+        if plan.as_any().is::<Projection>() && hides_subquery_columns(&original_children[0]) {
+            if let Some(hiding) = children[0].as_any().downcast_ref::<Projection>() {
+                children[0] = hiding.input();
+            }
+        }
+        let plan = if children.is_empty() {
+            plan
+        } else {
+            plan.try_with_new_children(children)?
+        };
+
+        let any = plan.as_any();
+        if let Some(selection) = any.downcast_ref::<Selection>() {
+            if contains_subquery(&selection.expr()) {
+                return self.rewrite_selection(selection, next_id);
+            }
+        }
+        if let Some(projection) = any.downcast_ref::<Projection>() {
+            if projection.expr().iter().any(contains_subquery) {
+                return self.rewrite_projection(projection, next_id);
+            }
+        }
+        Ok(plan)
+    }
+
+    fn rewrite_selection(
+        &self,
+        selection: &Selection,
+        next_id: &mut usize,
+    ) -> Result<Arc<dyn LogicalPlan>> {
+        let original = selection.input();
+        let mut input = Arc::clone(&original);
+        let mut kept = vec![];
+        for predicate in split_conjunction(&selection.expr()) {
+            let any = predicate.as_any();
+            if let Some(in_subquery) = any.downcast_ref::<InSubqueryExpression>() {
+                input = self.in_subquery_to_join(input, in_subquery, next_id)?;
+            } else if let Some(exists) = any.downcast_ref::<ExistsExpression>() {
+                // A subquery that always returns one row exists for every outer row,
+                // whatever it is correlated on.
+                if returns_one_row(&exists.subquery()) {
+                    if exists.negated() {
+                        kept.push(Arc::new(LiteralBooleanExpression::new(false)) as _);
+                    }
+                } else {
+                    input = self.exists_to_join(input, exists, next_id)?;
+                }
+            } else {
+                let predicate = self.replace_scalar_subqueries(&predicate, &mut input, next_id)?;
+                if contains_subquery(&predicate) {
+                    return Err(QrustError::NotImplemented(format!(
+                        "IN and EXISTS subqueries are only supported as conditions of a filter joined with AND: {}",
+                        predicate.to_string()
+                    )));
+                }
+                kept.push(predicate);
+            }
+        }
+
+        let columns_added = input.schema().fields().len() != original.schema().fields().len();
+        let mut plan = match conjunction(kept) {
+            Some(predicate) => Arc::new(Selection::new(input, predicate)),
+            None => input,
+        };
+        // Scalar subqueries add columns that the filter's output must not include.
+        if columns_added {
+            let expr = original
+                .schema()
+                .fields()
+                .iter()
+                .map(|f| Arc::new(ColumnExpression::new(f.name().clone())) as _)
+                .collect();
+            plan = Arc::new(Projection::new(plan, expr));
+        }
+        Ok(plan)
+    }
+
+    fn rewrite_projection(
+        &self,
+        projection: &Projection,
+        next_id: &mut usize,
+    ) -> Result<Arc<dyn LogicalPlan>> {
+        let original = projection.input();
+        let mut input = Arc::clone(&original);
+        let mut exprs = vec![];
+        for expr in projection.expr() {
+            let name = expr.to_field(Arc::clone(&original)).name().clone();
+            let expr = self.replace_scalar_subqueries(expr, &mut input, next_id)?;
+            if contains_subquery(&expr) {
+                return Err(QrustError::NotImplemented(format!(
+                    "IN and EXISTS subqueries are only supported as conditions of a filter: {}",
+                    expr.to_string()
+                )));
+            }
+            // Keep the output name of the expression the subquery was replaced in.
+            if expr.to_field(Arc::clone(&input)).name() != &name {
+                exprs.push(Arc::new(AliasExpression::new(expr, name)) as _);
+            } else {
+                exprs.push(expr);
+            }
+        }
+        Ok(Arc::new(Projection::new(input, exprs)))
+    }
+
+    fn in_subquery_to_join(
+        &self,
+        input: Arc<dyn LogicalPlan>,
+        in_subquery: &InSubqueryExpression,
+        next_id: &mut usize,
+    ) -> Result<Arc<dyn LogicalPlan>> {
+        let expr = in_subquery.expr();
+        let column = expr
+            .as_any()
+            .downcast_ref::<ColumnExpression>()
+            .ok_or_else(|| {
+                QrustError::NotImplemented(format!(
+                    "IN subqueries are only supported on columns: {}",
+                    in_subquery.to_string()
+                ))
+            })?;
+        let subquery = self.rewrite(in_subquery.subquery(), next_id)?;
+        let value = subquery.schema().field(0).clone();
+
+        // `x NOT IN (...)` is never true when either side is null, which an anti join
+        // doesn't account for.
+        let input_field = input.schema().field_with_name(column.name())?.clone();
+        if in_subquery.negated() && (input_field.is_nullable() || value.is_nullable()) {
+            return Err(QrustError::NotImplemented(format!(
+                "NOT IN subqueries are only supported on columns that can't be null: {}",
+                in_subquery.to_string()
+            )));
+        }
+
+        let (subquery, correlated) = pull_up_correlation(subquery)?;
+        let mut on = vec![(column.name().to_owned(), value.name().clone())];
+        on.extend(correlated);
+        let join_type = if in_subquery.negated() {
+            JoinType::Anti
+        } else {
+            JoinType::Semi
+        };
+        Ok(Arc::new(Join::try_new(
+            input, subquery, join_type, on, None,
+        )?))
+    }
+
+    fn exists_to_join(
+        &self,
+        input: Arc<dyn LogicalPlan>,
+        exists: &ExistsExpression,
+        next_id: &mut usize,
+    ) -> Result<Arc<dyn LogicalPlan>> {
+        let subquery = self.rewrite(exists.subquery(), next_id)?;
+        // Without correlation there are no keys, so every row matches any row of the
+        // subquery.
+        let (subquery, on) = pull_up_correlation(subquery)?;
+        let join_type = if exists.negated() {
+            JoinType::Anti
+        } else {
+            JoinType::Semi
+        };
+        Ok(Arc::new(Join::try_new(
+            input, subquery, join_type, on, None,
+        )?))
+    }
+
+    /// Replaces every scalar subquery in `expr` with a column computed by a left join
+    /// that is added on top of `input`.
+    fn replace_scalar_subqueries(
+        &self,
+        expr: &Arc<dyn LogicalExpression>,
+        input: &mut Arc<dyn LogicalPlan>,
+        next_id: &mut usize,
+    ) -> Result<Arc<dyn LogicalExpression>> {
+        if let Some(scalar) = expr.as_any().downcast_ref::<ScalarSubqueryExpression>() {
+            let subquery = self.rewrite(scalar.subquery(), next_id)?;
+            let value = subquery.schema().field(0).name().clone();
+            // Only an aggregate without grouping is known to produce at most one row.
+            // Other subqueries are checked as they run.
+            let subquery = if is_single_aggregate(&subquery) {
+                subquery
+            } else {
+                let single =
+                    AggregateExpression::single(Arc::new(ColumnExpression::new(value.clone())))
+                        .with_alias(value.clone());
+                Arc::new(Aggregate::new(subquery, vec![], vec![Arc::new(single)]))
+            };
+            let (subquery, correlated) = pull_up_correlation(subquery)?;
+
+            *next_id += 1;
+            let alias = format!("__scalar_sq_{}", next_id);
+            let mut exprs: Vec<Arc<dyn LogicalExpression>> = vec![Arc::new(AliasExpression::new(
+                Arc::new(ColumnExpression::new(value)),
+                alias.clone(),
+            ))];
+            let mut on = vec![];
+            for (outer, inner) in correlated {
+                let key = format!("{}.{}", alias, inner);
+                if !on.iter().any(|(_, k)| *k == key) {
+                    exprs.push(Arc::new(AliasExpression::new(
+                        Arc::new(ColumnExpression::new(inner)),
+                        key.clone(),
+                    )));
+                }
+                on.push((outer, key));
+            }
+            let right = Arc::new(Projection::new(subquery, exprs));
+            *input = Arc::new(Join::try_new(
+                Arc::clone(input),
+                right,
+                JoinType::Left,
+                on,
+                None,
+            )?);
+            return Ok(Arc::new(ColumnExpression::new(alias)));
+        }
+
+        let children = expr.children();
+        if children.is_empty() {
+            return Ok(Arc::clone(expr));
+        }
+        let children = children
+            .iter()
+            .map(|child| self.replace_scalar_subqueries(child, input, next_id))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(expr.with_new_children(children))
+    }
+}
+
+/// Pairs of an outer column and the inner column a subquery compares it with.
+type Correlation = Vec<(String, String)>;
+
+/// Removes the conditions that compare columns of `plan` with outer columns, returning
+/// the plan without them and the pairs of outer and inner columns they compared. The
+/// inner columns are kept in the output of the returned plan so that it can be joined
+/// on them.
+fn pull_up_correlation(plan: Arc<dyn LogicalPlan>) -> Result<(Arc<dyn LogicalPlan>, Correlation)> {
+    let any = plan.as_any();
+
+    if let Some(selection) = any.downcast_ref::<Selection>() {
+        let (input, mut correlated) = pull_up_correlation(selection.input())?;
+        let mut kept = vec![];
+        for predicate in split_conjunction(&selection.expr()) {
+            if !has_outer_columns(&predicate) {
+                kept.push(predicate);
+                continue;
+            }
+            correlated.push(correlated_equality(&predicate).ok_or_else(|| {
+                QrustError::NotImplemented(format!(
+                    "Correlated condition {} is not supported, only equality between a column and an outer column is",
+                    predicate.to_string()
+                ))
+            })?);
+        }
+        let plan = match conjunction(kept) {
+            Some(predicate) => Arc::new(Selection::new(input, predicate)),
+            None => input,
+        };
+        return Ok((plan, correlated));
+    }
+
+    if let Some(projection) = any.downcast_ref::<Projection>() {
+        expect_uncorrelated(projection.expr(), plan.as_ref())?;
+        let (input, correlated) = pull_up_correlation(projection.input())?;
+        let mut expr = projection.expr().to_vec();
+        for (_, inner) in &correlated {
+            let projected = expr
+                .iter()
+                .find(|e| e.to_field(Arc::clone(&input)).name() == inner);
+            match projected {
+                None => expr.push(Arc::new(ColumnExpression::new(inner.clone()))),
+                Some(e) if is_column(&unalias(e), inner) => {}
+                Some(_) => {
+                    return Err(QrustError::NotImplemented(format!(
+                        "Correlated subquery replaces column {} that it is correlated on",
+                        inner
+                    )))
+                }
+            }
+        }
+        return Ok((Arc::new(Projection::new(input, expr)), correlated));
+    }
+
+    if let Some(aggregate) = any.downcast_ref::<Aggregate>() {
+        expect_uncorrelated(aggregate.group_expr(), plan.as_ref())?;
+        let aggregates: Vec<Arc<dyn LogicalExpression>> = aggregate
+            .aggregate_expr()
+            .iter()
+            .map(|a| Arc::clone(a) as _)
+            .collect();
+        expect_uncorrelated(&aggregates, plan.as_ref())?;
+
+        let (input, correlated) = pull_up_correlation(aggregate.input())?;
+        let mut group_expr = aggregate.group_expr().to_vec();
+        for (_, inner) in &correlated {
+            if !group_expr.iter().any(|g| is_column(g, inner)) {
+                group_expr.push(Arc::new(ColumnExpression::new(inner.clone())));
+            }
+        }
+        let aggregate = Aggregate::new(input, group_expr, aggregate.aggregate_expr().to_vec());
+        return Ok((Arc::new(aggregate), correlated));
+    }
+
+    if let Some(alias) = any.downcast_ref::<SubqueryAlias>() {
+        let (input, correlated) = pull_up_correlation(alias.input())?;
+        return Ok((plan.try_with_new_children(vec![input])?, correlated));
+    }
+
+    for child in plan.children() {
+        let (_, correlated) = pull_up_correlation(child)?;
+        if !correlated.is_empty() {
+            return Err(QrustError::NotImplemented(format!(
+                "Correlated subqueries are not supported through {}",
+                LogicalPlan::to_string(plan.as_ref())
+            )));
+        }
+    }
+    Ok((plan, vec![]))
+}
+
+/// Returns the outer and inner column of a condition like `inner = outer(column)`.
+fn correlated_equality(predicate: &Arc<dyn LogicalExpression>) -> Option<(String, String)> {
+    let binary = predicate.as_any().downcast_ref::<BooleanExpression>()?;
+    if binary.name() != "eq" {
+        return None;
+    }
+    let inner = |e: &Arc<dyn LogicalExpression>| {
+        e.as_any()
+            .downcast_ref::<ColumnExpression>()
+            .map(|c| c.name().to_owned())
+    };
+    let outer = |e: &Arc<dyn LogicalExpression>| {
+        e.as_any()
+            .downcast_ref::<OuterColumnExpression>()
+            .map(|c| c.name().to_owned())
+    };
+    let (l, r) = (binary.l(), binary.r());
+    match (outer(&l), inner(&r)) {
+        (Some(o), Some(i)) => Some((o, i)),
+        _ => Some((outer(&r)?, inner(&l)?)),
+    }
+}
+
+fn expect_uncorrelated(exprs: &[Arc<dyn LogicalExpression>], plan: &dyn LogicalPlan) -> Result<()> {
+    match exprs.iter().find(|e| has_outer_columns(e)) {
+        Some(e) => Err(QrustError::NotImplemented(format!(
+            "Outer columns are only supported in the conditions of a subquery, not in {} of {}",
+            e.to_string(),
+            plan.to_string()
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Returns true if `plan` computes a single aggregate row, possibly filtered or
+/// projected afterwards.
+fn is_single_aggregate(plan: &Arc<dyn LogicalPlan>) -> bool {
+    let any = plan.as_any();
+    if let Some(aggregate) = any.downcast_ref::<Aggregate>() {
+        return aggregate.group_expr().is_empty();
+    }
+    if any.is::<Projection>() || any.is::<Selection>() || any.is::<SubqueryAlias>() {
+        return is_single_aggregate(&plan.children()[0]);
+    }
+    false
+}
+
+/// Returns true if `plan` computes an aggregate without grouping, which returns exactly
+/// one row even when its input is empty.
+fn returns_one_row(plan: &Arc<dyn LogicalPlan>) -> bool {
+    let any = plan.as_any();
+    if let Some(aggregate) = any.downcast_ref::<Aggregate>() {
+        return aggregate.group_expr().is_empty();
+    }
+    if any.is::<Projection>() || any.is::<SubqueryAlias>() {
+        return returns_one_row(&plan.children()[0]);
+    }
+    false
+}
+
+/// Returns true if `plan` is a filter that is rewritten into a projection over the
+/// joins of its scalar subqueries.
+fn hides_subquery_columns(plan: &Arc<dyn LogicalPlan>) -> bool {
+    plan.as_any()
+        .downcast_ref::<Selection>()
+        .is_some_and(|selection| {
+            split_conjunction(&selection.expr())
+                .iter()
+                .any(|predicate| {
+                    let any = predicate.as_any();
+                    !any.is::<InSubqueryExpression>()
+                        && !any.is::<ExistsExpression>()
+                        && contains_subquery(predicate)
+                })
+        })
+}
+
+fn is_column(expr: &Arc<dyn LogicalExpression>, name: &str) -> bool {
+    expr.as_any()
+        .downcast_ref::<ColumnExpression>()
+        .is_some_and(|c| c.name() == name)
+}
+
+fn has_outer_columns(expr: &Arc<dyn LogicalExpression>) -> bool {
+    expr.as_any().is::<OuterColumnExpression>() || expr.children().iter().any(has_outer_columns)
+}
+
+fn contains_subquery(expr: &Arc<dyn LogicalExpression>) -> bool {
+    let any = expr.as_any();
+    any.is::<InSubqueryExpression>()
+        || any.is::<ExistsExpression>()
+        || any.is::<ScalarSubqueryExpression>()
+        || expr.children().iter().any(contains_subquery)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data_source::CsvReadOptions;
+    use crate::core::data_type::ArrowType;
+    use crate::core::execution_context::ExecutionContext;
+    use crate::core::helper::*;
+    use crate::test_util::{csv_context, sorted_rows};
+
+    fn context() -> ExecutionContext {
+        let ctx = ExecutionContext::new();
+        ctx.register_csv(
+            "orders",
+            "orders.csv",
+            CsvReadOptions::new().schema(schema(vec![
+                ("id", ArrowType::Int64Type, false),
+                ("customer", ArrowType::Int64Type, true),
+                ("amount", ArrowType::Int64Type, false),
+            ])),
+        )
+        .unwrap();
+        ctx.register_csv(
+            "customers",
+            "customers.csv",
+            CsvReadOptions::new().schema(schema(vec![
+                ("cid", ArrowType::Int64Type, false),
+                ("name", ArrowType::StringType, true),
+            ])),
+        )
+        .unwrap();
+        ctx
+    }
+
+    fn optimize(sql: &str) -> Result<String> {
+        let plan = context().sql(sql)?.plan();
+        Ok(DecorrelateSubqueries::new().optimize(plan)?.format())
+    }
+
+    #[test]
+    fn in_and_exists_to_semi_and_anti_joins() {
+        assert_eq!(
+            optimize(
+                "SELECT id FROM orders WHERE amount > 10 \
+                 AND customer IN (SELECT cid FROM customers WHERE name = 'a')"
+            )
+            .unwrap(),
+            "Projection: id
+  Filter: amount>10
+    Join: joinType=Semi, on=[customer=cid]
+      Scan: orders, projection=None
+      Projection: cid
+        Filter: name='a'
+          Scan: customers, projection=None
+"
+        );
+        assert_eq!(
+            optimize(
+                "SELECT cid FROM customers c \
+                 WHERE NOT EXISTS (SELECT id FROM orders WHERE customer = c.cid)"
+            )
+            .unwrap(),
+            "Projection: cid
+  Join: joinType=Anti, on=[cid=customer]
+    Scan: customers, projection=None
+    Projection: id, customer
+      Scan: orders, projection=None
+"
+        );
+        assert_eq!(
+            optimize("SELECT cid FROM customers WHERE cid NOT IN (SELECT id FROM orders)").unwrap(),
+            "Projection: cid
+  Join: joinType=Anti, on=[cid=id]
+    Scan: customers, projection=None
+    Projection: id
+      Scan: orders, projection=None
+"
+        );
+    }
+
+    #[test]
+    fn scalar_subqueries_to_left_joins() {
+        assert_eq!(
+            optimize(
+                "SELECT name, (SELECT sum(amount) FROM orders WHERE customer = cid) AS total \
+                 FROM customers"
+            )
+            .unwrap(),
+            "Projection: name, __scalar_sq_1 AS total
+  Join: joinType=Left, on=[cid=__scalar_sq_1.customer]
+    Scan: customers, projection=None
+    Projection: sum(amount) AS __scalar_sq_1, customer AS __scalar_sq_1.customer
+      Aggregate: groupExpr=customer, aggregateExpr=sum(amount)
+        Scan: orders, projection=None
+"
+        );
+        assert_eq!(
+            optimize("SELECT id FROM orders WHERE amount > (SELECT avg(amount) FROM orders)")
+                .unwrap(),
+            "Projection: id
+  Filter: amount>__scalar_sq_1
+    Join: joinType=Left, on=[]
+      Scan: orders, projection=None
+      Projection: avg(amount) AS __scalar_sq_1
+        Aggregate: groupExpr=, aggregateExpr=avg(amount)
+          Scan: orders, projection=None
+"
+        );
+        assert_eq!(
+            optimize("SELECT name, (SELECT id FROM orders WHERE customer = cid) FROM customers")
+                .unwrap(),
+            "Projection: name, __scalar_sq_1 AS id
+  Join: joinType=Left, on=[cid=__scalar_sq_1.customer]
+    Scan: customers, projection=None
+    Projection: id AS __scalar_sq_1, customer AS __scalar_sq_1.customer
+      Aggregate: groupExpr=customer, aggregateExpr=single(id) AS id
+        Projection: id, customer
+          Scan: orders, projection=None
+"
+        );
+    }
+
+    #[test]
+    fn scalar_subqueries_without_aggregates() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = csv_context(
+            &dir,
+            vec![
+                (
+                    "t",
+                    CsvReadOptions::new().schema(schema(vec![
+                        ("id", ArrowType::Int64Type, false),
+                        ("n", ArrowType::Int64Type, true),
+                    ])),
+                    &["1,10", "2,", "3,30", "3,31"],
+                ),
+                (
+                    "u",
+                    CsvReadOptions::new().schema(schema(vec![("id", ArrowType::Int64Type, false)])),
+                    &["1", "2", "4"],
+                ),
+            ],
+        );
+        let run = |sql: &str| ctx.sql(sql).unwrap().collect();
+
+        // Outer rows without a row in the subquery get a null.
+        assert_eq!(
+            sorted_rows(
+                &run("SELECT id, (SELECT n FROM t WHERE t.id = u.id AND t.id < 3) AS n FROM u")
+                    .unwrap()
+            ),
+            vec!["1,10", "2,", "4,"]
+        );
+        assert_eq!(
+            sorted_rows(
+                &run("SELECT id FROM u WHERE id < (SELECT n FROM t WHERE id = 1)").unwrap()
+            ),
+            vec!["1", "2", "4"]
+        );
+        for sql in [
+            "SELECT (SELECT n FROM t WHERE id > 2) FROM u",
+            // The rows of t with id 3 are checked even though no row of u has that id.
+            "SELECT (SELECT n FROM t WHERE t.id = u.id) FROM u",
+        ] {
+            assert_eq!(
+                run(sql).err().unwrap().to_string(),
+                "Execution error: Scalar subquery returned more than one row"
+            );
+        }
+    }
+
+    #[test]
+    fn exists_over_aggregates() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = csv_context(
+            &dir,
+            vec![
+                (
+                    "t",
+                    CsvReadOptions::new().schema(schema(vec![
+                        ("id", ArrowType::Int64Type, false),
+                        ("n", ArrowType::Int64Type, false),
+                    ])),
+                    &["1,10", "2,20", "3,30", "4,40"],
+                ),
+                (
+                    "u",
+                    CsvReadOptions::new().schema(schema(vec![("id", ArrowType::Int64Type, false)])),
+                    &["1", "3", "5"],
+                ),
+            ],
+        );
+        let run = |sql: &str| sorted_rows(&ctx.sql(sql).unwrap().collect().unwrap());
+
+        // An aggregate without grouping has a row even when no row of t matches.
+        assert_eq!(
+            run("SELECT id FROM u WHERE EXISTS (SELECT sum(n) FROM t WHERE t.id = u.id)"),
+            vec!["1", "3", "5"]
+        );
+        assert!(
+            run("SELECT id FROM u WHERE NOT EXISTS (SELECT max(n) FROM t WHERE t.id = u.id)")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn unsupported_subqueries() {
+        let err = |sql: &str| optimize(sql).err().unwrap().to_string();
+        assert_eq!(
+            err("SELECT id FROM orders WHERE customer NOT IN (SELECT cid FROM customers)"),
+            "Not implemented: NOT IN subqueries are only supported on columns that can't be null: \
+             customer NOT IN (Projection: cid -> Scan: customers, projection=None)"
+        );
+        assert_eq!(
+            err("SELECT id FROM orders WHERE amount > 1 OR customer IN (SELECT cid FROM customers)"),
+            "Not implemented: IN and EXISTS subqueries are only supported as conditions of a filter \
+             joined with AND: amount>1 OR customer IN (Projection: cid -> Scan: customers, projection=None)"
+        );
+        assert_eq!(
+            err("SELECT id FROM orders WHERE amount + 1 IN (SELECT cid FROM customers)"),
+            "Not implemented: IN subqueries are only supported on columns: \
+             amount + 1 IN (Projection: cid -> Scan: customers, projection=None)"
+        );
+        assert_eq!(
+            err("SELECT name, (SELECT amount FROM orders WHERE customer > cid) FROM customers"),
+            "Not implemented: Correlated condition customer>outer(cid) is not supported, \
+             only equality between a column and an outer column is"
+        );
+        assert_eq!(
+            err("SELECT cid FROM customers WHERE EXISTS (SELECT id FROM orders WHERE customer > cid)"),
+            "Not implemented: Correlated condition customer>outer(cid) is not supported, \
+             only equality between a column and an outer column is"
+        );
+    }
+}
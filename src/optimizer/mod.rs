@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use crate::core::error::Result;
+use crate::logical_plan::LogicalPlan;
+
+pub mod decorrelate_subqueries;
+pub mod predicate_pushdown;
+pub mod projection_pushdown;
+pub mod simplify_expressions;
+pub mod sort_limit_fusion;
+pub mod utils;
+
+/// A rewrite of a logical plan into an equivalent plan that is cheaper to run.
+pub trait OptimizerRule: Send + Sync {
+    /// A short name for the rule, used when tracing how a plan was optimized.
+    fn name(&self) -> &str;
+    fn optimize(&self, plan: Arc<dyn LogicalPlan>) -> Result<Arc<dyn LogicalPlan>>;
+}
+
+/// Applies a list of rules to a plan over and over until it stops changing.
+pub struct Optimizer {
+    rules: Vec<Arc<dyn OptimizerRule>>,
+    max_passes: usize,
+}
+
+impl Optimizer {
+    pub fn new(rules: Vec<Arc<dyn OptimizerRule>>) -> Optimizer {
+        Optimizer {
+            rules,
+            max_passes: 16,
+        }
+    }
+
+    /// Sets the maximum number of times the rule list is applied. This guards against
+    /// rules that keep rewriting each other's output.
+    pub fn with_max_passes(self, max_passes: usize) -> Optimizer {
+        Optimizer { max_passes, ..self }
+    }
+
+    /// Optimizes `plan`, calling `observer` with the rule and the resulting plan every
+    /// time a rule changes the plan.
+    pub fn optimize<F>(
+        &self,
+        plan: Arc<dyn LogicalPlan>,
+        mut observer: F,
+    ) -> Result<Arc<dyn LogicalPlan>>
+    where
+        F: FnMut(&dyn OptimizerRule, &Arc<dyn LogicalPlan>),
+    {
+        // Plans don't implement equality, so changes are detected through their
+        // formatted representation.
+        let mut plan = plan;
+        let mut formatted = plan.format();
+        for _ in 0..self.max_passes {
+            let mut changed = false;
+            for rule in &self.rules {
+                let new_plan = rule.optimize(Arc::clone(&plan))?;
+                let new_formatted = new_plan.format();
+                if new_formatted != formatted {
+                    observer(rule.as_ref(), &new_plan);
+                    changed = true;
+                    formatted = new_formatted;
+                }
+                plan = new_plan;
+            }
+            if !changed {
+                break;
+            }
+        }
+        Ok(plan)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data_type::ArrowType;
+    use crate::core::execution_context::ExecutionContext;
+    use crate::core::helper::*;
+    use crate::logical_plan::projection::Projection;
+    use crate::optimizer::projection_pushdown::ProjectionPushdown;
+
+    /// Strips one projection from the top of the plan per application.
+    struct RemoveProjection {}
+
+    impl OptimizerRule for RemoveProjection {
+        fn name(&self) -> &str {
+            "remove_projection"
+        }
+
+        fn optimize(&self, plan: Arc<dyn LogicalPlan>) -> Result<Arc<dyn LogicalPlan>> {
+            match plan.as_any().downcast_ref::<Projection>() {
+                Some(projection) => Ok(projection.input()),
+                None => Ok(plan),
+            }
+        }
+    }
+
+    fn generate_plan() -> Arc<dyn LogicalPlan> {
+        let schema = schema(vec![
+            ("column1", ArrowType::Int64Type, false),
+            ("column2", ArrowType::Int64Type, false),
+        ]);
+        ExecutionContext::new()
+            .csv(schema, "test.csv")
+            .select(vec![col("column1")])
+            .select(vec![col("column1")])
+            .plan()
+    }
+
+    #[test]
+    fn runs_to_fixed_point() {
+        let optimizer = Optimizer::new(vec![
+            Arc::new(RemoveProjection {}),
+            Arc::new(ProjectionPushdown::new()),
+        ]);
+        let mut trace = vec![];
+        let plan = optimizer
+            .optimize(generate_plan(), |rule, plan| {
+                trace.push(format!("{}: {}", rule.name(), plan.format().trim()))
+            })
+            .unwrap();
+
+        assert_eq!(plan.format(), "Scan: test.csv, projection=[\"column1\"]\n");
+        assert_eq!(
+            trace,
+            vec![
+                "remove_projection: Projection: column1\n  Scan: test.csv, projection=None",
+                "projection_pushdown: Projection: column1\n  Scan: test.csv, projection=[\"column1\"]",
+                "remove_projection: Scan: test.csv, projection=[\"column1\"]",
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_after_max_passes() {
+        let optimizer = Optimizer::new(vec![Arc::new(RemoveProjection {})]).with_max_passes(1);
+        let plan = optimizer.optimize(generate_plan(), |_, _| {}).unwrap();
+
+        assert_eq!(
+            plan.format(),
+            "Projection: column1\n  Scan: test.csv, projection=None\n"
+        );
+    }
+}
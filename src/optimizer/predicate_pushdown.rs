@@ -0,0 +1,476 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::utils::{conjunction, exprlist_columns, replace_columns, split_conjunction, unalias};
+use super::OptimizerRule;
+use crate::core::data_source::FilterPushdown;
+use crate::core::error::Result;
+use crate::logical_plan::aggregate::Aggregate;
+use crate::logical_plan::expression::{ColumnExpression, LogicalExpression};
+use crate::logical_plan::join::{Join, JoinType};
+use crate::logical_plan::projection::Projection;
+use crate::logical_plan::scan::Scan;
+use crate::logical_plan::selection::Selection;
+use crate::logical_plan::sort::Sort;
+use crate::logical_plan::subquery_alias::SubqueryAlias;
+use crate::logical_plan::LogicalPlan;
+
+/// Moves filters as close to the scans as possible. Predicates are split on `AND` so
+/// that each part can travel as far down as it is allowed to.
+#[derive(Default)]
+pub struct PredicatePushdown {}
+
+impl OptimizerRule for PredicatePushdown {
+    fn name(&self) -> &str {
+        "predicate_pushdown"
+    }
+
+    fn optimize(&self, plan: Arc<dyn LogicalPlan>) -> Result<Arc<dyn LogicalPlan>> {
+        self.push_down(plan, vec![])
+    }
+}
+
+impl PredicatePushdown {
+    pub fn new() -> PredicatePushdown {
+        PredicatePushdown {}
+    }
+
+    /// Pushes `predicates`, which apply to the output of `plan`, as far into `plan` as
+    /// possible.
+    fn push_down(
+        &self,
+        plan: Arc<dyn LogicalPlan>,
+        mut predicates: Vec<Arc<dyn LogicalExpression>>,
+    ) -> Result<Arc<dyn LogicalPlan>> {
+        let any = plan.as_any();
+
+        if let Some(selection) = any.downcast_ref::<Selection>() {
+            predicates.extend(split_conjunction(&selection.expr()));
+            return self.push_down(selection.input(), predicates);
+        }
+
+        if let Some(projection) = any.downcast_ref::<Projection>() {
+            // Predicates refer to the projection's outputs, so rewrite them in terms of
+            // the expressions that compute those outputs.
+            let schema = plan.schema();
+            let outputs: Vec<(String, Arc<dyn LogicalExpression>)> = projection
+                .expr()
+                .iter()
+                .enumerate()
+                .map(|(i, e)| (schema.field(i).name().clone(), unalias(e)))
+                .collect();
+            let (pushed, kept) = rewrite_through(predicates, &outputs);
+            let input = self.push_down(projection.input(), pushed)?;
+            return Ok(add_filter(plan.try_with_new_children(vec![input])?, kept));
+        }
+
+        if let Some(aggregate) = any.downcast_ref::<Aggregate>() {
+            // Only predicates on the grouping columns can be evaluated before grouping.
+            // Without any, the aggregate produces a row even from no rows, so even
+            // predicates that don't reference a column have to stay above it.
+            if aggregate.group_expr().is_empty() {
+                let input = self.push_down(aggregate.input(), vec![])?;
+                return Ok(add_filter(
+                    plan.try_with_new_children(vec![input])?,
+                    predicates,
+                ));
+            }
+            let schema = plan.schema();
+            let outputs: Vec<(String, Arc<dyn LogicalExpression>)> = aggregate
+                .group_expr()
+                .iter()
+                .enumerate()
+                .map(|(i, e)| (schema.field(i).name().clone(), unalias(e)))
+                .collect();
+            let (pushed, kept) = rewrite_through(predicates, &outputs);
+            let input = self.push_down(aggregate.input(), pushed)?;
+            return Ok(add_filter(plan.try_with_new_children(vec![input])?, kept));
+        }
+
+        // Filtering before sorting keeps the same rows in the same order, unless the
+        // sort only keeps the first rows.
+        if let Some(sort) = any.downcast_ref::<Sort>().filter(|s| s.fetch().is_none()) {
+            let input = self.push_down(sort.input(), predicates)?;
+            return plan.try_with_new_children(vec![input]);
+        }
+
+        // Column names are the same on both sides of an alias.
+        if let Some(alias) = any.downcast_ref::<SubqueryAlias>() {
+            let input = self.push_down(alias.input(), predicates)?;
+            return plan.try_with_new_children(vec![input]);
+        }
+
+        if let Some(join) = any.downcast_ref::<Join>() {
+            // A predicate on the columns of one side can filter that side before the
+            // join, unless the join has to keep the rows of that side that don't match.
+            let (to_left, to_right) = match join.join_type() {
+                JoinType::Inner => (true, true),
+                JoinType::Left | JoinType::Semi | JoinType::Anti => (true, false),
+                JoinType::Right => (false, true),
+                JoinType::Full => (false, false),
+            };
+            // Predicates name the columns of a side as they appear in the output.
+            let outputs = |input: Arc<dyn LogicalPlan>, name: &dyn Fn(&str) -> String| {
+                input
+                    .schema()
+                    .fields()
+                    .iter()
+                    .map(|f| {
+                        let column: Arc<dyn LogicalExpression> =
+                            Arc::new(ColumnExpression::new(f.name().clone()));
+                        (name(f.name()), column)
+                    })
+                    .collect::<Vec<_>>()
+            };
+            let (left, predicates) = if to_left {
+                let name = |c: &str| {
+                    if join.join_type().includes_right() {
+                        join.left_column(c)
+                    } else {
+                        c.to_owned()
+                    }
+                };
+                rewrite_through(predicates, &outputs(join.left(), &name))
+            } else {
+                (vec![], predicates)
+            };
+            let (right, kept) = if to_right {
+                rewrite_through(
+                    predicates,
+                    &outputs(join.right(), &|c| join.right_column(c)),
+                )
+            } else {
+                (vec![], predicates)
+            };
+            let left = self.push_down(join.left(), left)?;
+            let right = self.push_down(join.right(), right)?;
+            return Ok(add_filter(
+                plan.try_with_new_children(vec![left, right])?,
+                kept,
+            ));
+        }
+
+        if let Some(scan) = any.downcast_ref::<Scan>() {
+            // Hand the predicates to the data source, keeping a filter above the scan
+            // for the ones the source can't apply exactly.
+            let datasource = scan.datasource();
+            let mut filters = scan.filters().to_vec();
+            let mut kept = vec![];
+            for predicate in predicates {
+                let pushdown = datasource.supports_filter(&predicate);
+                if pushdown != FilterPushdown::Unsupported
+                    && !filters
+                        .iter()
+                        .any(|f| f.to_string() == predicate.to_string())
+                {
+                    filters.push(Arc::clone(&predicate));
+                }
+                if pushdown != FilterPushdown::Exact {
+                    kept.push(predicate);
+                }
+            }
+            return Ok(add_filter(Arc::new(scan.with_filters(filters)), kept));
+        }
+
+        // Predicates can't be moved past any other node, but its inputs may still
+        // contain filters of their own.
+        let children = plan
+            .children()
+            .into_iter()
+            .map(|child| self.push_down(child, vec![]))
+            .collect::<Result<Vec<_>>>()?;
+        let plan = if children.is_empty() {
+            plan
+        } else {
+            plan.try_with_new_children(children)?
+        };
+        Ok(add_filter(plan, predicates))
+    }
+}
+
+type Predicates = Vec<Arc<dyn LogicalExpression>>;
+
+/// Rewrites each predicate in terms of the expressions that compute the named
+/// `outputs`. Returns the predicates that could be rewritten, followed by the ones
+/// that reference columns not in `outputs`.
+fn rewrite_through(
+    predicates: Predicates,
+    outputs: &[(String, Arc<dyn LogicalExpression>)],
+) -> (Predicates, Predicates) {
+    let mut replacements: HashMap<String, Arc<dyn LogicalExpression>> = HashMap::new();
+    let mut ambiguous = vec![];
+    for (name, expr) in outputs {
+        if replacements
+            .insert(name.clone(), Arc::clone(expr))
+            .is_some()
+        {
+            ambiguous.push(name.clone());
+        }
+    }
+    for name in ambiguous {
+        replacements.remove(&name);
+    }
+
+    let mut pushed = vec![];
+    let mut kept = vec![];
+    for predicate in predicates {
+        let columns = exprlist_columns(&[Arc::clone(&predicate)]);
+        if columns.iter().all(|c| replacements.contains_key(c)) {
+            pushed.push(replace_columns(&predicate, &replacements));
+        } else {
+            kept.push(predicate);
+        }
+    }
+    (pushed, kept)
+}
+
+/// Wraps `plan` in a filter for `predicates`, if there are any.
+fn add_filter(
+    plan: Arc<dyn LogicalPlan>,
+    predicates: Vec<Arc<dyn LogicalExpression>>,
+) -> Arc<dyn LogicalPlan> {
+    match conjunction(predicates) {
+        Some(predicate) => Arc::new(Selection::new(plan, predicate)),
+        None => plan,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data_frame::DataFrame;
+    use crate::core::data_source::DataSource;
+    use crate::core::data_type::ArrowType;
+    use crate::core::execution_context::ExecutionContext;
+    use crate::core::helper::*;
+    use crate::logical_plan::expression::{BinaryExpression, BooleanExpression};
+    use crate::physical_plan::planner::PhysicalPlanner;
+    use crate::physical_plan::{collect, BatchIterator};
+    use crate::test_util::{rows, write_csv};
+    use arrow::datatypes::Schema;
+
+    fn generate_df() -> DataFrame {
+        let schema = schema(vec![
+            ("column1", ArrowType::Int64Type, false),
+            ("column2", ArrowType::Int64Type, false),
+            ("column3", ArrowType::StringType, false),
+        ]);
+        ExecutionContext::new().csv(schema, "test.csv")
+    }
+
+    fn optimize(df: DataFrame) -> String {
+        PredicatePushdown::new()
+            .optimize(df.plan())
+            .unwrap()
+            .format()
+    }
+
+    #[test]
+    fn below_projection() {
+        let df = generate_df()
+            .select(vec![col("column1"), col("column3")])
+            .filter(eq(col("column1"), lit(&5)));
+
+        assert_eq!(
+            optimize(df),
+            "Projection: column1, column3
+  Scan: test.csv, projection=None, filters=[column1=5]
+"
+        );
+    }
+
+    #[test]
+    fn through_aliases() {
+        let df = generate_df()
+            .select(vec![
+                alias(col("column1"), "id"),
+                alias(add(col("column1"), col("column2")), "total"),
+            ])
+            .filter(and(gt(col("total"), lit(&10)), lt(col("id"), lit(&3))));
+
+        assert_eq!(
+            optimize(df),
+            "Projection: column1 AS id, column1 + column2 AS total
+  Scan: test.csv, projection=None, filters=[column1 + column2>10, column1<3]
+"
+        );
+    }
+
+    #[test]
+    fn merges_stacked_filters() {
+        let df = generate_df()
+            .filter(eq(col("column3"), lit(&"a")))
+            .select(vec![col("column1"), col("column3")])
+            .filter(gt(col("column1"), lit(&1)));
+
+        assert_eq!(
+            optimize(df),
+            "Projection: column1, column3
+  Scan: test.csv, projection=None, filters=[column1>1, column3='a']
+"
+        );
+    }
+
+    #[test]
+    fn splits_around_aggregate() {
+        let df = generate_df()
+            .aggregate(vec![col("column3")], vec![sum(col("column1"))])
+            .filter(and(eq(col("column3"), lit(&"a")), gt(col("sum"), lit(&10))));
+
+        assert_eq!(
+            optimize(df),
+            "Filter: sum>10
+  Aggregate: groupExpr=column3, aggregateExpr=sum(column1)
+    Scan: test.csv, projection=None, filters=[column3='a']
+"
+        );
+    }
+
+    #[test]
+    fn above_aggregate_without_groups() {
+        let df = generate_df()
+            .aggregate(vec![], vec![sum(col("column1"))])
+            .filter(eq(lit(&1), lit(&5)));
+
+        assert_eq!(
+            optimize(df),
+            "Filter: 1=5
+  Aggregate: groupExpr=, aggregateExpr=sum(column1)
+    Scan: test.csv, projection=None
+"
+        );
+
+        // Below the aggregate, a false filter would still leave its one row.
+        let dir = tempfile::tempdir().unwrap();
+        let df = ExecutionContext::new()
+            .csv(
+                schema(vec![("a", ArrowType::Int64Type, false)]),
+                &write_csv(&dir, "a.csv", &["1", "2"]),
+            )
+            .aggregate(vec![], vec![sum(col("a"))]);
+        let filtered = |value: i64| {
+            let df = df.filter(eq(lit(&value), lit(&5)));
+            let plan = PredicatePushdown::new().optimize(df.plan()).unwrap();
+            rows(&collect(PhysicalPlanner::new().create_physical_plan(&plan).unwrap()).unwrap())
+        };
+        assert!(filtered(1).is_empty());
+        assert_eq!(filtered(5), vec!["3"]);
+    }
+
+    #[test]
+    fn below_sort() {
+        let df = generate_df()
+            .sort(vec![desc(col("column2"))])
+            .filter(gt(col("column1"), lit(&1)));
+
+        assert_eq!(
+            optimize(df),
+            "Sort: column2 DESC NULLS LAST
+  Scan: test.csv, projection=None, filters=[column1>1]
+"
+        );
+
+        // Filtering first would change which rows are kept.
+        let df = generate_df()
+            .sort(vec![desc(col("column2"))])
+            .limit(0, Some(5))
+            .filter(gt(col("column1"), lit(&1)));
+        assert_eq!(
+            optimize(df),
+            "Filter: column1>1
+  Limit: skip=0, fetch=5
+    Sort: column2 DESC NULLS LAST
+      Scan: test.csv, projection=None
+"
+        );
+    }
+
+    /// A source that uses equality filters to skip rows, but can't apply them exactly.
+    struct InexactSource {
+        schema: Arc<Schema>,
+    }
+
+    impl DataSource for InexactSource {
+        fn schema(&self) -> Arc<Schema> {
+            Arc::clone(&self.schema)
+        }
+
+        fn supports_filter(&self, filter: &Arc<dyn LogicalExpression>) -> FilterPushdown {
+            match filter.as_any().downcast_ref::<BooleanExpression>() {
+                Some(b) if b.name() == "eq" => FilterPushdown::Inexact,
+                _ => FilterPushdown::Unsupported,
+            }
+        }
+
+        fn scan(
+            &self,
+            _projection: Vec<String>,
+            _filters: Vec<Arc<dyn LogicalExpression>>,
+        ) -> Result<BatchIterator> {
+            Ok(Box::new(std::iter::empty()))
+        }
+    }
+
+    #[test]
+    fn inexact_and_unsupported_filters() {
+        let schema = schema(vec![
+            ("column1", ArrowType::Int64Type, false),
+            ("column2", ArrowType::Int64Type, false),
+        ]);
+        let source = InexactSource {
+            schema: Arc::clone(&schema),
+        };
+        let scan = Scan::new("source".to_owned(), schema, Arc::new(source), vec![]);
+        let plan: Arc<dyn LogicalPlan> = Arc::new(Selection::new(
+            Arc::new(scan),
+            and(eq(col("column1"), lit(&1)), gt(col("column2"), lit(&2))),
+        ));
+
+        let once = PredicatePushdown::new().optimize(plan).unwrap();
+        let expected = "Filter: column1=1 AND column2>2
+  Scan: source, projection=None, filters=[column1=1]
+";
+        assert_eq!(once.format(), expected);
+
+        // Filters already handed to the source aren't duplicated on later passes.
+        let twice = PredicatePushdown::new().optimize(once).unwrap();
+        assert_eq!(twice.format(), expected);
+    }
+
+    #[test]
+    fn is_stable() {
+        let df = generate_df()
+            .select(vec![col("column1"), col("column3")])
+            .filter(eq(col("column1"), lit(&5)));
+        let once = PredicatePushdown::new().optimize(df.plan()).unwrap();
+        let twice = PredicatePushdown::new()
+            .optimize(Arc::clone(&once))
+            .unwrap();
+
+        assert_eq!(once.format(), twice.format());
+    }
+
+    #[test]
+    fn same_results() {
+        use crate::test_util::write_csv;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_csv(&dir, "input.csv", &["1,10,a", "2,20,b", "3,30,a"]);
+        let schema = schema(vec![
+            ("column1", ArrowType::Int64Type, false),
+            ("column2", ArrowType::Int64Type, false),
+            ("column3", ArrowType::StringType, false),
+        ]);
+        let df = ExecutionContext::new()
+            .csv(schema, &path)
+            .select(vec![alias(
+                multiply(col("column1"), col("column2")),
+                "product",
+            )])
+            .filter(gt(col("product"), lit(&20)));
+
+        let batches = df.collect().unwrap();
+        let total_rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+        assert_eq!(total_rows, 2);
+    }
+}
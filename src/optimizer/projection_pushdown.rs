@@ -0,0 +1,259 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use super::utils::{expr_columns, exprlist_columns};
+use super::OptimizerRule;
+use crate::core::error::Result;
+use crate::logical_plan::aggregate::Aggregate;
+use crate::logical_plan::expression::LogicalExpression;
+use crate::logical_plan::join::Join;
+use crate::logical_plan::limit::Limit;
+use crate::logical_plan::projection::Projection;
+use crate::logical_plan::scan::Scan;
+use crate::logical_plan::selection::Selection;
+use crate::logical_plan::sort::Sort;
+use crate::logical_plan::subquery_alias::SubqueryAlias;
+use crate::logical_plan::LogicalPlan;
+
+/// Rewrites every `Scan` so that it only reads the columns that the rest of the plan
+/// references.
+#[derive(Default)]
+pub struct ProjectionPushdown {}
+
+impl OptimizerRule for ProjectionPushdown {
+    fn name(&self) -> &str {
+        "projection_pushdown"
+    }
+
+    fn optimize(&self, plan: Arc<dyn LogicalPlan>) -> Result<Arc<dyn LogicalPlan>> {
+        self.optimize_plan(plan, None)
+    }
+}
+
+impl ProjectionPushdown {
+    pub fn new() -> ProjectionPushdown {
+        ProjectionPushdown {}
+    }
+
+    /// Pushes the set of `required` columns down through `plan`. `None` means that
+    /// every column of `plan` is needed.
+    fn optimize_plan(
+        &self,
+        plan: Arc<dyn LogicalPlan>,
+        required: Option<&HashSet<String>>,
+    ) -> Result<Arc<dyn LogicalPlan>> {
+        let any = plan.as_any();
+
+        if let Some(projection) = any.downcast_ref::<Projection>() {
+            let required = exprlist_columns(projection.expr());
+            let input = self.optimize_plan(projection.input(), Some(&required))?;
+            return plan.try_with_new_children(vec![input]);
+        }
+
+        if let Some(selection) = any.downcast_ref::<Selection>() {
+            let required = required.map(|r| {
+                let mut r = r.clone();
+                expr_columns(&selection.expr(), &mut r);
+                r
+            });
+            let input = self.optimize_plan(selection.input(), required.as_ref())?;
+            return plan.try_with_new_children(vec![input]);
+        }
+
+        if let Some(sort) = any.downcast_ref::<Sort>() {
+            let required = required.map(|r| {
+                let mut r = r.clone();
+                r.extend(exprlist_columns(&sort.expressions()));
+                r
+            });
+            let input = self.optimize_plan(sort.input(), required.as_ref())?;
+            return plan.try_with_new_children(vec![input]);
+        }
+
+        if let Some(aggregate) = any.downcast_ref::<Aggregate>() {
+            let mut required = exprlist_columns(aggregate.group_expr());
+            for a in aggregate.aggregate_expr() {
+                expr_columns(
+                    &(Arc::clone(a) as Arc<dyn LogicalExpression>),
+                    &mut required,
+                );
+            }
+            let input = self.optimize_plan(aggregate.input(), Some(&required))?;
+            return plan.try_with_new_children(vec![input]);
+        }
+
+        if let Some(limit) = any.downcast_ref::<Limit>() {
+            let input = self.optimize_plan(limit.input(), required)?;
+            return plan.try_with_new_children(vec![input]);
+        }
+
+        if let Some(alias) = any.downcast_ref::<SubqueryAlias>() {
+            let input = self.optimize_plan(alias.input(), required)?;
+            return plan.try_with_new_children(vec![input]);
+        }
+
+        if let Some(join) = any.downcast_ref::<Join>() {
+            // Work out the columns needed from the joined rows, which name the columns
+            // of both sides as they appear in the output of an inner join.
+            let mut joined = match (join.join_type().includes_right(), required) {
+                (true, required) => required.cloned(),
+                // The output of semi and anti joins is the left input, whose columns may
+                // be qualified in the joined rows.
+                (false, required) => Some(
+                    join.left()
+                        .schema()
+                        .fields()
+                        .iter()
+                        .filter(|f| required.is_none_or(|r| r.contains(f.name())))
+                        .map(|f| join.left_column(f.name()))
+                        .collect(),
+                ),
+            };
+            if let (Some(joined), Some(filter)) = (joined.as_mut(), join.filter()) {
+                expr_columns(&filter, joined);
+            }
+
+            let left_keys: Vec<String> = join.on().iter().map(|(l, _)| l.clone()).collect();
+            let right_keys: Vec<String> = join.on().iter().map(|(_, r)| r.clone()).collect();
+            let left_required = join_side_columns(&join.left(), &left_keys, joined.as_ref(), |c| {
+                join.left_column(c)
+            });
+            let right_required =
+                join_side_columns(&join.right(), &right_keys, joined.as_ref(), |c| {
+                    join.right_column(c)
+                });
+            let left = self.optimize_plan(join.left(), left_required.as_ref())?;
+            let right = self.optimize_plan(join.right(), right_required.as_ref())?;
+            return plan.try_with_new_children(vec![left, right]);
+        }
+
+        if let Some(scan) = any.downcast_ref::<Scan>() {
+            let required = match required {
+                Some(required) => required,
+                None => return Ok(plan),
+            };
+            let available = scan.schema();
+            let mut projection: Vec<String> = available
+                .fields()
+                .iter()
+                .map(|f| f.name().clone())
+                .filter(|name| required.contains(name))
+                .collect();
+            // A batch needs at least one column to carry its row count.
+            if projection.is_empty() {
+                projection.push(available.field(0).name().clone());
+            }
+            return Ok(Arc::new(scan.with_projection(projection)));
+        }
+
+        // Any other node may need every column of its inputs.
+        let children = plan
+            .children()
+            .into_iter()
+            .map(|child| self.optimize_plan(child, None))
+            .collect::<Result<Vec<_>>>()?;
+        plan.try_with_new_children(children)
+    }
+}
+
+/// Returns the columns that one side of a join has to produce: those that are
+/// `required` of the joined rows, in which `joined_name` gives their names, plus its
+/// join `keys`.
+fn join_side_columns(
+    input: &Arc<dyn LogicalPlan>,
+    keys: &[String],
+    required: Option<&HashSet<String>>,
+    joined_name: impl Fn(&str) -> String,
+) -> Option<HashSet<String>> {
+    required.map(|required| {
+        input
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .filter(|name| required.contains(&joined_name(name)) || keys.contains(name))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data_frame::DataFrame;
+    use crate::core::data_type::ArrowType;
+    use crate::core::execution_context::ExecutionContext;
+    use crate::core::helper::*;
+
+    fn generate_df() -> DataFrame {
+        let schema = schema(vec![
+            ("column1", ArrowType::Int64Type, false),
+            ("column2", ArrowType::Int64Type, false),
+            ("column3", ArrowType::Int64Type, false),
+            ("column4", ArrowType::StringType, false),
+        ]);
+        ExecutionContext::new().csv(schema, "test.csv")
+    }
+
+    fn optimize(df: DataFrame) -> String {
+        ProjectionPushdown::new()
+            .optimize(df.plan())
+            .unwrap()
+            .format()
+    }
+
+    #[test]
+    fn projection() {
+        let df = generate_df().select(vec![col("column3"), col("column1")]);
+
+        assert_eq!(
+            optimize(df),
+            "Projection: column3, column1
+  Scan: test.csv, projection=[\"column1\", \"column3\"]
+"
+        );
+    }
+
+    #[test]
+    fn selection_below_projection() {
+        let df = generate_df()
+            .filter(eq(col("column4"), lit(&"abc")))
+            .select(vec![add(col("column1"), col("column2"))]);
+
+        assert_eq!(
+            optimize(df),
+            "Projection: column1 + column2
+  Filter: column4='abc'
+    Scan: test.csv, projection=[\"column1\", \"column2\", \"column4\"]
+"
+        );
+    }
+
+    #[test]
+    fn aggregate() {
+        let df = generate_df().aggregate(vec![col("column4")], vec![max(col("column2"))]);
+
+        assert_eq!(
+            optimize(df),
+            "Aggregate: groupExpr=column4, aggregateExpr=max(column2)
+  Scan: test.csv, projection=[\"column2\", \"column4\"]
+"
+        );
+    }
+
+    #[test]
+    fn no_columns_referenced() {
+        let df = generate_df().select(vec![lit(&1)]);
+
+        assert_eq!(
+            optimize(df),
+            "Projection: 1
+  Scan: test.csv, projection=[\"column1\"]
+"
+        );
+    }
+
+    #[test]
+    fn bare_scan() {
+        assert_eq!(optimize(generate_df()), "Scan: test.csv, projection=None\n");
+    }
+}
@@ -0,0 +1,352 @@
+use std::sync::Arc;
+
+use super::OptimizerRule;
+use crate::core::error::Result;
+use crate::logical_plan::aggregate::Aggregate;
+use crate::logical_plan::empty_relation::EmptyRelation;
+use crate::logical_plan::expression::{
+    AggregateExpression, AliasExpression, BinaryExpression, BooleanExpression,
+    LiteralBooleanExpression, LiteralI64Expression, LiteralStringExpression, LogicalExpression,
+    MathExpression, NotExpression,
+};
+use crate::logical_plan::projection::Projection;
+use crate::logical_plan::selection::Selection;
+use crate::logical_plan::LogicalPlan;
+
+/// Evaluates expressions that only depend on literals, simplifies boolean identities and
+/// removes filters whose outcome is known up front.
+#[derive(Default)]
+pub struct SimplifyExpressions {}
+
+impl OptimizerRule for SimplifyExpressions {
+    fn name(&self) -> &str {
+        "simplify_expressions"
+    }
+
+    fn optimize(&self, plan: Arc<dyn LogicalPlan>) -> Result<Arc<dyn LogicalPlan>> {
+        let children = plan
+            .children()
+            .into_iter()
+            .map(|child| self.optimize(child))
+            .collect::<Result<Vec<_>>>()?;
+        let any = plan.as_any();
+
+        if let Some(selection) = any.downcast_ref::<Selection>() {
+            let input = Arc::clone(&children[0]);
+            if input.as_any().downcast_ref::<EmptyRelation>().is_some() {
+                return Ok(input);
+            }
+            let expr = simplify(&selection.expr());
+            return Ok(match as_bool_literal(&expr) {
+                Some(true) => input,
+                Some(false) => Arc::new(EmptyRelation::new(input.schema())),
+                None => Arc::new(Selection::new(input, expr)),
+            });
+        }
+
+        if let Some(projection) = any.downcast_ref::<Projection>() {
+            let input = Arc::clone(&children[0]);
+            let expr = projection
+                .expr()
+                .iter()
+                .map(|e| simplify_named(e, &input))
+                .collect();
+            return Ok(Arc::new(Projection::new(input, expr)));
+        }
+
+        if let Some(aggregate) = any.downcast_ref::<Aggregate>() {
+            let input = Arc::clone(&children[0]);
+            let groupexpr = aggregate
+                .group_expr()
+                .iter()
+                .map(|e| simplify_named(e, &input))
+                .collect();
+            let aggregateexpr = aggregate
+                .aggregate_expr()
+                .iter()
+                .map(|a| {
+                    // The aggregate is named after its function, so only its input changes.
+                    let simplified = a.with_new_children(vec![simplify(&a.expr())]);
+                    Arc::new(
+                        simplified
+                            .as_any()
+                            .downcast_ref::<AggregateExpression>()
+                            .unwrap()
+                            .clone(),
+                    )
+                })
+                .collect();
+            return Ok(Arc::new(Aggregate::new(input, groupexpr, aggregateexpr)));
+        }
+
+        plan.try_with_new_children(children)
+    }
+}
+
+impl SimplifyExpressions {
+    pub fn new() -> SimplifyExpressions {
+        SimplifyExpressions {}
+    }
+}
+
+/// Simplifies an expression that names an output column of `input`, keeping the
+/// original name if simplification would change it.
+fn simplify_named(
+    expr: &Arc<dyn LogicalExpression>,
+    input: &Arc<dyn LogicalPlan>,
+) -> Arc<dyn LogicalExpression> {
+    let simplified = simplify(expr);
+    if simplified.to_string() == expr.to_string() {
+        return Arc::clone(expr);
+    }
+    let name = expr.to_field(Arc::clone(input)).name().clone();
+    if simplified.to_field(Arc::clone(input)).name() == &name {
+        simplified
+    } else {
+        Arc::new(AliasExpression::new(simplified, name))
+    }
+}
+
+/// Simplifies `expr` bottom-up.
+pub fn simplify(expr: &Arc<dyn LogicalExpression>) -> Arc<dyn LogicalExpression> {
+    let children = expr.children();
+    let expr = if children.is_empty() {
+        Arc::clone(expr)
+    } else {
+        expr.with_new_children(children.iter().map(simplify).collect())
+    };
+    let any = expr.as_any();
+
+    if let Some(binary) = any.downcast_ref::<MathExpression>() {
+        if let (Some(l), Some(r)) = (as_i64_literal(&binary.l()), as_i64_literal(&binary.r())) {
+            if let Some(val) = fold_math(&binary.name(), l, r) {
+                return Arc::new(LiteralI64Expression::new(val));
+            }
+        }
+    }
+
+    if let Some(binary) = any.downcast_ref::<BooleanExpression>() {
+        let (l, r) = (binary.l(), binary.r());
+        match binary.name().as_str() {
+            "and" => {
+                match (as_bool_literal(&l), as_bool_literal(&r)) {
+                    (Some(false), _) | (_, Some(false)) => {
+                        return Arc::new(LiteralBooleanExpression::new(false))
+                    }
+                    (Some(true), _) => return r,
+                    (_, Some(true)) => return l,
+                    _ => {}
+                };
+            }
+            "or" => {
+                match (as_bool_literal(&l), as_bool_literal(&r)) {
+                    (Some(true), _) | (_, Some(true)) => {
+                        return Arc::new(LiteralBooleanExpression::new(true))
+                    }
+                    (Some(false), _) => return r,
+                    (_, Some(false)) => return l,
+                    _ => {}
+                };
+            }
+            name => {
+                if let Some(val) = fold_comparison(name, &l, &r) {
+                    return Arc::new(LiteralBooleanExpression::new(val));
+                }
+            }
+        }
+    }
+
+    if let Some(not) = any.downcast_ref::<NotExpression>() {
+        let inner = not.expr();
+        if let Some(val) = as_bool_literal(&inner) {
+            return Arc::new(LiteralBooleanExpression::new(!val));
+        }
+        if let Some(inner) = inner.as_any().downcast_ref::<NotExpression>() {
+            return inner.expr();
+        }
+    }
+
+    expr
+}
+
+fn as_bool_literal(expr: &Arc<dyn LogicalExpression>) -> Option<bool> {
+    expr.as_any()
+        .downcast_ref::<LiteralBooleanExpression>()
+        .map(|l| l.val())
+}
+
+fn as_i64_literal(expr: &Arc<dyn LogicalExpression>) -> Option<i64> {
+    expr.as_any()
+        .downcast_ref::<LiteralI64Expression>()
+        .map(|l| l.val())
+}
+
+fn as_string_literal(expr: &Arc<dyn LogicalExpression>) -> Option<&str> {
+    expr.as_any()
+        .downcast_ref::<LiteralStringExpression>()
+        .map(|l| l.val())
+}
+
+/// Evaluates a math operator over two integers. Overflow and division by zero are left
+/// for execution to report.
+fn fold_math(name: &str, l: i64, r: i64) -> Option<i64> {
+    match name {
+        "add" => l.checked_add(r),
+        "subtract" => l.checked_sub(r),
+        "mult" => l.checked_mul(r),
+        "div" => l.checked_div(r),
+        "modulus" => l.checked_rem(r),
+        _ => None,
+    }
+}
+
+fn fold_comparison(
+    name: &str,
+    l: &Arc<dyn LogicalExpression>,
+    r: &Arc<dyn LogicalExpression>,
+) -> Option<bool> {
+    if let (Some(l), Some(r)) = (as_i64_literal(l), as_i64_literal(r)) {
+        return compare(name, l, r);
+    }
+    if let (Some(l), Some(r)) = (as_string_literal(l), as_string_literal(r)) {
+        return compare(name, l, r);
+    }
+    if let (Some(l), Some(r)) = (as_bool_literal(l), as_bool_literal(r)) {
+        return compare(name, l, r);
+    }
+    None
+}
+
+fn compare<T: PartialOrd>(name: &str, l: T, r: T) -> Option<bool> {
+    match name {
+        "eq" => Some(l == r),
+        "neq" => Some(l != r),
+        "gt" => Some(l > r),
+        "lt" => Some(l < r),
+        "gteq" => Some(l >= r),
+        "lteq" => Some(l <= r),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data_frame::DataFrame;
+    use crate::core::data_type::ArrowType;
+    use crate::core::execution_context::ExecutionContext;
+    use crate::core::helper::*;
+
+    fn generate_df() -> DataFrame {
+        let schema = schema(vec![
+            ("column1", ArrowType::Int64Type, false),
+            ("column2", ArrowType::Int64Type, false),
+            ("column3", ArrowType::StringType, false),
+        ]);
+        ExecutionContext::new().csv(schema, "test.csv")
+    }
+
+    fn optimize(df: DataFrame) -> String {
+        SimplifyExpressions::new()
+            .optimize(df.plan())
+            .unwrap()
+            .format()
+    }
+
+    #[test]
+    fn folds_literals() {
+        let expr = multiply(add(lit(&2), lit(&3)), subtract(lit(&10), col("column1")));
+        assert_eq!(simplify(&(expr as _)).to_string(), "5 * 10 - column1");
+
+        let expr = gt(modulus(lit(&7), lit(&4)), lit(&2));
+        assert_eq!(simplify(&(expr as _)).to_string(), "true");
+
+        let expr = eq(lit(&"a"), lit(&"b"));
+        assert_eq!(simplify(&(expr as _)).to_string(), "false");
+
+        // Division by zero is reported when the query runs, not swallowed here.
+        let expr = divide(lit(&1), lit(&0));
+        assert_eq!(simplify(&(expr as _)).to_string(), "1 / 0");
+    }
+
+    #[test]
+    fn boolean_identities() {
+        let x: Arc<dyn LogicalExpression> = gt(col("column1"), lit(&1));
+        let cases: Vec<(Arc<dyn LogicalExpression>, &str)> = vec![
+            (and(Arc::clone(&x), lit(&true)), "column1>1"),
+            (and(lit(&true), Arc::clone(&x)), "column1>1"),
+            (and(Arc::clone(&x), lit(&false)), "false"),
+            (or(Arc::clone(&x), lit(&false)), "column1>1"),
+            (or(lit(&true), Arc::clone(&x)), "true"),
+            (not(not(Arc::clone(&x))), "column1>1"),
+            (not(eq(lit(&1), lit(&2))), "true"),
+            (
+                and(x, or(lit(&false), eq(lit(&2), add(lit(&1), lit(&1))))),
+                "column1>1",
+            ),
+        ];
+        for (expr, expected) in cases {
+            assert_eq!(simplify(&expr).to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn removes_always_true_filter() {
+        let df = generate_df()
+            .filter(or(gt(col("column1"), lit(&1)), lit(&true)))
+            .select(vec![col("column1")]);
+
+        assert_eq!(
+            optimize(df),
+            "Projection: column1\n  Scan: test.csv, projection=None\n"
+        );
+    }
+
+    #[test]
+    fn replaces_always_false_filter() {
+        let df = generate_df()
+            .filter(and(gt(col("column1"), lit(&1)), lt(lit(&3), lit(&2))))
+            .select(vec![col("column1")]);
+
+        assert_eq!(optimize(df), "Projection: column1\n  EmptyRelation\n");
+    }
+
+    #[test]
+    fn keeps_output_names() {
+        let df = generate_df().select(vec![
+            add(lit(&2), lit(&3)),
+            alias(add(col("column1"), multiply(lit(&2), lit(&3))), "total"),
+        ]);
+        let before = df.schema();
+
+        let plan = SimplifyExpressions::new().optimize(df.plan()).unwrap();
+        assert_eq!(
+            LogicalPlan::to_string(plan.as_ref()),
+            "Projection: 5 AS add, column1 + 6 AS total"
+        );
+        assert_eq!(plan.schema(), before);
+    }
+
+    #[test]
+    fn empty_results() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = crate::test_util::write_csv(&dir, "input.csv", &["1,10,a", "2,20,b"]);
+        let schema = schema(vec![
+            ("column1", ArrowType::Int64Type, false),
+            ("column2", ArrowType::Int64Type, false),
+            ("column3", ArrowType::StringType, false),
+        ]);
+        let df = ExecutionContext::new()
+            .csv(schema, &path)
+            .filter(eq(lit(&1), lit(&2)));
+
+        assert!(df.collect().unwrap().is_empty());
+
+        // A global aggregate still produces its single row over no input.
+        let batches = df
+            .aggregate(vec![], vec![sum(col("column1"))])
+            .collect()
+            .unwrap();
+        assert_eq!(batches[0].num_rows(), 1);
+    }
+}
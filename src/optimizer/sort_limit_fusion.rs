@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use super::OptimizerRule;
+use crate::core::error::Result;
+use crate::logical_plan::limit::Limit;
+use crate::logical_plan::projection::Projection;
+use crate::logical_plan::sort::Sort;
+use crate::logical_plan::subquery_alias::SubqueryAlias;
+use crate::logical_plan::LogicalPlan;
+
+/// Tells a sort under a limit how many rows the limit needs, so that only those are
+/// kept while sorting instead of sorting the whole input. The limit stays in place to
+/// skip the rows before its offset.
+#[derive(Default)]
+pub struct SortLimitFusion {}
+
+impl OptimizerRule for SortLimitFusion {
+    fn name(&self) -> &str {
+        "sort_limit_fusion"
+    }
+
+    fn optimize(&self, plan: Arc<dyn LogicalPlan>) -> Result<Arc<dyn LogicalPlan>> {
+        let children = plan
+            .children()
+            .into_iter()
+            .map(|child| self.optimize(child))
+            .collect::<Result<Vec<_>>>()?;
+        let plan = if children.is_empty() {
+            plan
+        } else {
+            plan.try_with_new_children(children)?
+        };
+
+        if let Some(limit) = plan.as_any().downcast_ref::<Limit>() {
+            // A limit past the largest row count needs every row, so the sort is left alone.
+            if let Some(fetch) = limit.fetch().and_then(|f| f.checked_add(limit.skip())) {
+                if let Some(input) = fetch_sorted(&limit.input(), fetch) {
+                    return plan.try_with_new_children(vec![input]);
+                }
+            }
+        }
+        Ok(plan)
+    }
+}
+
+impl SortLimitFusion {
+    pub fn new() -> SortLimitFusion {
+        SortLimitFusion {}
+    }
+}
+
+/// Limits the sort that produces the rows of `plan` to its first `fetch` rows, looking
+/// through the nodes that keep the position of every row they pass on. Returns `None` if there is
+/// no such sort.
+fn fetch_sorted(plan: &Arc<dyn LogicalPlan>, fetch: usize) -> Option<Arc<dyn LogicalPlan>> {
+    let any = plan.as_any();
+    if let Some(sort) = any.downcast_ref::<Sort>() {
+        let fetch = sort.fetch().map_or(fetch, |f| f.min(fetch));
+        return Some(Arc::new(
+            Sort::new(sort.input(), sort.expr().to_vec()).with_fetch(Some(fetch)),
+        ));
+    }
+    let input = if let Some(projection) = any.downcast_ref::<Projection>() {
+        fetch_sorted(&projection.input(), fetch)
+    } else if let Some(alias) = any.downcast_ref::<SubqueryAlias>() {
+        fetch_sorted(&alias.input(), fetch)
+    } else if let Some(limit) = any.downcast_ref::<Limit>() {
+        fetch_sorted(&limit.input(), limit.skip().checked_add(fetch)?)
+    } else {
+        None
+    }?;
+    Some(plan.with_new_children(vec![input]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data_type::ArrowType;
+    use crate::core::execution_context::ExecutionContext;
+    use crate::core::helper::*;
+
+    #[test]
+    fn fuses_sort_and_limit() {
+        let double = schema(vec![
+            ("column1", ArrowType::Int64Type, false),
+            ("column2", ArrowType::Int64Type, false),
+        ]);
+        let df = ExecutionContext::new()
+            .csv(double, "test.csv")
+            .sort(vec![desc(col("column2"))])
+            .select(vec![col("column1")])
+            .limit(5, Some(10));
+        let plan = SortLimitFusion::new().optimize(df.plan()).unwrap();
+        assert_eq!(
+            plan.format(),
+            "Limit: skip=5, fetch=10
+  Projection: column1
+    Sort: column2 DESC NULLS LAST, fetch=15
+      Scan: test.csv, projection=None
+"
+        );
+
+        // A tighter limit further up lowers the fetch, a looser one leaves it.
+        let tighter = Arc::new(Limit::new(Arc::clone(&plan), 0, Some(3)));
+        let tighter = SortLimitFusion::new().optimize(tighter).unwrap();
+        assert!(tighter.format().contains("fetch=8\n        Scan"));
+        let looser = Arc::new(Limit::new(plan, 0, Some(30)));
+        let looser = SortLimitFusion::new().optimize(looser).unwrap();
+        assert!(looser.format().contains("fetch=15\n        Scan"));
+
+        // Rows are not fetched from a sort under a filter, without a fetch count, or when
+        // the offset and fetch count add up past the largest row count.
+        let single = schema(vec![("column1", ArrowType::Int64Type, false)]);
+        let ctx = ExecutionContext::new();
+        let sorted = || {
+            ctx.csv(Arc::clone(&single), "test.csv")
+                .sort(vec![asc(col("column1"))])
+        };
+        for df in [
+            sorted()
+                .filter(gt(col("column1"), lit(&1)))
+                .limit(0, Some(1)),
+            sorted().limit(1, None),
+            sorted().limit(1, Some(usize::MAX)),
+        ] {
+            let plan = SortLimitFusion::new().optimize(df.plan()).unwrap();
+            assert_eq!(plan.format(), df.plan().format());
+        }
+        // A limit further up still bounds the sort.
+        let df = sorted().limit(1, Some(usize::MAX)).limit(0, Some(1));
+        let plan = SortLimitFusion::new().optimize(df.plan()).unwrap();
+        assert!(plan.format().contains("fetch=2\n      Scan"));
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::logical_plan::expression::{
+    AliasExpression, BinaryExpression, BooleanExpression, ColumnExpression, LogicalExpression,
+};
+
+/// Adds the name of every column referenced by `expr` to `columns`.
+pub fn expr_columns(expr: &Arc<dyn LogicalExpression>, columns: &mut HashSet<String>) {
+    if let Some(column) = expr.as_any().downcast_ref::<ColumnExpression>() {
+        columns.insert(column.name().to_owned());
+    }
+    for child in expr.children() {
+        expr_columns(&child, columns);
+    }
+}
+
+/// Returns the name of every column referenced by any of `exprs`.
+pub fn exprlist_columns(exprs: &[Arc<dyn LogicalExpression>]) -> HashSet<String> {
+    let mut columns = HashSet::new();
+    for expr in exprs {
+        expr_columns(expr, &mut columns);
+    }
+    columns
+}
+
+/// Splits a predicate into the parts that are combined with `AND`.
+pub fn split_conjunction(expr: &Arc<dyn LogicalExpression>) -> Vec<Arc<dyn LogicalExpression>> {
+    match expr.as_any().downcast_ref::<BooleanExpression>() {
+        Some(binary) if binary.name() == "and" => {
+            let mut exprs = split_conjunction(&binary.l());
+            exprs.extend(split_conjunction(&binary.r()));
+            exprs
+        }
+        _ => vec![Arc::clone(expr)],
+    }
+}
+
+/// Combines predicates with `AND`, returning `None` when there are none.
+pub fn conjunction(exprs: Vec<Arc<dyn LogicalExpression>>) -> Option<Arc<dyn LogicalExpression>> {
+    exprs
+        .into_iter()
+        .reduce(|l, r| Arc::new(BooleanExpression::and(l, r)))
+}
+
+/// Replaces every column of `expr` that has an entry in `replacements`.
+pub fn replace_columns(
+    expr: &Arc<dyn LogicalExpression>,
+    replacements: &HashMap<String, Arc<dyn LogicalExpression>>,
+) -> Arc<dyn LogicalExpression> {
+    if let Some(column) = expr.as_any().downcast_ref::<ColumnExpression>() {
+        if let Some(replacement) = replacements.get(column.name()) {
+            return Arc::clone(replacement);
+        }
+    }
+    let children = expr.children();
+    if children.is_empty() {
+        return Arc::clone(expr);
+    }
+    expr.with_new_children(
+        children
+            .iter()
+            .map(|c| replace_columns(c, replacements))
+            .collect(),
+    )
+}
+
+/// Strips any aliases from the top of `expr`.
+pub fn unalias(expr: &Arc<dyn LogicalExpression>) -> Arc<dyn LogicalExpression> {
+    match expr.as_any().downcast_ref::<AliasExpression>() {
+        Some(alias) => unalias(&alias.expr()),
+        None => Arc::clone(expr),
+    }
+}
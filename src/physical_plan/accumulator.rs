@@ -0,0 +1,401 @@
+use arrow::array::ArrayRef;
+use arrow::datatypes::DataType;
+use std::sync::Arc;
+
+use super::expression::PhysicalExpression;
+use crate::core::error::{QrustError, Result};
+use crate::core::scalar_value::ScalarValue;
+
+/// Holds the running state of an aggregate function for a single group.
+pub trait Accumulator: Send {
+    /// Folds the value at `index` of `values` into the accumulator.
+    fn update(&mut self, values: &ArrayRef, index: usize) -> Result<()>;
+    /// Returns the aggregate of every value seen so far.
+    fn evaluate(&self) -> Result<ScalarValue>;
+}
+
+/// An aggregate function applied to the result of an input expression. New aggregates
+/// are added by implementing this trait along with an `Accumulator`.
+pub trait AggregateExpression: Send + Sync {
+    /// The expression whose values are fed to the accumulators.
+    fn expr(&self) -> Arc<dyn PhysicalExpression>;
+    /// Creates a fresh accumulator for a new group.
+    fn create_accumulator(&self) -> Box<dyn Accumulator>;
+    fn to_string(&self) -> String;
+}
+
+pub struct SumExpression {
+    expr: Arc<dyn PhysicalExpression>,
+    data_type: DataType,
+}
+
+impl AggregateExpression for SumExpression {
+    fn expr(&self) -> Arc<dyn PhysicalExpression> {
+        Arc::clone(&self.expr)
+    }
+
+    fn create_accumulator(&self) -> Box<dyn Accumulator> {
+        Box::new(SumAccumulator {
+            sum: ScalarValue::try_null(&self.data_type).unwrap(),
+        })
+    }
+
+    fn to_string(&self) -> String {
+        format!("sum({})", self.expr.to_string())
+    }
+}
+
+impl SumExpression {
+    pub fn try_new(
+        expr: Arc<dyn PhysicalExpression>,
+        data_type: DataType,
+    ) -> Result<SumExpression> {
+        match data_type {
+            DataType::Int64 | DataType::Float64 => Ok(SumExpression { expr, data_type }),
+            other => Err(QrustError::Plan(format!(
+                "Cannot sum values of type {}",
+                other
+            ))),
+        }
+    }
+}
+
+struct SumAccumulator {
+    sum: ScalarValue,
+}
+
+impl Accumulator for SumAccumulator {
+    fn update(&mut self, values: &ArrayRef, index: usize) -> Result<()> {
+        let value = ScalarValue::try_from_array(values, index)?;
+        self.sum = match (&self.sum, value) {
+            (_, ScalarValue::Int64(None)) | (_, ScalarValue::Float64(None)) => return Ok(()),
+            (ScalarValue::Int64(s), ScalarValue::Int64(Some(v))) => {
+                let sum = s
+                    .unwrap_or(0)
+                    .checked_add(v)
+                    .ok_or_else(|| QrustError::Execution("Int64 overflow in sum".to_owned()))?;
+                ScalarValue::Int64(Some(sum))
+            }
+            (ScalarValue::Float64(s), ScalarValue::Float64(Some(v))) => {
+                ScalarValue::Float64(Some(s.unwrap_or(0.0) + v))
+            }
+            (s, v) => {
+                return Err(QrustError::Execution(format!(
+                    "Cannot add {:?} to a sum of type {}",
+                    v,
+                    s.data_type()
+                )))
+            }
+        };
+        Ok(())
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        Ok(self.sum.clone())
+    }
+}
+
+pub struct MinExpression {
+    expr: Arc<dyn PhysicalExpression>,
+    data_type: DataType,
+}
+
+impl AggregateExpression for MinExpression {
+    fn expr(&self) -> Arc<dyn PhysicalExpression> {
+        Arc::clone(&self.expr)
+    }
+
+    fn create_accumulator(&self) -> Box<dyn Accumulator> {
+        Box::new(MinMaxAccumulator {
+            value: ScalarValue::try_null(&self.data_type).unwrap(),
+            keep_smaller: true,
+        })
+    }
+
+    fn to_string(&self) -> String {
+        format!("min({})", self.expr.to_string())
+    }
+}
+
+impl MinExpression {
+    pub fn try_new(
+        expr: Arc<dyn PhysicalExpression>,
+        data_type: DataType,
+    ) -> Result<MinExpression> {
+        ScalarValue::try_null(&data_type)?;
+        Ok(MinExpression { expr, data_type })
+    }
+}
+
+pub struct MaxExpression {
+    expr: Arc<dyn PhysicalExpression>,
+    data_type: DataType,
+}
+
+impl AggregateExpression for MaxExpression {
+    fn expr(&self) -> Arc<dyn PhysicalExpression> {
+        Arc::clone(&self.expr)
+    }
+
+    fn create_accumulator(&self) -> Box<dyn Accumulator> {
+        Box::new(MinMaxAccumulator {
+            value: ScalarValue::try_null(&self.data_type).unwrap(),
+            keep_smaller: false,
+        })
+    }
+
+    fn to_string(&self) -> String {
+        format!("max({})", self.expr.to_string())
+    }
+}
+
+impl MaxExpression {
+    pub fn try_new(
+        expr: Arc<dyn PhysicalExpression>,
+        data_type: DataType,
+    ) -> Result<MaxExpression> {
+        ScalarValue::try_null(&data_type)?;
+        Ok(MaxExpression { expr, data_type })
+    }
+}
+
+/// Tracks the smallest or largest non-null value seen so far.
+struct MinMaxAccumulator {
+    value: ScalarValue,
+    keep_smaller: bool,
+}
+
+impl Accumulator for MinMaxAccumulator {
+    fn update(&mut self, values: &ArrayRef, index: usize) -> Result<()> {
+        let value = ScalarValue::try_from_array(values, index)?;
+        if value.is_null() {
+            return Ok(());
+        }
+        if self.value.is_null()
+            || (self.keep_smaller && value < self.value)
+            || (!self.keep_smaller && value > self.value)
+        {
+            self.value = value;
+        }
+        Ok(())
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        Ok(self.value.clone())
+    }
+}
+
+pub struct AvgExpression {
+    expr: Arc<dyn PhysicalExpression>,
+}
+
+impl AggregateExpression for AvgExpression {
+    fn expr(&self) -> Arc<dyn PhysicalExpression> {
+        Arc::clone(&self.expr)
+    }
+
+    fn create_accumulator(&self) -> Box<dyn Accumulator> {
+        Box::new(AvgAccumulator { sum: 0.0, count: 0 })
+    }
+
+    fn to_string(&self) -> String {
+        format!("avg({})", self.expr.to_string())
+    }
+}
+
+impl AvgExpression {
+    pub fn try_new(
+        expr: Arc<dyn PhysicalExpression>,
+        data_type: DataType,
+    ) -> Result<AvgExpression> {
+        match data_type {
+            DataType::Int64 | DataType::Float64 => Ok(AvgExpression { expr }),
+            other => Err(QrustError::Plan(format!(
+                "Cannot average values of type {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Averages are always computed as floats, whatever the input type.
+struct AvgAccumulator {
+    sum: f64,
+    count: u64,
+}
+
+impl Accumulator for AvgAccumulator {
+    fn update(&mut self, values: &ArrayRef, index: usize) -> Result<()> {
+        match ScalarValue::try_from_array(values, index)? {
+            ScalarValue::Int64(Some(v)) => self.sum += v as f64,
+            ScalarValue::Float64(Some(v)) => self.sum += v,
+            ScalarValue::Int64(None) | ScalarValue::Float64(None) => return Ok(()),
+            v => return Err(QrustError::Execution(format!("Cannot average {:?}", v))),
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        if self.count == 0 {
+            Ok(ScalarValue::Float64(None))
+        } else {
+            Ok(ScalarValue::Float64(Some(self.sum / self.count as f64)))
+        }
+    }
+}
+
+/// The only value of its input, or null without any. Used for scalar subqueries that
+/// don't aggregate, which must not return more than one row.
+pub struct SingleExpression {
+    expr: Arc<dyn PhysicalExpression>,
+    data_type: DataType,
+}
+
+impl AggregateExpression for SingleExpression {
+    fn expr(&self) -> Arc<dyn PhysicalExpression> {
+        Arc::clone(&self.expr)
+    }
+
+    fn create_accumulator(&self) -> Box<dyn Accumulator> {
+        Box::new(SingleAccumulator {
+            value: ScalarValue::try_null(&self.data_type).unwrap(),
+            seen: false,
+        })
+    }
+
+    fn to_string(&self) -> String {
+        format!("single({})", self.expr.to_string())
+    }
+}
+
+impl SingleExpression {
+    pub fn try_new(
+        expr: Arc<dyn PhysicalExpression>,
+        data_type: DataType,
+    ) -> Result<SingleExpression> {
+        ScalarValue::try_null(&data_type)?;
+        Ok(SingleExpression { expr, data_type })
+    }
+}
+
+/// Nulls count as values, since they are rows of the subquery all the same.
+struct SingleAccumulator {
+    value: ScalarValue,
+    seen: bool,
+}
+
+impl Accumulator for SingleAccumulator {
+    fn update(&mut self, values: &ArrayRef, index: usize) -> Result<()> {
+        if self.seen {
+            return Err(QrustError::Execution(
+                "Scalar subquery returned more than one row".to_owned(),
+            ));
+        }
+        self.value = ScalarValue::try_from_array(values, index)?;
+        self.seen = true;
+        Ok(())
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        Ok(self.value.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expression::ColumnExpression;
+    use arrow::array::{Int64Array, StringArray};
+
+    fn accumulate(agg: &dyn AggregateExpression, values: ArrayRef) -> ScalarValue {
+        let mut acc = agg.create_accumulator();
+        for i in 0..values.len() {
+            acc.update(&values, i).unwrap();
+        }
+        acc.evaluate().unwrap()
+    }
+
+    fn input() -> Arc<dyn PhysicalExpression> {
+        Arc::new(ColumnExpression::new(0))
+    }
+
+    #[test]
+    fn numeric_aggregates() {
+        let values: ArrayRef = Arc::new(Int64Array::from(vec![Some(4), None, Some(1), Some(7)]));
+        let sum = SumExpression::try_new(input(), DataType::Int64).unwrap();
+        let min = MinExpression::try_new(input(), DataType::Int64).unwrap();
+        let max = MaxExpression::try_new(input(), DataType::Int64).unwrap();
+        let avg = AvgExpression::try_new(input(), DataType::Int64).unwrap();
+
+        assert_eq!(
+            accumulate(&sum, Arc::clone(&values)),
+            ScalarValue::Int64(Some(12))
+        );
+        assert_eq!(
+            accumulate(&min, Arc::clone(&values)),
+            ScalarValue::Int64(Some(1))
+        );
+        assert_eq!(
+            accumulate(&max, Arc::clone(&values)),
+            ScalarValue::Int64(Some(7))
+        );
+        assert_eq!(accumulate(&avg, values), ScalarValue::Float64(Some(4.0)));
+    }
+
+    #[test]
+    fn string_min_max() {
+        let values: ArrayRef = Arc::new(StringArray::from(vec!["b", "c", "a"]));
+        let min = MinExpression::try_new(input(), DataType::Utf8).unwrap();
+        let max = MaxExpression::try_new(input(), DataType::Utf8).unwrap();
+
+        assert_eq!(
+            accumulate(&min, Arc::clone(&values)),
+            ScalarValue::Utf8(Some("a".to_owned()))
+        );
+        assert_eq!(
+            accumulate(&max, values),
+            ScalarValue::Utf8(Some("c".to_owned()))
+        );
+        assert!(SumExpression::try_new(input(), DataType::Utf8).is_err());
+    }
+
+    #[test]
+    fn sum_overflow() {
+        let values: ArrayRef = Arc::new(Int64Array::from(vec![i64::MAX, 1]));
+        let sum = SumExpression::try_new(input(), DataType::Int64).unwrap();
+        let mut acc = sum.create_accumulator();
+        acc.update(&values, 0).unwrap();
+        assert_eq!(
+            acc.update(&values, 1).unwrap_err().to_string(),
+            "Execution error: Int64 overflow in sum"
+        );
+    }
+
+    #[test]
+    fn single_value() {
+        let values: ArrayRef = Arc::new(Int64Array::from(vec![None, Some(3)]));
+        let single = SingleExpression::try_new(input(), DataType::Int64).unwrap();
+        let mut acc = single.create_accumulator();
+        assert_eq!(acc.evaluate().unwrap(), ScalarValue::Int64(None));
+        acc.update(&values, 0).unwrap();
+        assert_eq!(acc.evaluate().unwrap(), ScalarValue::Int64(None));
+        assert_eq!(
+            acc.update(&values, 1).unwrap_err().to_string(),
+            "Execution error: Scalar subquery returned more than one row"
+        );
+    }
+
+    #[test]
+    fn empty_input() {
+        let values: ArrayRef = Arc::new(Int64Array::from(Vec::<i64>::new()));
+        let sum = SumExpression::try_new(input(), DataType::Int64).unwrap();
+        let avg = AvgExpression::try_new(input(), DataType::Int64).unwrap();
+
+        assert_eq!(
+            accumulate(&sum, Arc::clone(&values)),
+            ScalarValue::Int64(None)
+        );
+        assert_eq!(accumulate(&avg, values), ScalarValue::Float64(None));
+    }
+}
@@ -0,0 +1,225 @@
+use arrow::array::ArrayRef;
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::accumulator::{Accumulator, AggregateExpression};
+use super::expression::PhysicalExpression;
+use super::{BatchIterator, PhysicalPlan};
+use crate::core::error::Result;
+use crate::core::scalar_value::ScalarValue;
+
+/// Groups the input rows by the values of the group expressions and runs the
+/// aggregate expressions over each group.
+pub struct HashAggregateExec {
+    input: Arc<dyn PhysicalPlan>,
+    groupexpr: Vec<Arc<dyn PhysicalExpression>>,
+    aggregateexpr: Vec<Arc<dyn AggregateExpression>>,
+    schema: Arc<Schema>,
+}
+
+impl PhysicalPlan for HashAggregateExec {
+    fn schema(&self) -> Arc<Schema> {
+        Arc::clone(&self.schema)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalPlan>> {
+        vec![Arc::clone(&self.input)]
+    }
+
+    fn execute(&self) -> Result<BatchIterator> {
+        // Groups are kept in the order they are first seen so that the output is stable.
+        let mut keys: Vec<Vec<ScalarValue>> = vec![];
+        let mut accumulators: Vec<Vec<Box<dyn Accumulator>>> = vec![];
+        let mut group_index: HashMap<Vec<ScalarValue>, usize> = HashMap::new();
+
+        for batch in self.input.execute()? {
+            let batch = batch?;
+            let group_values = evaluate_all(&self.groupexpr, &batch)?;
+            let aggregate_values = self
+                .aggregateexpr
+                .iter()
+                .map(|a| a.expr().evaluate(&batch))
+                .collect::<Result<Vec<ArrayRef>>>()?;
+
+            for row in 0..batch.num_rows() {
+                let key = group_values
+                    .iter()
+                    .map(|v| ScalarValue::try_from_array(v, row))
+                    .collect::<Result<Vec<_>>>()?;
+                let index = match group_index.get(&key) {
+                    Some(index) => *index,
+                    None => {
+                        keys.push(key.clone());
+                        accumulators.push(self.create_accumulators());
+                        group_index.insert(key, keys.len() - 1);
+                        keys.len() - 1
+                    }
+                };
+                for (acc, values) in accumulators[index].iter_mut().zip(&aggregate_values) {
+                    acc.update(values, row)?;
+                }
+            }
+        }
+
+        // An aggregate without grouping always produces exactly one row, even when the
+        // input is empty.
+        if keys.is_empty() {
+            if !self.groupexpr.is_empty() {
+                return Ok(Box::new(std::iter::empty()));
+            }
+            keys.push(vec![]);
+            accumulators.push(self.create_accumulators());
+        }
+
+        let mut columns: Vec<ArrayRef> = vec![];
+        for i in 0..self.groupexpr.len() {
+            let values: Vec<ScalarValue> = keys.iter().map(|k| k[i].clone()).collect();
+            columns.push(ScalarValue::iter_to_array(
+                &values,
+                self.schema.field(i).data_type(),
+            )?);
+        }
+        for i in 0..self.aggregateexpr.len() {
+            let values = accumulators
+                .iter()
+                .map(|accs| accs[i].evaluate())
+                .collect::<Result<Vec<_>>>()?;
+            columns.push(ScalarValue::iter_to_array(
+                &values,
+                self.schema.field(self.groupexpr.len() + i).data_type(),
+            )?);
+        }
+
+        let batch = RecordBatch::try_new(Arc::clone(&self.schema), columns)?;
+        Ok(Box::new(std::iter::once(Ok(batch))))
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "HashAggregateExec: groupExpr={}, aggregateExpr={}",
+            self.groupexpr
+                .iter()
+                .map(|g| g.to_string())
+                .collect::<Vec<String>>()
+                .join(", "),
+            self.aggregateexpr
+                .iter()
+                .map(|a| a.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+}
+
+impl HashAggregateExec {
+    pub fn new(
+        input: Arc<dyn PhysicalPlan>,
+        groupexpr: Vec<Arc<dyn PhysicalExpression>>,
+        aggregateexpr: Vec<Arc<dyn AggregateExpression>>,
+        schema: Arc<Schema>,
+    ) -> HashAggregateExec {
+        HashAggregateExec {
+            input,
+            groupexpr,
+            aggregateexpr,
+            schema,
+        }
+    }
+
+    fn create_accumulators(&self) -> Vec<Box<dyn Accumulator>> {
+        self.aggregateexpr
+            .iter()
+            .map(|a| a.create_accumulator())
+            .collect()
+    }
+}
+
+fn evaluate_all(
+    expr: &[Arc<dyn PhysicalExpression>],
+    batch: &RecordBatch,
+) -> Result<Vec<ArrayRef>> {
+    expr.iter().map(|e| e.evaluate(batch)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::data_frame::DataFrame;
+    use crate::core::data_type::ArrowType;
+    use crate::core::execution_context::ExecutionContext;
+    use crate::core::helper::*;
+    use crate::test_util::{rows, write_csv};
+
+    fn sales(dir: &tempfile::TempDir) -> DataFrame {
+        let path = write_csv(
+            dir,
+            "sales.csv",
+            &[
+                "north,a,10",
+                "south,a,5",
+                "north,b,7",
+                "north,a,1",
+                "south,b,3",
+            ],
+        );
+        let schema = schema(vec![
+            ("region", ArrowType::StringType, false),
+            ("product", ArrowType::StringType, false),
+            ("amount", ArrowType::Int64Type, false),
+        ]);
+        ExecutionContext::new().csv(schema, &path)
+    }
+
+    #[test]
+    fn group_by_string() {
+        let dir = tempfile::tempdir().unwrap();
+        let df = sales(&dir).aggregate(
+            vec![col("region")],
+            vec![
+                sum(col("amount")),
+                min(col("amount")),
+                max(col("amount")),
+                avg(col("amount")),
+            ],
+        );
+
+        assert_eq!(
+            rows(&df.collect().unwrap()),
+            vec!["north,18,1,10,6", "south,8,3,5,4"]
+        );
+    }
+
+    #[test]
+    fn group_by_multiple_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let df = sales(&dir).aggregate(
+            vec![col("region"), col("product")],
+            vec![sum(col("amount"))],
+        );
+
+        assert_eq!(
+            rows(&df.collect().unwrap()),
+            vec!["north,a,11", "south,a,5", "north,b,7", "south,b,3"]
+        );
+    }
+
+    #[test]
+    fn group_by_expression() {
+        let dir = tempfile::tempdir().unwrap();
+        let df = sales(&dir).aggregate(
+            vec![modulus(col("amount"), lit(&2))],
+            vec![max(col("product"))],
+        );
+
+        assert_eq!(rows(&df.collect().unwrap()), vec!["0,a", "1,b"]);
+    }
+
+    #[test]
+    fn aggregate_without_grouping() {
+        let dir = tempfile::tempdir().unwrap();
+        let df = sales(&dir).aggregate(vec![], vec![sum(col("amount")), avg(col("amount"))]);
+
+        assert_eq!(rows(&df.collect().unwrap()), vec!["26,5.2"]);
+    }
+}
@@ -0,0 +1,34 @@
+use arrow::datatypes::Schema;
+use std::sync::Arc;
+
+use super::{BatchIterator, PhysicalPlan};
+use crate::core::error::Result;
+
+/// Produces no batches at all.
+pub struct EmptyExec {
+    schema: Arc<Schema>,
+}
+
+impl PhysicalPlan for EmptyExec {
+    fn schema(&self) -> Arc<Schema> {
+        Arc::clone(&self.schema)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalPlan>> {
+        vec![]
+    }
+
+    fn execute(&self) -> Result<BatchIterator> {
+        Ok(Box::new(std::iter::empty()))
+    }
+
+    fn to_string(&self) -> String {
+        "EmptyExec".to_owned()
+    }
+}
+
+impl EmptyExec {
+    pub fn new(schema: Arc<Schema>) -> EmptyExec {
+        EmptyExec { schema }
+    }
+}
@@ -0,0 +1,76 @@
+use arrow::array::{ArrayRef, StringArray};
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
+use std::sync::Arc;
+
+use super::{BatchIterator, PhysicalPlan};
+use crate::core::error::Result;
+use crate::logical_plan::explain::StringifiedPlan;
+
+/// Produces a single batch describing how `input` is planned. When `analyze` is set,
+/// `input` is run to completion first so that the physical plan it reports carries the
+/// metrics collected while running.
+pub struct ExplainExec {
+    schema: Arc<Schema>,
+    stringified_plans: Vec<StringifiedPlan>,
+    input: Arc<dyn PhysicalPlan>,
+    analyze: bool,
+}
+
+impl PhysicalPlan for ExplainExec {
+    fn schema(&self) -> Arc<Schema> {
+        Arc::clone(&self.schema)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalPlan>> {
+        vec![Arc::clone(&self.input)]
+    }
+
+    fn execute(&self) -> Result<BatchIterator> {
+        let schema = Arc::clone(&self.schema);
+        let mut plans = self.stringified_plans.clone();
+        let input = Arc::clone(&self.input);
+        let analyze = self.analyze;
+        Ok(Box::new(std::iter::once_with(move || {
+            if analyze {
+                for batch in input.execute()? {
+                    batch?;
+                }
+            }
+            plans.push(StringifiedPlan::new("physical_plan", input.format()));
+
+            let plan_types: StringArray = plans.iter().map(|p| Some(&p.plan_type)).collect();
+            let texts: StringArray = plans.iter().map(|p| Some(&p.plan)).collect();
+            Ok(RecordBatch::try_new(
+                schema,
+                vec![Arc::new(plan_types) as ArrayRef, Arc::new(texts)],
+            )?)
+        })))
+    }
+
+    fn to_string(&self) -> String {
+        if self.analyze {
+            "ExplainExec: analyze".to_owned()
+        } else {
+            "ExplainExec".to_owned()
+        }
+    }
+}
+
+impl ExplainExec {
+    /// Creates an operator that reports `stringified_plans` followed by the plan of
+    /// `input`.
+    pub fn new(
+        schema: Arc<Schema>,
+        stringified_plans: Vec<StringifiedPlan>,
+        input: Arc<dyn PhysicalPlan>,
+        analyze: bool,
+    ) -> ExplainExec {
+        ExplainExec {
+            schema,
+            stringified_plans,
+            input,
+            analyze,
+        }
+    }
+}
@@ -0,0 +1,493 @@
+use arrow::array::{ArrayRef, BooleanArray, Float64Array, Int64Array, StringArray};
+use arrow::compute::kernels::{arithmetic, boolean, comparison};
+use arrow::datatypes::DataType;
+use arrow::record_batch::RecordBatch;
+use std::sync::Arc;
+
+use crate::core::error::{QrustError, Result};
+use crate::core::scalar_value::ScalarValue;
+
+/// An expression that can be evaluated against a batch of rows.
+pub trait PhysicalExpression: Send + Sync {
+    fn evaluate(&self, input: &RecordBatch) -> Result<ArrayRef>;
+    fn to_string(&self) -> String;
+
+    /// The index of the input column this expression returns unchanged, if any.
+    fn column_index(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// References a column of the input batch by index.
+pub struct ColumnExpression {
+    index: usize,
+}
+
+impl PhysicalExpression for ColumnExpression {
+    fn evaluate(&self, input: &RecordBatch) -> Result<ArrayRef> {
+        Ok(Arc::clone(input.column(self.index)))
+    }
+
+    fn to_string(&self) -> String {
+        format!("#{}", self.index)
+    }
+
+    fn column_index(&self) -> Option<usize> {
+        Some(self.index)
+    }
+}
+
+impl ColumnExpression {
+    pub fn new(index: usize) -> ColumnExpression {
+        ColumnExpression { index }
+    }
+}
+
+/// Broadcasts a string literal to every row of the input batch.
+pub struct LiteralStringExpression {
+    val: String,
+}
+
+impl PhysicalExpression for LiteralStringExpression {
+    fn evaluate(&self, input: &RecordBatch) -> Result<ArrayRef> {
+        Ok(Arc::new(StringArray::from(vec![
+            self.val.as_str();
+            input.num_rows()
+        ])))
+    }
+
+    fn to_string(&self) -> String {
+        format!("'{}'", self.val)
+    }
+}
+
+impl LiteralStringExpression {
+    pub fn new(val: String) -> LiteralStringExpression {
+        LiteralStringExpression { val }
+    }
+}
+
+/// Broadcasts an integer literal to every row of the input batch.
+pub struct LiteralI64Expression {
+    val: i64,
+}
+
+impl PhysicalExpression for LiteralI64Expression {
+    fn evaluate(&self, input: &RecordBatch) -> Result<ArrayRef> {
+        Ok(Arc::new(Int64Array::from(vec![self.val; input.num_rows()])))
+    }
+
+    fn to_string(&self) -> String {
+        format!("{}", self.val)
+    }
+}
+
+impl LiteralI64Expression {
+    pub fn new(val: i64) -> LiteralI64Expression {
+        LiteralI64Expression { val }
+    }
+}
+
+/// Broadcasts a boolean literal to every row of the input batch.
+pub struct LiteralBooleanExpression {
+    val: bool,
+}
+
+impl PhysicalExpression for LiteralBooleanExpression {
+    fn evaluate(&self, input: &RecordBatch) -> Result<ArrayRef> {
+        Ok(Arc::new(BooleanArray::from(vec![
+            self.val;
+            input.num_rows()
+        ])))
+    }
+
+    fn to_string(&self) -> String {
+        format!("{}", self.val)
+    }
+}
+
+impl LiteralBooleanExpression {
+    pub fn new(val: bool) -> LiteralBooleanExpression {
+        LiteralBooleanExpression { val }
+    }
+}
+
+/// Broadcasts a value of any type, which may be null, to every row of the input batch.
+pub struct LiteralScalarExpression {
+    value: ScalarValue,
+}
+
+impl PhysicalExpression for LiteralScalarExpression {
+    fn evaluate(&self, input: &RecordBatch) -> Result<ArrayRef> {
+        let values = vec![self.value.clone(); input.num_rows()];
+        ScalarValue::iter_to_array(&values, &self.value.data_type())
+    }
+
+    fn to_string(&self) -> String {
+        match &self.value {
+            ScalarValue::Boolean(Some(b)) => format!("{}", b),
+            ScalarValue::Int64(Some(i)) => format!("{}", i),
+            ScalarValue::Float64(Some(f)) => format!("{}", f),
+            ScalarValue::Utf8(Some(s)) => format!("'{}'", s),
+            _ => "NULL".to_owned(),
+        }
+    }
+}
+
+impl LiteralScalarExpression {
+    pub fn new(value: ScalarValue) -> LiteralScalarExpression {
+        LiteralScalarExpression { value }
+    }
+}
+
+/// Negates a boolean input.
+pub struct NotExpression {
+    expr: Arc<dyn PhysicalExpression>,
+}
+
+impl PhysicalExpression for NotExpression {
+    fn evaluate(&self, input: &RecordBatch) -> Result<ArrayRef> {
+        let values = self.expr.evaluate(input)?;
+        Ok(Arc::new(boolean::not(as_boolean(&values)?)?))
+    }
+
+    fn to_string(&self) -> String {
+        format!("NOT {}", self.expr.to_string())
+    }
+}
+
+impl NotExpression {
+    pub fn new(expr: Arc<dyn PhysicalExpression>) -> NotExpression {
+        NotExpression { expr }
+    }
+}
+
+/// Compares or combines two inputs, producing a boolean array.
+pub struct BooleanExpression {
+    name: String,
+    op: String,
+    l: Arc<dyn PhysicalExpression>,
+    r: Arc<dyn PhysicalExpression>,
+}
+
+impl PhysicalExpression for BooleanExpression {
+    fn evaluate(&self, input: &RecordBatch) -> Result<ArrayRef> {
+        let l = self.l.evaluate(input)?;
+        let r = self.r.evaluate(input)?;
+        let result = match self.name.as_str() {
+            "eq" => comparison::eq_dyn(l.as_ref(), r.as_ref())?,
+            "neq" => comparison::neq_dyn(l.as_ref(), r.as_ref())?,
+            "gt" => comparison::gt_dyn(l.as_ref(), r.as_ref())?,
+            "lt" => comparison::lt_dyn(l.as_ref(), r.as_ref())?,
+            "gteq" => comparison::gt_eq_dyn(l.as_ref(), r.as_ref())?,
+            "lteq" => comparison::lt_eq_dyn(l.as_ref(), r.as_ref())?,
+            "and" => boolean::and_kleene(as_boolean(&l)?, as_boolean(&r)?)?,
+            "or" => boolean::or_kleene(as_boolean(&l)?, as_boolean(&r)?)?,
+            _ => {
+                return Err(QrustError::NotImplemented(format!(
+                    "Unsupported boolean operator {}",
+                    self.op
+                )))
+            }
+        };
+        Ok(Arc::new(result))
+    }
+
+    fn to_string(&self) -> String {
+        let op = match self.name.as_str() {
+            "and" | "or" => format!(" {} ", self.op),
+            _ => self.op.clone(),
+        };
+        format!("{}{}{}", self.l.to_string(), op, self.r.to_string())
+    }
+}
+
+impl BooleanExpression {
+    /// Creates a boolean expression from the name of a logical boolean expression,
+    /// e.g. `eq` or `and`.
+    pub fn try_new(
+        name: &str,
+        l: Arc<dyn PhysicalExpression>,
+        r: Arc<dyn PhysicalExpression>,
+    ) -> Result<BooleanExpression> {
+        let op = match name {
+            "eq" => "=",
+            "neq" => "!=",
+            "gt" => ">",
+            "lt" => "<",
+            "gteq" => ">=",
+            "lteq" => "<=",
+            "and" => "AND",
+            "or" => "OR",
+            _ => {
+                return Err(QrustError::NotImplemented(format!(
+                    "Unsupported boolean expression {}",
+                    name
+                )))
+            }
+        };
+        Ok(BooleanExpression {
+            name: name.to_owned(),
+            op: op.to_owned(),
+            l,
+            r,
+        })
+    }
+}
+
+/// Downcasts both inputs to `$ARRAY` and applies the arithmetic kernel matching `$NAME`.
+macro_rules! math_op {
+    ($L:expr, $R:expr, $ARRAY:ty, $NAME:expr, $OP:expr) => {{
+        let l = $L.as_any().downcast_ref::<$ARRAY>().unwrap();
+        let r = $R.as_any().downcast_ref::<$ARRAY>().unwrap();
+        let result = match $NAME {
+            "add" => arithmetic::add(l, r)?,
+            "subtract" => arithmetic::subtract(l, r)?,
+            "mult" => arithmetic::multiply(l, r)?,
+            "div" => arithmetic::divide(l, r)?,
+            "modulus" => arithmetic::modulus(l, r)?,
+            _ => {
+                return Err(QrustError::NotImplemented(format!(
+                    "Unsupported math operator {}",
+                    $OP
+                )))
+            }
+        };
+        Ok(Arc::new(result) as ArrayRef)
+    }};
+}
+
+/// Applies an arithmetic operator to two numeric inputs.
+pub struct MathExpression {
+    name: String,
+    op: String,
+    l: Arc<dyn PhysicalExpression>,
+    r: Arc<dyn PhysicalExpression>,
+}
+
+impl PhysicalExpression for MathExpression {
+    fn evaluate(&self, input: &RecordBatch) -> Result<ArrayRef> {
+        let l = self.l.evaluate(input)?;
+        let r = self.r.evaluate(input)?;
+        match (l.data_type(), r.data_type()) {
+            (DataType::Int64, DataType::Int64) => {
+                math_op!(l, r, Int64Array, self.name.as_str(), self.op)
+            }
+            (DataType::Float64, DataType::Float64) => {
+                math_op!(l, r, Float64Array, self.name.as_str(), self.op)
+            }
+            (lt, rt) => Err(QrustError::Execution(format!(
+                "Cannot evaluate {} on types {} and {}",
+                self.to_string(),
+                lt,
+                rt
+            ))),
+        }
+    }
+
+    fn to_string(&self) -> String {
+        format!("{} {} {}", self.l.to_string(), self.op, self.r.to_string())
+    }
+}
+
+impl MathExpression {
+    /// Creates a math expression from the name of a logical math expression,
+    /// e.g. `add` or `mult`.
+    pub fn try_new(
+        name: &str,
+        l: Arc<dyn PhysicalExpression>,
+        r: Arc<dyn PhysicalExpression>,
+    ) -> Result<MathExpression> {
+        let op = match name {
+            "add" => "+",
+            "subtract" => "-",
+            "mult" => "*",
+            "div" => "/",
+            "modulus" => "%",
+            _ => {
+                return Err(QrustError::NotImplemented(format!(
+                    "Unsupported math expression {}",
+                    name
+                )))
+            }
+        };
+        Ok(MathExpression {
+            name: name.to_owned(),
+            op: op.to_owned(),
+            l,
+            r,
+        })
+    }
+}
+
+fn as_boolean(array: &ArrayRef) -> Result<&BooleanArray> {
+    array
+        .as_any()
+        .downcast_ref::<BooleanArray>()
+        .ok_or_else(|| {
+            QrustError::Execution(format!(
+                "Expected a boolean input but found {}",
+                array.data_type()
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Array;
+    use arrow::datatypes::{Field, Schema};
+
+    fn batch() -> RecordBatch {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int64, false),
+            Field::new("b", DataType::Int64, false),
+            Field::new("c", DataType::Utf8, false),
+        ]);
+        RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 5, 9])),
+                Arc::new(Int64Array::from(vec![3, 5, 7])),
+                Arc::new(StringArray::from(vec!["x", "y", "z"])),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn col(index: usize) -> Arc<dyn PhysicalExpression> {
+        Arc::new(ColumnExpression::new(index))
+    }
+
+    fn evaluate_bool(
+        name: &str,
+        l: Arc<dyn PhysicalExpression>,
+        r: Arc<dyn PhysicalExpression>,
+    ) -> Vec<bool> {
+        let result = BooleanExpression::try_new(name, l, r)
+            .unwrap()
+            .evaluate(&batch())
+            .unwrap();
+        let result = result.as_any().downcast_ref::<BooleanArray>().unwrap();
+        (0..result.len()).map(|i| result.value(i)).collect()
+    }
+
+    fn evaluate_i64(
+        name: &str,
+        l: Arc<dyn PhysicalExpression>,
+        r: Arc<dyn PhysicalExpression>,
+    ) -> Vec<i64> {
+        let result = MathExpression::try_new(name, l, r)
+            .unwrap()
+            .evaluate(&batch())
+            .unwrap();
+        result
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap()
+            .values()
+            .to_vec()
+    }
+
+    #[test]
+    fn literals() {
+        let i = LiteralI64Expression::new(42).evaluate(&batch()).unwrap();
+        let i = i.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(i.values(), &[42, 42, 42]);
+
+        let s = LiteralStringExpression::new("abc".to_owned())
+            .evaluate(&batch())
+            .unwrap();
+        let s = s.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(s.len(), 3);
+        assert_eq!(s.value(2), "abc");
+
+        let b = LiteralBooleanExpression::new(true)
+            .evaluate(&batch())
+            .unwrap();
+        let b = b.as_any().downcast_ref::<BooleanArray>().unwrap();
+        assert_eq!(b.len(), 3);
+        assert!(b.value(1));
+
+        let null = LiteralScalarExpression::new(ScalarValue::Int64(None));
+        assert_eq!(null.to_string(), "NULL");
+        let null = null.evaluate(&batch()).unwrap();
+        assert_eq!(null.data_type(), &DataType::Int64);
+        assert_eq!(null.null_count(), 3);
+    }
+
+    #[test]
+    fn negation() {
+        let lt: Arc<dyn PhysicalExpression> =
+            Arc::new(BooleanExpression::try_new("lt", col(0), col(1)).unwrap());
+        let result = NotExpression::new(lt).evaluate(&batch()).unwrap();
+        let result = result.as_any().downcast_ref::<BooleanArray>().unwrap();
+        assert_eq!(
+            (0..result.len())
+                .map(|i| result.value(i))
+                .collect::<Vec<_>>(),
+            vec![false, true, true]
+        );
+    }
+
+    #[test]
+    fn comparisons() {
+        assert_eq!(
+            evaluate_bool("eq", col(0), col(1)),
+            vec![false, true, false]
+        );
+        assert_eq!(
+            evaluate_bool("neq", col(0), col(1)),
+            vec![true, false, true]
+        );
+        assert_eq!(
+            evaluate_bool("gt", col(0), col(1)),
+            vec![false, false, true]
+        );
+        assert_eq!(
+            evaluate_bool("lt", col(0), col(1)),
+            vec![true, false, false]
+        );
+        assert_eq!(
+            evaluate_bool("gteq", col(0), col(1)),
+            vec![false, true, true]
+        );
+        assert_eq!(
+            evaluate_bool("lteq", col(0), col(1)),
+            vec![true, true, false]
+        );
+
+        let y: Arc<dyn PhysicalExpression> = Arc::new(LiteralStringExpression::new("y".to_owned()));
+        assert_eq!(evaluate_bool("eq", col(2), y), vec![false, true, false]);
+    }
+
+    #[test]
+    fn conjunctions() {
+        let lt: Arc<dyn PhysicalExpression> =
+            Arc::new(BooleanExpression::try_new("lt", col(0), col(1)).unwrap());
+        let eq: Arc<dyn PhysicalExpression> =
+            Arc::new(BooleanExpression::try_new("eq", col(0), col(1)).unwrap());
+        assert_eq!(
+            evaluate_bool("and", Arc::clone(&lt), Arc::clone(&eq)),
+            vec![false, false, false]
+        );
+        assert_eq!(evaluate_bool("or", lt, eq), vec![true, true, false]);
+    }
+
+    #[test]
+    fn math() {
+        assert_eq!(evaluate_i64("add", col(0), col(1)), vec![4, 10, 16]);
+        assert_eq!(evaluate_i64("subtract", col(0), col(1)), vec![-2, 0, 2]);
+        assert_eq!(evaluate_i64("mult", col(0), col(1)), vec![3, 25, 63]);
+        assert_eq!(evaluate_i64("div", col(0), col(1)), vec![0, 1, 1]);
+        assert_eq!(evaluate_i64("modulus", col(0), col(1)), vec![1, 0, 2]);
+    }
+
+    #[test]
+    fn math_type_mismatch() {
+        let result = MathExpression::try_new("add", col(0), col(2))
+            .unwrap()
+            .evaluate(&batch());
+        assert!(result.is_err());
+    }
+}
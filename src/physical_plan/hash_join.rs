@@ -0,0 +1,650 @@
+use arrow::array::{new_null_array, Array, ArrayRef, BooleanArray, UInt32Array};
+use arrow::compute::{concat, take};
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+use super::expression::PhysicalExpression;
+use super::{BatchIterator, PhysicalPlan};
+use crate::core::error::{QrustError, Result};
+use crate::core::scalar_value::ScalarValue;
+use crate::logical_plan::join::JoinType;
+
+/// Joins two inputs on equal keys. Both inputs are read until one of them runs out;
+/// that smaller input is built into a hash table, and the other one is streamed through
+/// it one batch at a time.
+pub struct HashJoinExec {
+    left: Arc<dyn PhysicalPlan>,
+    right: Arc<dyn PhysicalPlan>,
+    join_type: JoinType,
+    /// Pairs of key column indices, the first into the left input and the second into
+    /// the right input.
+    on: Vec<(usize, usize)>,
+    /// Pairs of rows only match if it is true.
+    filter: Option<JoinFilter>,
+    schema: Arc<Schema>,
+}
+
+impl PhysicalPlan for HashJoinExec {
+    fn schema(&self) -> Arc<Schema> {
+        Arc::clone(&self.schema)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalPlan>> {
+        vec![Arc::clone(&self.left), Arc::clone(&self.right)]
+    }
+
+    fn execute(&self) -> Result<BatchIterator> {
+        // Read from whichever input has produced fewer rows so far. The first one to run
+        // out is the smaller one.
+        let mut inputs = [self.left.execute()?, self.right.execute()?];
+        let mut buffered: [Vec<RecordBatch>; 2] = [vec![], vec![]];
+        let mut rows = [0, 0];
+        let build = loop {
+            let side = if rows[1] <= rows[0] { 1 } else { 0 };
+            match inputs[side].next() {
+                Some(batch) => {
+                    let batch = batch?;
+                    rows[side] += batch.num_rows();
+                    buffered[side].push(batch);
+                }
+                None => break side,
+            }
+        };
+        let build_left = build == 0;
+        let [left_input, right_input] = inputs;
+        let [left_buffered, right_buffered] = buffered;
+        let (build_input, build_batches, probe, probe_batches, probe_input) = if build_left {
+            (
+                &self.left,
+                left_buffered,
+                right_input,
+                right_buffered,
+                &self.right,
+            )
+        } else {
+            (
+                &self.right,
+                right_buffered,
+                left_input,
+                left_buffered,
+                &self.left,
+            )
+        };
+
+        let build_keys: Vec<usize> = self
+            .on
+            .iter()
+            .map(|(l, r)| if build_left { *l } else { *r })
+            .collect();
+        let probe_keys = self
+            .on
+            .iter()
+            .map(|(l, r)| if build_left { *r } else { *l })
+            .collect();
+        let build = BuildSide::try_new(build_input.schema(), build_batches, &build_keys)?;
+        Ok(Box::new(JoinStream {
+            probe: Box::new(probe_batches.into_iter().map(Ok).chain(probe)),
+            probe_schema: probe_input.schema(),
+            probe_keys,
+            build,
+            build_left,
+            join_type: self.join_type,
+            filter: self.filter.clone(),
+            schema: Arc::clone(&self.schema),
+            done: false,
+        }))
+    }
+
+    fn to_string(&self) -> String {
+        let mut s = format!(
+            "HashJoinExec: joinType={}, on=[{}]",
+            self.join_type,
+            self.on
+                .iter()
+                .map(|(l, r)| format!("#{}=#{}", l, r))
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+        if let Some(filter) = &self.filter {
+            s.push_str(&format!(", filter={}", filter));
+        }
+        s
+    }
+}
+
+impl HashJoinExec {
+    pub fn new(
+        left: Arc<dyn PhysicalPlan>,
+        right: Arc<dyn PhysicalPlan>,
+        join_type: JoinType,
+        on: Vec<(usize, usize)>,
+        filter: Option<JoinFilter>,
+        schema: Arc<Schema>,
+    ) -> HashJoinExec {
+        HashJoinExec {
+            left,
+            right,
+            join_type,
+            on,
+            filter,
+            schema,
+        }
+    }
+}
+
+/// The input of a join that was read up front, together with an index from key values
+/// to its rows.
+struct BuildSide {
+    batch: RecordBatch,
+    rows: HashMap<Vec<ScalarValue>, Vec<u32>>,
+    /// Whether each row has matched a row of the other input.
+    visited: Vec<bool>,
+}
+
+impl BuildSide {
+    fn try_new(
+        schema: Arc<Schema>,
+        batches: Vec<RecordBatch>,
+        keys: &[usize],
+    ) -> Result<BuildSide> {
+        let batch = concat_batches(schema, &batches)?;
+
+        let mut rows: HashMap<Vec<ScalarValue>, Vec<u32>> = HashMap::new();
+        for row in 0..batch.num_rows() {
+            if let Some(key) = row_key(&batch, keys, row)? {
+                rows.entry(key).or_default().push(row as u32);
+            }
+        }
+        let visited = vec![false; batch.num_rows()];
+        Ok(BuildSide {
+            batch,
+            rows,
+            visited,
+        })
+    }
+}
+
+/// Streams the probe input through the build side, then emits the rows of the build
+/// side that the join type keeps without a match.
+struct JoinStream {
+    probe: BatchIterator,
+    probe_schema: Arc<Schema>,
+    probe_keys: Vec<usize>,
+    build: BuildSide,
+    build_left: bool,
+    join_type: JoinType,
+    filter: Option<JoinFilter>,
+    schema: Arc<Schema>,
+    done: bool,
+}
+
+impl Iterator for JoinStream {
+    type Item = Result<RecordBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let output = match self.probe.next() {
+                Some(batch) => batch.and_then(|batch| self.probe_batch(&batch)),
+                None => {
+                    self.done = true;
+                    self.unmatched_build_rows()
+                }
+            };
+            match output {
+                Ok(None) => {}
+                Ok(Some(batch)) => return Some(Ok(batch)),
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl JoinStream {
+    /// Joins one batch of the probe input with the build side.
+    fn probe_batch(&mut self, batch: &RecordBatch) -> Result<Option<RecordBatch>> {
+        let mut probe_rows: Vec<u32> = vec![];
+        let mut build_rows: Vec<u32> = vec![];
+        for row in 0..batch.num_rows() {
+            if let Some(key) = row_key(batch, &self.probe_keys, row)? {
+                for &m in self.build.rows.get(&key).into_iter().flatten() {
+                    probe_rows.push(row as u32);
+                    build_rows.push(m);
+                }
+            }
+        }
+        if let Some(filter) = &self.filter {
+            filter.filter_pairs(
+                &mut probe_rows,
+                &mut build_rows,
+                |probe_rows, build_rows| {
+                    self.combine(batch, probe_rows, build_rows, filter.schema())
+                },
+            )?;
+        }
+        for &b in &build_rows {
+            self.build.visited[b as usize] = true;
+        }
+
+        // The build side is the left input, whose rows are only known to have matched
+        // once the whole probe input has been read.
+        if matches!(self.join_type, JoinType::Semi | JoinType::Anti) && self.build_left {
+            return Ok(None);
+        }
+        let preserved = match self.join_type {
+            JoinType::Left => !self.build_left,
+            JoinType::Right => self.build_left,
+            JoinType::Full => true,
+            _ => false,
+        };
+        let (probe_out, build_out) = join_rows(
+            self.join_type,
+            0..batch.num_rows() as u32,
+            &probe_rows,
+            &build_rows,
+            preserved,
+        );
+        Ok(Some(self.combine(
+            batch,
+            &probe_out,
+            &build_out,
+            &self.schema,
+        )?))
+    }
+
+    /// The rows of the build side that are part of the output without a match, or of a
+    /// semi join with one.
+    fn unmatched_build_rows(&self) -> Result<Option<RecordBatch>> {
+        let keep_visited = match self.join_type {
+            JoinType::Left | JoinType::Anti if self.build_left => false,
+            JoinType::Right if !self.build_left => false,
+            JoinType::Full => false,
+            JoinType::Semi if self.build_left => true,
+            _ => return Ok(None),
+        };
+        let build_rows: Vec<Option<u32>> = (0..self.build.visited.len())
+            .filter(|&r| self.build.visited[r] == keep_visited)
+            .map(|r| Some(r as u32))
+            .collect();
+        let probe_rows = vec![None; build_rows.len()];
+        let empty = RecordBatch::new_empty(Arc::clone(&self.probe_schema));
+        Ok(Some(self.combine(
+            &empty,
+            &probe_rows,
+            &build_rows,
+            &self.schema,
+        )?))
+    }
+
+    /// Takes the given rows of the probe batch and the build side, where `None` stands
+    /// for a row of nulls, and puts their columns in the order of `schema`.
+    fn combine(
+        &self,
+        probe: &RecordBatch,
+        probe_rows: &[Option<u32>],
+        build_rows: &[Option<u32>],
+        schema: &Arc<Schema>,
+    ) -> Result<RecordBatch> {
+        let probe_columns = take_rows(probe, probe_rows)?;
+        let build_columns = take_rows(&self.build.batch, build_rows)?;
+        if self.build_left {
+            joined_batch(schema, build_columns, probe_columns)
+        } else {
+            joined_batch(schema, probe_columns, build_columns)
+        }
+    }
+}
+
+/// Concatenates the batches of an input that was read up front into one batch.
+pub(super) fn concat_batches(schema: Arc<Schema>, batches: &[RecordBatch]) -> Result<RecordBatch> {
+    if batches.is_empty() {
+        return Ok(RecordBatch::new_empty(schema));
+    }
+    let columns = (0..schema.fields().len())
+        .map(|i| {
+            let arrays: Vec<&dyn Array> = batches.iter().map(|b| b.column(i).as_ref()).collect();
+            concat(&arrays)
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+/// The condition of a join other than its equality keys, evaluated on pairs of rows
+/// with the columns of the left input followed by those of the right input.
+#[derive(Clone)]
+pub struct JoinFilter {
+    expr: Arc<dyn PhysicalExpression>,
+    schema: Arc<Schema>,
+}
+
+impl JoinFilter {
+    /// `schema` is the schema of the joined rows that `expr` is evaluated on.
+    pub fn new(expr: Arc<dyn PhysicalExpression>, schema: Arc<Schema>) -> JoinFilter {
+        JoinFilter { expr, schema }
+    }
+
+    pub(super) fn schema(&self) -> &Arc<Schema> {
+        &self.schema
+    }
+
+    /// Keeps the pairs of rows `(rows[i], other_rows[i])` that the filter accepts.
+    /// `candidates` takes the given pairs of rows as a batch of the joined schema.
+    pub(super) fn filter_pairs(
+        &self,
+        rows: &mut Vec<u32>,
+        other_rows: &mut Vec<u32>,
+        candidates: impl FnOnce(&[Option<u32>], &[Option<u32>]) -> Result<RecordBatch>,
+    ) -> Result<()> {
+        let candidates = candidates(
+            &rows.iter().map(|&r| Some(r)).collect::<Vec<_>>(),
+            &other_rows.iter().map(|&r| Some(r)).collect::<Vec<_>>(),
+        )?;
+        let mask = self.expr.evaluate(&candidates)?;
+        let mask = mask
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .ok_or_else(|| {
+                QrustError::Execution(format!(
+                    "Join filter {} did not evaluate to a boolean",
+                    self.expr.to_string()
+                ))
+            })?;
+        let keep = |i: usize| mask.is_valid(i) && mask.value(i);
+        *rows = (0..rows.len())
+            .filter(|&i| keep(i))
+            .map(|i| rows[i])
+            .collect();
+        *other_rows = (0..other_rows.len())
+            .filter(|&i| keep(i))
+            .map(|i| other_rows[i])
+            .collect();
+        Ok(())
+    }
+}
+
+impl fmt::Display for JoinFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.expr.to_string())
+    }
+}
+
+/// Lays out the output of a join for the streamed rows `rows`, given the pairs
+/// `(matches[i], other_matches[i])` of those rows with rows of the other input, in the
+/// order of the streamed rows. Returns the streamed rows and the other rows of the
+/// output, where `None` stands for a row of nulls.
+///
+/// Semi and anti joins output the streamed rows with and without a match. Other joins
+/// output the pairs, and the streamed rows without a match in their place between them
+/// if they are `preserved`.
+pub(super) fn join_rows(
+    join_type: JoinType,
+    rows: Range<u32>,
+    matches: &[u32],
+    other_matches: &[u32],
+    preserved: bool,
+) -> (Vec<Option<u32>>, Vec<Option<u32>>) {
+    let start = rows.start;
+    let mut matched = vec![false; rows.len()];
+    for &row in matches {
+        matched[(row - start) as usize] = true;
+    }
+    let is_matched = |row: u32| matched[(row - start) as usize];
+    if let JoinType::Semi | JoinType::Anti = join_type {
+        let keep = join_type == JoinType::Semi;
+        let rows = rows.filter(|&r| is_matched(r) == keep);
+        return (rows.map(Some).collect(), vec![]);
+    }
+
+    let mut out = vec![];
+    let mut other_out = vec![];
+    let mut pairs = matches.iter().zip(other_matches).peekable();
+    for row in rows {
+        if preserved && !is_matched(row) {
+            out.push(Some(row));
+            other_out.push(None);
+        }
+        while let Some((_, &other)) = pairs.next_if(|(&r, _)| r == row) {
+            out.push(Some(row));
+            other_out.push(Some(other));
+        }
+    }
+    (out, other_out)
+}
+
+/// Puts the columns of the left and right rows of a join together into a batch of
+/// `schema`. Semi and anti joins only output the columns of the left input, so the
+/// right columns are left out of their batches.
+pub(super) fn joined_batch(
+    schema: &Arc<Schema>,
+    left: Vec<ArrayRef>,
+    right: Vec<ArrayRef>,
+) -> Result<RecordBatch> {
+    let columns = left
+        .into_iter()
+        .chain(right)
+        .take(schema.fields().len())
+        .collect();
+    Ok(RecordBatch::try_new(Arc::clone(schema), columns)?)
+}
+
+/// Returns the key of a row, or `None` if any part of it is null.
+pub(super) fn row_key(
+    batch: &RecordBatch,
+    keys: &[usize],
+    row: usize,
+) -> Result<Option<Vec<ScalarValue>>> {
+    let mut key = Vec::with_capacity(keys.len());
+    for &column in keys {
+        let value = ScalarValue::try_from_array(batch.column(column), row)?;
+        if value.is_null() {
+            return Ok(None);
+        }
+        key.push(value);
+    }
+    Ok(Some(key))
+}
+
+/// Takes the given rows of every column of `batch`. `rows` may be empty for a side
+/// whose columns aren't needed.
+pub(super) fn take_rows(batch: &RecordBatch, rows: &[Option<u32>]) -> Result<Vec<ArrayRef>> {
+    if rows.iter().all(Option::is_none) {
+        let len = rows.len();
+        return Ok(batch
+            .columns()
+            .iter()
+            .map(|c| new_null_array(c.data_type(), len))
+            .collect());
+    }
+    let indices = UInt32Array::from(rows.to_vec());
+    Ok(batch
+        .columns()
+        .iter()
+        .map(|c| take(c.as_ref(), &indices, None))
+        .collect::<std::result::Result<_, _>>()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data_source::DataSource;
+    use crate::core::data_type::ArrowType;
+    use crate::core::execution_context::ExecutionContext;
+    use crate::core::helper::*;
+    use crate::logical_plan::expression::LogicalExpression;
+    use crate::logical_plan::join::Join;
+    use crate::logical_plan::scan::Scan;
+    use crate::logical_plan::subquery_alias::SubqueryAlias;
+    use crate::logical_plan::LogicalPlan;
+    use crate::physical_plan::collect;
+    use crate::physical_plan::planner::PhysicalPlanner;
+    use crate::test_util::{rows, sorted_rows, write_csv, CountingSource};
+
+    const LEFT: &[&str] = &["1,a", "2,b", "3,c", "2,d"];
+    const RIGHT: &[&str] = &["2,20", "3,30", "2,21", "4,40"];
+
+    fn join(
+        dir: &tempfile::TempDir,
+        right_rows: &[&str],
+        join_type: JoinType,
+        filter: Option<Arc<dyn LogicalExpression>>,
+    ) -> Vec<RecordBatch> {
+        let ctx = ExecutionContext::new();
+        let left = ctx
+            .csv(
+                schema(vec![
+                    ("id", ArrowType::Int64Type, false),
+                    ("name", ArrowType::StringType, false),
+                ]),
+                &write_csv(dir, "left.csv", LEFT),
+            )
+            .plan();
+        let right = ctx
+            .csv(
+                schema(vec![
+                    ("rid", ArrowType::Int64Type, false),
+                    ("value", ArrowType::Int64Type, false),
+                ]),
+                &write_csv(dir, "right.csv", right_rows),
+            )
+            .plan();
+        let join = Arc::new(
+            Join::try_new(
+                left,
+                right,
+                join_type,
+                vec![("id".to_owned(), "rid".to_owned())],
+                filter,
+            )
+            .unwrap(),
+        ) as Arc<dyn LogicalPlan>;
+        collect(PhysicalPlanner::new().create_physical_plan(&join).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn join_types() {
+        let dir = tempfile::tempdir().unwrap();
+        let inner = ["2,b,2,20", "2,b,2,21", "3,c,3,30", "2,d,2,20", "2,d,2,21"];
+
+        // Both inputs have as many rows, so the right one is built and the output
+        // follows the order of the left one.
+        assert_eq!(rows(&join(&dir, RIGHT, JoinType::Inner, None)), inner);
+        let mut left = vec!["1,a,,"];
+        left.extend(inner);
+        assert_eq!(rows(&join(&dir, RIGHT, JoinType::Left, None)), left);
+        let mut right = inner.to_vec();
+        right.push(",,4,40");
+        assert_eq!(rows(&join(&dir, RIGHT, JoinType::Right, None)), right);
+        let mut full = left.clone();
+        full.push(",,4,40");
+        assert_eq!(rows(&join(&dir, RIGHT, JoinType::Full, None)), full);
+        assert_eq!(
+            rows(&join(&dir, RIGHT, JoinType::Semi, None)),
+            ["2,b", "3,c", "2,d"]
+        );
+        assert_eq!(rows(&join(&dir, RIGHT, JoinType::Anti, None)), ["1,a"]);
+    }
+
+    #[test]
+    fn builds_the_left_input_when_it_is_smaller() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut right_rows = RIGHT.to_vec();
+        right_rows.extend(["5,50", "6,60"]);
+        let right_rows = &right_rows;
+
+        let inner = vec!["2,b,2,20", "2,b,2,21", "2,d,2,20", "2,d,2,21", "3,c,3,30"];
+        assert_eq!(
+            sorted_rows(&join(&dir, right_rows, JoinType::Inner, None)),
+            inner
+        );
+        let mut left = inner.clone();
+        left.insert(0, "1,a,,");
+        assert_eq!(
+            sorted_rows(&join(&dir, right_rows, JoinType::Left, None)),
+            left
+        );
+        let mut right = vec![",,4,40", ",,5,50", ",,6,60"];
+        right.extend(&inner);
+        assert_eq!(
+            sorted_rows(&join(&dir, right_rows, JoinType::Right, None)),
+            right
+        );
+        let mut full = right.clone();
+        full.insert(3, "1,a,,");
+        assert_eq!(
+            sorted_rows(&join(&dir, right_rows, JoinType::Full, None)),
+            full
+        );
+        assert_eq!(
+            sorted_rows(&join(&dir, right_rows, JoinType::Semi, None)),
+            ["2,b", "2,d", "3,c"]
+        );
+        assert_eq!(rows(&join(&dir, right_rows, JoinType::Anti, None)), ["1,a"]);
+    }
+
+    #[test]
+    fn filters_pairs_of_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let filter = || Some(gt(col("value"), lit(&25)) as Arc<dyn LogicalExpression>);
+        assert_eq!(
+            rows(&join(&dir, RIGHT, JoinType::Inner, filter())),
+            ["3,c,3,30"]
+        );
+        // Rows whose matches all fail the filter have no match.
+        assert_eq!(
+            rows(&join(&dir, RIGHT, JoinType::Left, filter())),
+            ["1,a,,", "2,b,,", "3,c,3,30", "2,d,,"]
+        );
+        assert_eq!(
+            rows(&join(&dir, RIGHT, JoinType::Full, filter())),
+            ["1,a,,", "2,b,,", "3,c,3,30", "2,d,,", ",,2,20", ",,2,21", ",,4,40"]
+        );
+        assert_eq!(rows(&join(&dir, RIGHT, JoinType::Semi, filter())), ["3,c"]);
+        assert_eq!(
+            rows(&join(&dir, RIGHT, JoinType::Anti, filter())),
+            ["1,a", "2,b", "2,d"]
+        );
+    }
+
+    #[test]
+    fn streams_the_larger_input() {
+        let big = Arc::new(CountingSource::new(100));
+        let small = Arc::new(CountingSource::new(3));
+        // The sides are aliased, as both have an id column.
+        let scan = |name: &str, source: &Arc<CountingSource>| {
+            let scan = Scan::new(
+                name.to_owned(),
+                source.schema(),
+                Arc::clone(source) as Arc<dyn DataSource>,
+                vec![],
+            );
+            Arc::new(SubqueryAlias::new(Arc::new(scan), name.to_owned())) as Arc<dyn LogicalPlan>
+        };
+        let join = Arc::new(
+            Join::try_new(
+                scan("big", &big),
+                scan("small", &small),
+                JoinType::Left,
+                vec![("id".to_owned(), "id".to_owned())],
+                None,
+            )
+            .unwrap(),
+        ) as Arc<dyn LogicalPlan>;
+        let plan = PhysicalPlanner::new().create_physical_plan(&join).unwrap();
+
+        let mut batches = plan.execute().unwrap();
+        batches.next().unwrap().unwrap();
+        assert_eq!(small.pulled(), 3);
+        assert!(big.pulled() <= 5, "pulled {} batches", big.pulled());
+
+        let rest = batches.collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(rest.iter().map(|b| b.num_rows()).sum::<usize>(), 99);
+        assert_eq!(big.pulled(), 100);
+    }
+}
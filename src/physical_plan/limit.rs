@@ -0,0 +1,138 @@
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
+use std::sync::Arc;
+
+use super::{BatchIterator, PhysicalPlan};
+use crate::core::error::Result;
+
+/// Skips the first `skip` rows of its input and passes on at most `fetch` of the rest.
+/// The input stops being pulled as soon as enough rows have been produced.
+pub struct LimitExec {
+    input: Arc<dyn PhysicalPlan>,
+    skip: usize,
+    fetch: Option<usize>,
+}
+
+impl PhysicalPlan for LimitExec {
+    fn schema(&self) -> Arc<Schema> {
+        self.input.schema()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalPlan>> {
+        vec![Arc::clone(&self.input)]
+    }
+
+    fn execute(&self) -> Result<BatchIterator> {
+        if self.fetch == Some(0) {
+            return Ok(Box::new(std::iter::empty()));
+        }
+        Ok(Box::new(LimitStream {
+            input: self.input.execute()?,
+            skip: self.skip,
+            fetch: self.fetch,
+        }))
+    }
+
+    fn to_string(&self) -> String {
+        match self.fetch {
+            Some(fetch) => format!("LimitExec: skip={}, fetch={}", self.skip, fetch),
+            None => format!("LimitExec: skip={}, fetch=None", self.skip),
+        }
+    }
+
+    fn output_ordering(&self) -> Vec<String> {
+        self.input.output_ordering()
+    }
+}
+
+impl LimitExec {
+    pub fn new(input: Arc<dyn PhysicalPlan>, skip: usize, fetch: Option<usize>) -> LimitExec {
+        LimitExec { input, skip, fetch }
+    }
+}
+
+/// The rows still to be skipped and fetched are counted down as batches go by.
+struct LimitStream {
+    input: BatchIterator,
+    skip: usize,
+    fetch: Option<usize>,
+}
+
+impl Iterator for LimitStream {
+    type Item = Result<RecordBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.fetch == Some(0) {
+                return None;
+            }
+            let batch = match self.input.next()? {
+                Ok(batch) => batch,
+                Err(e) => return Some(Err(e)),
+            };
+            let offset = self.skip.min(batch.num_rows());
+            self.skip -= offset;
+            let mut len = batch.num_rows() - offset;
+            if let Some(fetch) = self.fetch.as_mut() {
+                len = len.min(*fetch);
+                *fetch -= len;
+            }
+            if len > 0 {
+                return Some(Ok(batch.slice(offset, len)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data_source::DataSource;
+    use crate::physical_plan::collect;
+    use crate::physical_plan::scan::ScanExec;
+    use crate::test_util::CountingSource;
+    use arrow::array::Int64Array;
+
+    fn scan(source: &Arc<CountingSource>) -> Arc<dyn PhysicalPlan> {
+        Arc::new(ScanExec::new(
+            "counting".to_owned(),
+            source.schema(),
+            Arc::clone(source) as Arc<dyn DataSource>,
+            vec![],
+            vec![],
+        ))
+    }
+
+    fn ids(batches: &[RecordBatch]) -> Vec<i64> {
+        batches
+            .iter()
+            .flat_map(|batch| {
+                let ids = batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<Int64Array>()
+                    .unwrap();
+                ids.values().to_vec()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stops_pulling_once_enough_rows_are_produced() {
+        let source = Arc::new(CountingSource::new(100));
+        let limit = Arc::new(LimitExec::new(scan(&source), 5, Some(3)));
+        let batches = collect(limit).unwrap();
+        assert_eq!(ids(&batches), vec![5, 6, 7]);
+        assert_eq!(source.pulled(), 8);
+
+        let source = Arc::new(CountingSource::new(100));
+        let limit = Arc::new(LimitExec::new(scan(&source), 0, Some(0)));
+        assert!(collect(limit).unwrap().is_empty());
+        assert_eq!(source.pulled(), 0);
+
+        let source = Arc::new(CountingSource::new(10));
+        let limit = Arc::new(LimitExec::new(scan(&source), 8, None));
+        assert_eq!(ids(&collect(limit).unwrap()), vec![8, 9]);
+        assert_eq!(source.pulled(), 10);
+    }
+}
@@ -0,0 +1,141 @@
+use arrow::datatypes::Schema;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::{BatchIterator, PhysicalPlan};
+use crate::core::error::Result;
+
+/// Counters describing what an operator did while it ran.
+#[derive(Default)]
+pub struct ExecutionMetrics {
+    output_rows: AtomicUsize,
+    output_batches: AtomicUsize,
+    elapsed_nanos: AtomicU64,
+}
+
+impl ExecutionMetrics {
+    pub fn new() -> ExecutionMetrics {
+        ExecutionMetrics::default()
+    }
+
+    pub fn output_rows(&self) -> usize {
+        self.output_rows.load(Ordering::Relaxed)
+    }
+
+    pub fn output_batches(&self) -> usize {
+        self.output_batches.load(Ordering::Relaxed)
+    }
+
+    /// The time spent producing batches, including the time spent in the operator's
+    /// inputs.
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_nanos(self.elapsed_nanos.load(Ordering::Relaxed))
+    }
+
+    fn record(&self, rows: usize, elapsed: Duration) {
+        self.output_rows.fetch_add(rows, Ordering::Relaxed);
+        self.output_batches.fetch_add(1, Ordering::Relaxed);
+        self.add_elapsed(elapsed);
+    }
+
+    fn add_elapsed(&self, elapsed: Duration) {
+        self.elapsed_nanos
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+/// Runs its input unchanged while recording `ExecutionMetrics` for it. It formats as
+/// the input annotated with the metrics, so a plan where every operator is wrapped
+/// reads like the original plan.
+pub struct MetricsExec {
+    input: Arc<dyn PhysicalPlan>,
+    metrics: Arc<ExecutionMetrics>,
+}
+
+impl PhysicalPlan for MetricsExec {
+    fn schema(&self) -> Arc<Schema> {
+        self.input.schema()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalPlan>> {
+        self.input.children()
+    }
+
+    fn execute(&self) -> Result<BatchIterator> {
+        let start = Instant::now();
+        let mut input = self.input.execute()?;
+        self.metrics.add_elapsed(start.elapsed());
+
+        let metrics = Arc::clone(&self.metrics);
+        Ok(Box::new(std::iter::from_fn(move || {
+            let start = Instant::now();
+            let next = input.next();
+            match &next {
+                Some(Ok(batch)) => metrics.record(batch.num_rows(), start.elapsed()),
+                _ => metrics.add_elapsed(start.elapsed()),
+            }
+            next
+        })))
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{}, metrics=[output_rows={}, output_batches={}, elapsed={:?}]",
+            PhysicalPlan::to_string(self.input.as_ref()),
+            self.metrics.output_rows(),
+            self.metrics.output_batches(),
+            self.metrics.elapsed()
+        )
+    }
+
+    fn output_ordering(&self) -> Vec<String> {
+        self.input.output_ordering()
+    }
+}
+
+impl MetricsExec {
+    pub fn new(input: Arc<dyn PhysicalPlan>) -> MetricsExec {
+        MetricsExec {
+            input,
+            metrics: Arc::new(ExecutionMetrics::new()),
+        }
+    }
+
+    pub fn metrics(&self) -> Arc<ExecutionMetrics> {
+        Arc::clone(&self.metrics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data_source::DataSource;
+    use crate::physical_plan::collect;
+    use crate::physical_plan::scan::ScanExec;
+    use crate::test_util::CountingSource;
+
+    #[test]
+    fn records_rows_and_batches() {
+        let source = Arc::new(CountingSource::new(3));
+        let scan = Arc::new(ScanExec::new(
+            "counting".to_owned(),
+            source.schema(),
+            source as Arc<dyn DataSource>,
+            vec![],
+            vec![],
+        ));
+        let exec = Arc::new(MetricsExec::new(scan));
+        assert!(exec
+            .to_string()
+            .starts_with("ScanExec: counting, projection=None, metrics=[output_rows=0"));
+
+        collect(Arc::clone(&exec) as Arc<dyn PhysicalPlan>).unwrap();
+        let metrics = exec.metrics();
+        assert_eq!(metrics.output_rows(), 3);
+        assert_eq!(metrics.output_batches(), 3);
+        assert!(exec
+            .to_string()
+            .contains("metrics=[output_rows=3, output_batches=3, elapsed="));
+    }
+}
@@ -0,0 +1,68 @@
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
+use std::{fmt, sync::Arc};
+
+use crate::core::error::Result;
+
+pub mod accumulator;
+pub mod aggregate;
+pub mod empty;
+pub mod explain;
+pub mod expression;
+pub mod hash_join;
+pub mod limit;
+pub mod metrics;
+pub mod nested_loop_join;
+pub mod planner;
+pub mod projection;
+pub mod scan;
+pub mod selection;
+pub mod sort;
+pub mod sort_merge_join;
+pub mod top_k;
+
+/// A lazily evaluated sequence of record batches. Batches are only produced as the
+/// iterator is advanced, which keeps memory use bounded for streaming operators.
+pub type BatchIterator = Box<dyn Iterator<Item = Result<RecordBatch>> + Send>;
+
+/// An executable counterpart of a `LogicalPlan`.
+pub trait PhysicalPlan: Send + Sync {
+    fn schema(&self) -> Arc<Schema>;
+    fn children(&self) -> Vec<Arc<dyn PhysicalPlan>>;
+    fn execute(&self) -> Result<BatchIterator>;
+    fn to_string(&self) -> String;
+
+    /// The output columns the rows are sorted on in ascending order, most significant
+    /// first. Rows with a null in one of them can appear anywhere.
+    fn output_ordering(&self) -> Vec<String> {
+        vec![]
+    }
+
+    fn format_helper(&self, indent: usize) -> String {
+        let mut builder = String::from("");
+        for _ in 0..indent {
+            builder.push_str("  ");
+        }
+        builder.push_str(&self.to_string());
+        builder.push('\n');
+        for child in self.children() {
+            builder.push_str(&child.format_helper(indent + 1));
+        }
+        builder
+    }
+
+    fn format(&self) -> String {
+        self.format_helper(0)
+    }
+}
+
+/// Runs a physical plan to completion and gathers every batch it produces.
+pub fn collect(plan: Arc<dyn PhysicalPlan>) -> Result<Vec<RecordBatch>> {
+    plan.execute()?.collect()
+}
+
+impl fmt::Display for dyn PhysicalPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format())
+    }
+}
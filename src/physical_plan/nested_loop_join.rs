@@ -0,0 +1,346 @@
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
+use std::sync::Arc;
+
+use super::hash_join::{concat_batches, join_rows, joined_batch, take_rows, JoinFilter};
+use super::{BatchIterator, PhysicalPlan};
+use crate::core::error::Result;
+use crate::logical_plan::join::JoinType;
+
+/// The most pairs of rows that are evaluated at once. Rows of the left input are joined
+/// in chunks small enough to stay under it.
+const MAX_PAIRS: usize = 8192;
+
+/// Joins two inputs by pairing every row of the left input with every row of the right
+/// input and keeping the pairs the filter accepts. The right input is read up front and
+/// the left one is streamed. Used for joins without equality keys, which can't be hashed
+/// or merged.
+pub struct NestedLoopJoinExec {
+    left: Arc<dyn PhysicalPlan>,
+    right: Arc<dyn PhysicalPlan>,
+    join_type: JoinType,
+    /// Without it, every pair of rows matches.
+    filter: Option<JoinFilter>,
+    schema: Arc<Schema>,
+}
+
+impl PhysicalPlan for NestedLoopJoinExec {
+    fn schema(&self) -> Arc<Schema> {
+        Arc::clone(&self.schema)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalPlan>> {
+        vec![Arc::clone(&self.left), Arc::clone(&self.right)]
+    }
+
+    fn execute(&self) -> Result<BatchIterator> {
+        let right_batches = self.right.execute()?.collect::<Result<Vec<_>>>()?;
+        let right = concat_batches(self.right.schema(), &right_batches)?;
+        Ok(Box::new(NestedLoopStream {
+            left: self.left.execute()?,
+            left_schema: self.left.schema(),
+            current: None,
+            visited: vec![false; right.num_rows()],
+            right,
+            join_type: self.join_type,
+            filter: self.filter.clone(),
+            schema: Arc::clone(&self.schema),
+            done: false,
+        }))
+    }
+
+    fn to_string(&self) -> String {
+        let mut s = format!("NestedLoopJoinExec: joinType={}", self.join_type);
+        if let Some(filter) = &self.filter {
+            s.push_str(&format!(", filter={}", filter));
+        }
+        s
+    }
+
+    fn output_ordering(&self) -> Vec<String> {
+        // The pairs of each left row are emitted together, in the order of the left
+        // input, but unmatched right rows come last.
+        match self.join_type {
+            JoinType::Inner | JoinType::Left | JoinType::Semi | JoinType::Anti => {}
+            JoinType::Right | JoinType::Full => return vec![],
+        }
+        let left_schema = self.left.schema();
+        self.left
+            .output_ordering()
+            .iter()
+            .map_while(|name| left_schema.index_of(name).ok())
+            .map(|index| self.schema.field(index).name().clone())
+            .collect()
+    }
+}
+
+impl NestedLoopJoinExec {
+    pub fn new(
+        left: Arc<dyn PhysicalPlan>,
+        right: Arc<dyn PhysicalPlan>,
+        join_type: JoinType,
+        filter: Option<JoinFilter>,
+        schema: Arc<Schema>,
+    ) -> NestedLoopJoinExec {
+        NestedLoopJoinExec {
+            left,
+            right,
+            join_type,
+            filter,
+            schema,
+        }
+    }
+}
+
+/// Streams the left input through the buffered right input a chunk of rows at a time,
+/// then emits the right rows that the join type keeps without a match.
+struct NestedLoopStream {
+    left: BatchIterator,
+    left_schema: Arc<Schema>,
+    /// The left batch being joined and the first of its rows that hasn't been.
+    current: Option<(RecordBatch, usize)>,
+    right: RecordBatch,
+    /// Whether each right row has matched a left row.
+    visited: Vec<bool>,
+    join_type: JoinType,
+    filter: Option<JoinFilter>,
+    schema: Arc<Schema>,
+    done: bool,
+}
+
+impl Iterator for NestedLoopStream {
+    type Item = Result<RecordBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let output = match self.current.take() {
+                Some((batch, start)) => {
+                    let chunk = (MAX_PAIRS / self.right.num_rows().max(1)).max(1);
+                    let end = batch.num_rows().min(start + chunk);
+                    let output = self.join_rows(&batch, start, end);
+                    if end < batch.num_rows() {
+                        self.current = Some((batch, end));
+                    }
+                    output
+                }
+                None => match self.left.next() {
+                    Some(Ok(batch)) => {
+                        self.current = Some((batch, 0));
+                        continue;
+                    }
+                    Some(Err(e)) => Err(e),
+                    None => {
+                        self.done = true;
+                        self.unmatched_right_rows()
+                    }
+                },
+            };
+            match output {
+                Ok(batch) if batch.num_rows() == 0 => {}
+                Ok(batch) => return Some(Ok(batch)),
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl NestedLoopStream {
+    /// Joins the rows from `start` up to `end` of a left batch with every right row.
+    fn join_rows(&mut self, batch: &RecordBatch, start: usize, end: usize) -> Result<RecordBatch> {
+        let right_count = self.right.num_rows() as u32;
+        let mut left_rows: Vec<u32> = vec![];
+        let mut right_rows: Vec<u32> = vec![];
+        for l in start as u32..end as u32 {
+            for r in 0..right_count {
+                left_rows.push(l);
+                right_rows.push(r);
+            }
+        }
+        if let Some(filter) = &self.filter {
+            filter.filter_pairs(&mut left_rows, &mut right_rows, |left_rows, right_rows| {
+                self.combine(batch, left_rows, right_rows, filter.schema())
+            })?;
+        }
+        for &r in &right_rows {
+            self.visited[r as usize] = true;
+        }
+
+        let preserved = matches!(self.join_type, JoinType::Left | JoinType::Full);
+        let (left_out, right_out) = join_rows(
+            self.join_type,
+            start as u32..end as u32,
+            &left_rows,
+            &right_rows,
+            preserved,
+        );
+        self.combine(batch, &left_out, &right_out, &self.schema)
+    }
+
+    /// The right rows that are part of the output without a match.
+    fn unmatched_right_rows(&self) -> Result<RecordBatch> {
+        if !matches!(self.join_type, JoinType::Right | JoinType::Full) {
+            return Ok(RecordBatch::new_empty(Arc::clone(&self.schema)));
+        }
+        let right_rows: Vec<Option<u32>> = (0..self.visited.len())
+            .filter(|&r| !self.visited[r])
+            .map(|r| Some(r as u32))
+            .collect();
+        let left_rows = vec![None; right_rows.len()];
+        let empty = RecordBatch::new_empty(Arc::clone(&self.left_schema));
+        self.combine(&empty, &left_rows, &right_rows, &self.schema)
+    }
+
+    /// Takes the given rows of the left batch and the right input, where `None` stands
+    /// for a row of nulls, and puts their columns in the order of `schema`.
+    fn combine(
+        &self,
+        left: &RecordBatch,
+        left_rows: &[Option<u32>],
+        right_rows: &[Option<u32>],
+        schema: &Arc<Schema>,
+    ) -> Result<RecordBatch> {
+        joined_batch(
+            schema,
+            take_rows(left, left_rows)?,
+            take_rows(&self.right, right_rows)?,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data_source::CsvReadOptions;
+    use crate::core::data_type::ArrowType;
+    use crate::core::execution_context::ExecutionContext;
+    use crate::core::helper::*;
+    use crate::logical_plan::expression::LogicalExpression;
+    use crate::logical_plan::join::Join;
+    use crate::logical_plan::LogicalPlan;
+    use crate::physical_plan::collect;
+    use crate::physical_plan::planner::PhysicalPlanner;
+    use crate::test_util::{csv_context, rows, write_csv};
+
+    fn context(dir: &tempfile::TempDir) -> ExecutionContext {
+        csv_context(
+            dir,
+            vec![
+                (
+                    "events",
+                    CsvReadOptions::new().schema(schema(vec![
+                        ("id", ArrowType::Int64Type, false),
+                        ("ts", ArrowType::Int64Type, false),
+                    ])),
+                    &["1,5", "2,15", "3,25", "4,12"],
+                ),
+                (
+                    "windows",
+                    CsvReadOptions::new().schema(schema(vec![
+                        ("name", ArrowType::StringType, false),
+                        ("start", ArrowType::Int64Type, false),
+                        ("stop", ArrowType::Int64Type, false),
+                    ])),
+                    &["a,0,9", "b,10,19", "c,12,30"],
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn range_join() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = context(&dir);
+        let df = ctx
+            .sql("SELECT id, name FROM events JOIN windows ON ts BETWEEN start AND stop")
+            .unwrap();
+        let plan = ctx.create_physical_plan(df.plan()).unwrap();
+        assert_eq!(
+            PhysicalPlan::to_string(plan.children()[0].as_ref()),
+            "NestedLoopJoinExec: joinType=Inner, filter=#1>=#3 AND #1<=#4"
+        );
+        assert_eq!(
+            rows(&df.collect().unwrap()),
+            ["1,a", "2,b", "2,c", "3,c", "4,b", "4,c"]
+        );
+
+        let df = ctx
+            .sql("SELECT id, name FROM events LEFT JOIN windows ON ts NOT BETWEEN start AND 20")
+            .unwrap();
+        assert_eq!(
+            rows(&df.collect().unwrap()),
+            ["1,b", "1,c", "2,", "3,a", "3,b", "3,c", "4,"]
+        );
+    }
+
+    #[test]
+    fn cross_join() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = context(&dir);
+        let df = ctx
+            .sql("SELECT id, name FROM events CROSS JOIN windows")
+            .unwrap();
+        let rows = rows(&df.collect().unwrap());
+        assert_eq!(rows.len(), 12);
+        assert_eq!(rows[..3], ["1,a", "1,b", "1,c"]);
+    }
+
+    #[test]
+    fn join_types() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = context(&dir);
+        let join = |join_type: JoinType| {
+            let filter = gt(col("ts"), col("stop")) as Arc<dyn LogicalExpression>;
+            let plan = Arc::new(
+                Join::try_new(
+                    ctx.table("events").unwrap().plan(),
+                    ctx.table("windows").unwrap().plan(),
+                    join_type,
+                    vec![],
+                    Some(filter),
+                )
+                .unwrap(),
+            ) as Arc<dyn LogicalPlan>;
+            let plan = PhysicalPlanner::new().create_physical_plan(&plan).unwrap();
+            rows(&collect(plan).unwrap())
+        };
+
+        let inner = ["2,15,a,0,9", "3,25,a,0,9", "3,25,b,10,19", "4,12,a,0,9"];
+        assert_eq!(join(JoinType::Inner), inner);
+        let mut left = inner.to_vec();
+        left.insert(0, "1,5,,,");
+        assert_eq!(join(JoinType::Left), left);
+        let mut right = inner.to_vec();
+        right.push(",,c,12,30");
+        assert_eq!(join(JoinType::Right), right);
+        let mut full = left.clone();
+        full.push(",,c,12,30");
+        assert_eq!(join(JoinType::Full), full);
+        assert_eq!(join(JoinType::Semi), ["2,15", "3,25", "4,12"]);
+        assert_eq!(join(JoinType::Anti), ["1,5"]);
+    }
+
+    #[test]
+    fn limits_the_pairs_evaluated_at_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = ExecutionContext::new();
+        let lines: Vec<String> = (0..200).map(|i| i.to_string()).collect();
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        let path = write_csv(&dir, "numbers.csv", &lines);
+        let options =
+            || CsvReadOptions::new().schema(schema(vec![("n", ArrowType::Int64Type, false)]));
+        ctx.register_csv("a", &path, options()).unwrap();
+        ctx.register_csv("b", &path, options()).unwrap();
+
+        let batches = ctx
+            .sql("SELECT a.n FROM a CROSS JOIN b")
+            .unwrap()
+            .collect()
+            .unwrap();
+        assert!(batches.iter().all(|b| b.num_rows() <= MAX_PAIRS));
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 40_000);
+    }
+}
@@ -0,0 +1,522 @@
+use arrow::compute::SortOptions;
+use arrow::datatypes::Schema;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::accumulator::{
+    AggregateExpression, AvgExpression, MaxExpression, MinExpression, SingleExpression,
+    SumExpression,
+};
+use super::aggregate::HashAggregateExec;
+use super::empty::EmptyExec;
+use super::explain::ExplainExec;
+use super::expression::{self, PhysicalExpression};
+use super::hash_join::{HashJoinExec, JoinFilter};
+use super::limit::LimitExec;
+use super::metrics::MetricsExec;
+use super::nested_loop_join::NestedLoopJoinExec;
+use super::projection::ProjectionExec;
+use super::scan::ScanExec;
+use super::selection::SelectionExec;
+use super::sort::{PhysicalSortExpression, SortExec, DEFAULT_SORT_MEMORY_LIMIT};
+use super::sort_merge_join::SortMergeJoinExec;
+use super::top_k::TopKExec;
+use super::PhysicalPlan;
+use crate::core::error::{QrustError, Result};
+use crate::core::scalar_value::ScalarValue;
+use crate::logical_plan::aggregate::Aggregate;
+use crate::logical_plan::empty_relation::EmptyRelation;
+use crate::logical_plan::explain::{Explain, StringifiedPlan};
+use crate::logical_plan::expression as logical_expression;
+use crate::logical_plan::expression::{
+    AliasExpression, BinaryExpression, BooleanExpression, ColumnExpression,
+    LiteralBooleanExpression, LiteralI64Expression, LiteralStringExpression, LogicalExpression,
+    MathExpression, NotExpression, PlaceholderExpression,
+};
+use crate::logical_plan::join::Join;
+use crate::logical_plan::limit::Limit;
+use crate::logical_plan::projection::Projection;
+use crate::logical_plan::scan::Scan;
+use crate::logical_plan::selection::Selection;
+use crate::logical_plan::sort::Sort;
+use crate::logical_plan::subquery_alias::SubqueryAlias;
+use crate::logical_plan::LogicalPlan;
+
+/// Converts logical plans into physical plans that can be executed.
+#[derive(Clone)]
+pub struct PhysicalPlanner {
+    collect_metrics: bool,
+    /// The values that the parameters of a prepared statement are replaced with.
+    param_values: HashMap<String, ScalarValue>,
+    /// The bytes of input a sort buffers before it spills sorted runs to disk.
+    sort_memory_limit: usize,
+}
+
+impl Default for PhysicalPlanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PhysicalPlanner {
+    pub fn new() -> PhysicalPlanner {
+        PhysicalPlanner {
+            collect_metrics: false,
+            param_values: HashMap::new(),
+            sort_memory_limit: DEFAULT_SORT_MEMORY_LIMIT,
+        }
+    }
+
+    /// Wraps every operator of the plans created by this planner in a `MetricsExec`.
+    pub fn with_metrics(self) -> PhysicalPlanner {
+        PhysicalPlanner {
+            collect_metrics: true,
+            ..self
+        }
+    }
+
+    /// Replaces each parameter, such as `$1`, with the value stored under its name.
+    pub fn with_param_values(self, param_values: HashMap<String, ScalarValue>) -> PhysicalPlanner {
+        PhysicalPlanner {
+            param_values,
+            ..self
+        }
+    }
+
+    /// Sets the bytes of input each sort may buffer before it spills to disk.
+    pub fn with_sort_memory_limit(self, sort_memory_limit: usize) -> PhysicalPlanner {
+        PhysicalPlanner {
+            sort_memory_limit,
+            ..self
+        }
+    }
+
+    /// The bytes of input each sort may buffer before it spills to disk.
+    pub fn sort_memory_limit(&self) -> usize {
+        self.sort_memory_limit
+    }
+
+    pub fn create_physical_plan(
+        &self,
+        plan: &Arc<dyn LogicalPlan>,
+    ) -> Result<Arc<dyn PhysicalPlan>> {
+        let exec = self.create_operator(plan)?;
+        if self.collect_metrics {
+            Ok(Arc::new(MetricsExec::new(exec)))
+        } else {
+            Ok(exec)
+        }
+    }
+
+    /// Creates the operator for the root of `plan`, planning its inputs recursively.
+    fn create_operator(&self, plan: &Arc<dyn LogicalPlan>) -> Result<Arc<dyn PhysicalPlan>> {
+        let any = plan.as_any();
+
+        if let Some(scan) = any.downcast_ref::<Scan>() {
+            return Ok(Arc::new(ScanExec::new(
+                scan.path().to_owned(),
+                scan.schema(),
+                scan.datasource(),
+                scan.projection().to_vec(),
+                scan.filters().to_vec(),
+            )));
+        }
+
+        if let Some(selection) = any.downcast_ref::<Selection>() {
+            let input = self.create_physical_plan(&selection.input())?;
+            let expr = self.create_physical_expression(&selection.expr(), &input.schema())?;
+            return Ok(Arc::new(SelectionExec::new(input, expr)));
+        }
+
+        if let Some(sort) = any.downcast_ref::<Sort>() {
+            let input = self.create_physical_plan(&sort.input())?;
+            let expr = sort
+                .expr()
+                .iter()
+                .map(|e| {
+                    let options = SortOptions {
+                        descending: !e.is_asc(),
+                        nulls_first: e.is_nulls_first(),
+                    };
+                    let expr = self.create_physical_expression(&e.expr(), &input.schema())?;
+                    Ok(PhysicalSortExpression::new(expr, options))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            if let Some(fetch) = sort.fetch() {
+                return Ok(Arc::new(TopKExec::new(input, expr, fetch)));
+            }
+            return Ok(Arc::new(SortExec::new(input, expr, self.sort_memory_limit)));
+        }
+
+        if let Some(limit) = any.downcast_ref::<Limit>() {
+            let input = self.create_physical_plan(&limit.input())?;
+            return Ok(Arc::new(LimitExec::new(input, limit.skip(), limit.fetch())));
+        }
+
+        if let Some(projection) = any.downcast_ref::<Projection>() {
+            let input = self.create_physical_plan(&projection.input())?;
+            let expr = projection
+                .expr()
+                .iter()
+                .map(|e| self.create_physical_expression(e, &input.schema()))
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(Arc::new(ProjectionExec::new(
+                input,
+                expr,
+                projection.schema(),
+            )));
+        }
+
+        if let Some(aggregate) = any.downcast_ref::<Aggregate>() {
+            let input = self.create_physical_plan(&aggregate.input())?;
+            let groupexpr = aggregate
+                .group_expr()
+                .iter()
+                .map(|e| self.create_physical_expression(e, &input.schema()))
+                .collect::<Result<Vec<_>>>()?;
+            let aggregateexpr = aggregate
+                .aggregate_expr()
+                .iter()
+                .map(|a| self.create_aggregate_expression(a, &aggregate.input(), &input.schema()))
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(Arc::new(HashAggregateExec::new(
+                input,
+                groupexpr,
+                aggregateexpr,
+                aggregate.schema(),
+            )));
+        }
+
+        if let Some(join) = any.downcast_ref::<Join>() {
+            let left = self.create_physical_plan(&join.left())?;
+            let right = self.create_physical_plan(&join.right())?;
+            let on = join
+                .on()
+                .iter()
+                .map(|(l, r)| Ok((left.schema().index_of(l)?, right.schema().index_of(r)?)))
+                .collect::<Result<Vec<_>>>()?;
+            let joined_schema = join.joined_schema();
+            let filter = join
+                .filter()
+                .map(|filter| self.create_physical_expression(&filter, &joined_schema))
+                .transpose()?
+                .map(|expr| JoinFilter::new(expr, joined_schema));
+            // Without equality keys, every pair of rows has to be tried.
+            if on.is_empty() {
+                return Ok(Arc::new(NestedLoopJoinExec::new(
+                    left,
+                    right,
+                    join.join_type(),
+                    filter,
+                    plan.schema(),
+                )));
+            }
+            // Inputs that are already sorted on the keys are merged, which doesn't need
+            // to hold either of them in memory.
+            let (left_keys, right_keys): (Vec<String>, Vec<String>) =
+                join.on().iter().cloned().unzip();
+            if left.output_ordering().starts_with(&left_keys)
+                && right.output_ordering().starts_with(&right_keys)
+            {
+                return Ok(Arc::new(SortMergeJoinExec::new(
+                    left,
+                    right,
+                    join.join_type(),
+                    on,
+                    filter,
+                    plan.schema(),
+                )));
+            }
+            return Ok(Arc::new(HashJoinExec::new(
+                left,
+                right,
+                join.join_type(),
+                on,
+                filter,
+                plan.schema(),
+            )));
+        }
+
+        // An alias only renames the relation, which doesn't change how it is computed.
+        if let Some(alias) = any.downcast_ref::<SubqueryAlias>() {
+            return self.create_operator(&alias.input());
+        }
+
+        if any.downcast_ref::<EmptyRelation>().is_some() {
+            return Ok(Arc::new(EmptyExec::new(plan.schema())));
+        }
+
+        if let Some(explain) = any.downcast_ref::<Explain>() {
+            let input = if explain.analyze() {
+                self.clone()
+                    .with_metrics()
+                    .create_physical_plan(&explain.input())?
+            } else {
+                self.create_physical_plan(&explain.input())?
+            };
+            let mut plans = explain.stringified_plans().to_vec();
+            plans.push(StringifiedPlan::new(
+                "optimized_logical_plan",
+                explain.input().format(),
+            ));
+            return Ok(Arc::new(ExplainExec::new(
+                plan.schema(),
+                plans,
+                input,
+                explain.analyze(),
+            )));
+        }
+
+        Err(QrustError::NotImplemented(format!(
+            "Physical planning is not supported for {}",
+            LogicalPlan::to_string(plan.as_ref())
+        )))
+    }
+
+    pub fn create_aggregate_expression(
+        &self,
+        aggregate: &logical_expression::AggregateExpression,
+        input: &Arc<dyn LogicalPlan>,
+        input_schema: &Schema,
+    ) -> Result<Arc<dyn AggregateExpression>> {
+        let expr = self.create_physical_expression(&aggregate.expr(), input_schema)?;
+        let data_type = aggregate
+            .expr()
+            .to_field(Arc::clone(input))
+            .data_type()
+            .clone();
+        Ok(match aggregate.name() {
+            "sum" => Arc::new(SumExpression::try_new(expr, data_type)?),
+            "min" => Arc::new(MinExpression::try_new(expr, data_type)?),
+            "max" => Arc::new(MaxExpression::try_new(expr, data_type)?),
+            "avg" => Arc::new(AvgExpression::try_new(expr, data_type)?),
+            "single" => Arc::new(SingleExpression::try_new(expr, data_type)?),
+            name => {
+                return Err(QrustError::NotImplemented(format!(
+                    "Unsupported aggregate function {}",
+                    name
+                )))
+            }
+        })
+    }
+
+    pub fn create_physical_expression(
+        &self,
+        expr: &Arc<dyn LogicalExpression>,
+        input_schema: &Schema,
+    ) -> Result<Arc<dyn PhysicalExpression>> {
+        let any = expr.as_any();
+
+        if let Some(column) = any.downcast_ref::<ColumnExpression>() {
+            let index = input_schema.index_of(column.name())?;
+            return Ok(Arc::new(expression::ColumnExpression::new(index)));
+        }
+
+        // Output names come from the logical schema, so aliases have nothing to evaluate.
+        if let Some(alias) = any.downcast_ref::<AliasExpression>() {
+            return self.create_physical_expression(&alias.expr(), input_schema);
+        }
+
+        if let Some(literal) = any.downcast_ref::<LiteralI64Expression>() {
+            return Ok(Arc::new(expression::LiteralI64Expression::new(
+                literal.val(),
+            )));
+        }
+
+        if let Some(literal) = any.downcast_ref::<LiteralStringExpression>() {
+            return Ok(Arc::new(expression::LiteralStringExpression::new(
+                literal.val().to_owned(),
+            )));
+        }
+
+        if let Some(literal) = any.downcast_ref::<LiteralBooleanExpression>() {
+            return Ok(Arc::new(expression::LiteralBooleanExpression::new(
+                literal.val(),
+            )));
+        }
+
+        if let Some(placeholder) = any.downcast_ref::<PlaceholderExpression>() {
+            let value = self.param_values.get(placeholder.id()).ok_or_else(|| {
+                QrustError::Plan(format!("No value for parameter {}", placeholder.id()))
+            })?;
+            return Ok(Arc::new(expression::LiteralScalarExpression::new(
+                value.clone(),
+            )));
+        }
+
+        if let Some(not) = any.downcast_ref::<NotExpression>() {
+            let expr = self.create_physical_expression(&not.expr(), input_schema)?;
+            return Ok(Arc::new(expression::NotExpression::new(expr)));
+        }
+
+        if let Some(binary) = any.downcast_ref::<BooleanExpression>() {
+            let l = self.create_physical_expression(&binary.l(), input_schema)?;
+            let r = self.create_physical_expression(&binary.r(), input_schema)?;
+            return Ok(Arc::new(expression::BooleanExpression::try_new(
+                &binary.name(),
+                l,
+                r,
+            )?));
+        }
+
+        if let Some(binary) = any.downcast_ref::<MathExpression>() {
+            let l = self.create_physical_expression(&binary.l(), input_schema)?;
+            let r = self.create_physical_expression(&binary.r(), input_schema)?;
+            return Ok(Arc::new(expression::MathExpression::try_new(
+                &binary.name(),
+                l,
+                r,
+            )?));
+        }
+
+        Err(QrustError::NotImplemented(format!(
+            "Physical planning is not supported for expression {}",
+            expr.to_string()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data_source::CsvReadOptions;
+    use crate::core::data_type::ArrowType;
+    use crate::core::execution_context::ExecutionContext;
+    use crate::core::helper::*;
+    use crate::physical_plan::collect;
+    use crate::test_util::write_csv;
+    use arrow::array::Int64Array;
+
+    #[test]
+    fn plan_projection() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_csv(&dir, "input.csv", &["1,2,3", "4,5,6"]);
+        let schema = schema(vec![
+            ("column1", ArrowType::Int64Type, false),
+            ("column2", ArrowType::Int64Type, false),
+            ("column3", ArrowType::Int64Type, false),
+        ]);
+        let df = ExecutionContext::new()
+            .csv(schema, &path)
+            .select(vec![col("column3"), col("column1")]);
+
+        let plan = PhysicalPlanner::new()
+            .create_physical_plan(&df.plan())
+            .unwrap();
+        assert_eq!(
+            plan.format(),
+            format!(
+                "ProjectionExec: #2, #0\n  ScanExec: {}, projection=None\n",
+                path
+            )
+        );
+        assert_eq!(plan.schema(), df.schema());
+
+        let batches = collect(plan).unwrap();
+        assert_eq!(batches.len(), 1);
+        let c1 = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(c1.values(), &[3, 6]);
+        let c2 = batches[0]
+            .column(1)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(c2.values(), &[1, 4]);
+    }
+
+    #[test]
+    fn plan_selection() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_csv(&dir, "input.csv", &["1,a", "2,b", "3,c", "4,d"]);
+        let schema = schema(vec![
+            ("id", ArrowType::Int64Type, false),
+            ("name", ArrowType::StringType, false),
+        ]);
+        let df = ExecutionContext::new()
+            .csv(schema, &path)
+            .filter(or(
+                eq(col("name"), lit(&"a")),
+                gt(modulus(col("id"), lit(&3)), lit(&1)),
+            ))
+            .select(vec![multiply(col("id"), lit(&10))]);
+
+        let plan = PhysicalPlanner::new()
+            .create_physical_plan(&df.plan())
+            .unwrap();
+        assert_eq!(
+            plan.format(),
+            format!(
+                "ProjectionExec: #0 * 10\n  SelectionExec: #1='a' OR #0 % 3>1\n    ScanExec: {}, projection=None\n",
+                path
+            )
+        );
+
+        let batches = collect(plan).unwrap();
+        let c = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(c.values(), &[10, 20]);
+    }
+
+    #[test]
+    fn plan_join() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = ExecutionContext::new();
+        let options = |sort_order: &[&str]| {
+            CsvReadOptions::new()
+                .schema(schema(vec![
+                    ("id", ArrowType::Int64Type, false),
+                    ("ts", ArrowType::Int64Type, false),
+                ]))
+                .sorted_by(sort_order)
+        };
+        let path = write_csv(&dir, "events.csv", &["1,10", "2,20"]);
+        ctx.register_csv("a", &path, options(&["id", "ts"]))
+            .unwrap();
+        ctx.register_csv("b", &path, options(&["id"])).unwrap();
+        ctx.register_csv("c", &path, options(&["ts"])).unwrap();
+        let physical = |sql: &str| {
+            let plan = ctx.optimize(ctx.sql(sql).unwrap().plan()).unwrap();
+            let plan = PhysicalPlanner::new().create_physical_plan(&plan).unwrap();
+            PhysicalPlan::to_string(plan.children()[0].as_ref())
+        };
+
+        assert_eq!(
+            physical("SELECT a.ts, b.id FROM a JOIN b ON a.id = b.id"),
+            "SortMergeJoinExec: joinType=Inner, on=[#0=#0]"
+        );
+        // b isn't sorted on ts.
+        assert_eq!(
+            physical("SELECT a.ts FROM a JOIN b ON a.id = b.id AND a.ts = b.ts"),
+            "HashJoinExec: joinType=Inner, on=[#0=#0, #1=#1]"
+        );
+        // a is sorted on id before ts.
+        assert_eq!(
+            physical("SELECT c.ts FROM c RIGHT JOIN a ON c.ts = a.ts"),
+            "HashJoinExec: joinType=Right, on=[#0=#0]"
+        );
+        // The ordering survives projections that keep the key columns.
+        assert_eq!(
+            physical("SELECT x.t FROM (SELECT ts AS t FROM c) x FULL JOIN b ON t = b.id"),
+            "SortMergeJoinExec: joinType=Full, on=[#0=#0]"
+        );
+    }
+
+    #[test]
+    fn plan_unsupported_expression() {
+        let schema = schema(vec![("column1", ArrowType::Int64Type, false)]);
+        let df = ExecutionContext::new()
+            .csv(schema, "test.csv")
+            .select(vec![sum(col("column1"))]);
+
+        assert!(PhysicalPlanner::new()
+            .create_physical_plan(&df.plan())
+            .is_err());
+    }
+}
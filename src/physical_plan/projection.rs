@@ -0,0 +1,75 @@
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
+use std::sync::Arc;
+
+use super::expression::PhysicalExpression;
+use super::{BatchIterator, PhysicalPlan};
+use crate::core::error::Result;
+
+pub struct ProjectionExec {
+    input: Arc<dyn PhysicalPlan>,
+    expr: Vec<Arc<dyn PhysicalExpression>>,
+    schema: Arc<Schema>,
+}
+
+impl PhysicalPlan for ProjectionExec {
+    fn schema(&self) -> Arc<Schema> {
+        Arc::clone(&self.schema)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalPlan>> {
+        vec![Arc::clone(&self.input)]
+    }
+
+    fn execute(&self) -> Result<BatchIterator> {
+        let expr = self.expr.clone();
+        let schema = Arc::clone(&self.schema);
+        Ok(Box::new(self.input.execute()?.map(move |batch| {
+            let batch = batch?;
+            let columns = expr
+                .iter()
+                .map(|e| e.evaluate(&batch))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(RecordBatch::try_new(Arc::clone(&schema), columns)?)
+        })))
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "ProjectionExec: {}",
+            self.expr
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+
+    /// The ordering of the input, as far as its columns are passed through.
+    fn output_ordering(&self) -> Vec<String> {
+        let input_schema = self.input.schema();
+        let mut ordering = vec![];
+        for column in self.input.output_ordering() {
+            let index = input_schema.index_of(&column).ok();
+            match self.expr.iter().position(|e| e.column_index() == index) {
+                Some(i) => ordering.push(self.schema.field(i).name().clone()),
+                None => break,
+            }
+        }
+        ordering
+    }
+}
+
+impl ProjectionExec {
+    pub fn new(
+        input: Arc<dyn PhysicalPlan>,
+        expr: Vec<Arc<dyn PhysicalExpression>>,
+        schema: Arc<Schema>,
+    ) -> ProjectionExec {
+        ProjectionExec {
+            input,
+            expr,
+            schema,
+        }
+    }
+}
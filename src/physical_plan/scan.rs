@@ -0,0 +1,76 @@
+use arrow::datatypes::Schema;
+use std::sync::Arc;
+
+use super::{BatchIterator, PhysicalPlan};
+use crate::core::data_source::DataSource;
+use crate::core::error::Result;
+use crate::logical_plan::expression::LogicalExpression;
+
+pub struct ScanExec {
+    path: String,
+    schema: Arc<Schema>,
+    datasource: Arc<dyn DataSource>,
+    projection: Vec<String>,
+    filters: Vec<Arc<dyn LogicalExpression>>,
+}
+
+impl PhysicalPlan for ScanExec {
+    fn schema(&self) -> Arc<Schema> {
+        Arc::clone(&self.schema)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalPlan>> {
+        vec![]
+    }
+
+    fn execute(&self) -> Result<BatchIterator> {
+        self.datasource
+            .scan(self.projection.clone(), self.filters.clone())
+    }
+
+    fn to_string(&self) -> String {
+        let mut s = if self.projection.is_empty() {
+            format!("ScanExec: {}, projection=None", self.path)
+        } else {
+            format!("ScanExec: {}, projection={:?}", self.path, self.projection)
+        };
+        if !self.filters.is_empty() {
+            s.push_str(&format!(
+                ", filters=[{}]",
+                self.filters
+                    .iter()
+                    .map(|f| f.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ));
+        }
+        s
+    }
+
+    /// The ordering of the source, as far as its columns are read.
+    fn output_ordering(&self) -> Vec<String> {
+        self.datasource
+            .output_ordering()
+            .into_iter()
+            .take_while(|c| self.schema.field_with_name(c).is_ok())
+            .collect()
+    }
+}
+
+impl ScanExec {
+    pub fn new(
+        path: String,
+        schema: Arc<Schema>,
+        datasource: Arc<dyn DataSource>,
+        projection: Vec<String>,
+        filters: Vec<Arc<dyn LogicalExpression>>,
+    ) -> ScanExec {
+        ScanExec {
+            path,
+            schema,
+            datasource,
+            projection,
+            filters,
+        }
+    }
+}
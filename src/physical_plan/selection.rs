@@ -0,0 +1,105 @@
+use arrow::array::BooleanArray;
+use arrow::compute::filter_record_batch;
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
+use std::sync::Arc;
+
+use super::expression::PhysicalExpression;
+use super::{BatchIterator, PhysicalPlan};
+use crate::core::error::{QrustError, Result};
+
+pub struct SelectionExec {
+    input: Arc<dyn PhysicalPlan>,
+    expr: Arc<dyn PhysicalExpression>,
+}
+
+impl PhysicalPlan for SelectionExec {
+    fn schema(&self) -> Arc<Schema> {
+        self.input.schema()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalPlan>> {
+        vec![Arc::clone(&self.input)]
+    }
+
+    fn execute(&self) -> Result<BatchIterator> {
+        let expr = Arc::clone(&self.expr);
+        Ok(Box::new(
+            self.input
+                .execute()?
+                .map(move |batch| filter_batch(&batch?, &expr)),
+        ))
+    }
+
+    fn to_string(&self) -> String {
+        format!("SelectionExec: {}", self.expr.to_string())
+    }
+
+    fn output_ordering(&self) -> Vec<String> {
+        self.input.output_ordering()
+    }
+}
+
+impl SelectionExec {
+    pub fn new(input: Arc<dyn PhysicalPlan>, expr: Arc<dyn PhysicalExpression>) -> SelectionExec {
+        SelectionExec { input, expr }
+    }
+}
+
+/// Keeps the rows of `batch` for which `predicate` evaluates to true.
+pub fn filter_batch(
+    batch: &RecordBatch,
+    predicate: &Arc<dyn PhysicalExpression>,
+) -> Result<RecordBatch> {
+    let mask = predicate.evaluate(batch)?;
+    let mask = mask
+        .as_any()
+        .downcast_ref::<BooleanArray>()
+        .ok_or_else(|| {
+            QrustError::Execution(format!(
+                "Filter predicate {} did not evaluate to a boolean",
+                predicate.to_string()
+            ))
+        })?;
+    Ok(filter_record_batch(batch, mask)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data_source::DataSource;
+    use crate::physical_plan::expression::{
+        BooleanExpression, ColumnExpression, LiteralI64Expression,
+    };
+    use crate::physical_plan::scan::ScanExec;
+    use crate::test_util::CountingSource;
+
+    #[test]
+    fn pulls_batches_lazily() {
+        let source = Arc::new(CountingSource::new(100));
+        let scan = Arc::new(ScanExec::new(
+            "counting".to_owned(),
+            source.schema(),
+            Arc::clone(&source) as Arc<dyn DataSource>,
+            vec![],
+            vec![],
+        ));
+        let predicate = BooleanExpression::try_new(
+            "lt",
+            Arc::new(ColumnExpression::new(0)),
+            Arc::new(LiteralI64Expression::new(10)),
+        )
+        .unwrap();
+        let selection = SelectionExec::new(scan, Arc::new(predicate));
+
+        let mut batches = selection.execute().unwrap();
+        assert_eq!(source.pulled(), 0);
+
+        let first = batches.next().unwrap().unwrap();
+        assert_eq!(first.num_rows(), 1);
+        assert_eq!(source.pulled(), 1);
+
+        assert_eq!(batches.count(), 99);
+        assert_eq!(source.pulled(), 100);
+    }
+}
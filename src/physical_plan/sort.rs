@@ -0,0 +1,530 @@
+use arrow::array::ArrayRef;
+use arrow::compute::{concat, lexsort_to_indices, take, SortColumn, SortOptions};
+use arrow::datatypes::Schema;
+use arrow::ipc::reader::StreamReader;
+use arrow::ipc::writer::StreamWriter;
+use arrow::record_batch::RecordBatch;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::io::{BufReader, Seek, SeekFrom};
+use std::sync::atomic::{self, AtomicUsize};
+use std::sync::Arc;
+
+use super::expression::PhysicalExpression;
+use super::hash_join::concat_batches;
+use super::{BatchIterator, PhysicalPlan};
+use crate::core::error::{QrustError, Result};
+use crate::core::scalar_value::ScalarValue;
+
+/// The number of rows in each batch the sort produces.
+const BATCH_SIZE: usize = 1024;
+
+/// The memory a sort may use to buffer its input before it spills to disk, in bytes.
+pub const DEFAULT_SORT_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+
+/// An expression to sort by, together with its direction and where it puts nulls.
+#[derive(Clone)]
+pub struct PhysicalSortExpression {
+    expr: Arc<dyn PhysicalExpression>,
+    options: SortOptions,
+}
+
+impl PhysicalSortExpression {
+    pub fn new(expr: Arc<dyn PhysicalExpression>, options: SortOptions) -> PhysicalSortExpression {
+        PhysicalSortExpression { expr, options }
+    }
+
+    /// Evaluates the expression being sorted by on `batch`.
+    pub(super) fn evaluate(&self, batch: &RecordBatch) -> Result<ArrayRef> {
+        self.expr.evaluate(batch)
+    }
+}
+
+impl fmt::Display for PhysicalSortExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} NULLS {}",
+            self.expr.to_string(),
+            if self.options.descending {
+                "DESC"
+            } else {
+                "ASC"
+            },
+            if self.options.nulls_first {
+                "FIRST"
+            } else {
+                "LAST"
+            }
+        )
+    }
+}
+
+/// Sorts its input. The input is buffered until it exceeds the memory limit, at which
+/// point the buffered rows are sorted and written to a temporary file as a sorted run.
+/// Once the input is exhausted, the runs and the rows still in memory are merged.
+pub struct SortExec {
+    input: Arc<dyn PhysicalPlan>,
+    expr: Vec<PhysicalSortExpression>,
+    memory_limit: usize,
+    /// The number of runs written to disk, over every execution.
+    spills: Arc<AtomicUsize>,
+}
+
+impl PhysicalPlan for SortExec {
+    fn schema(&self) -> Arc<Schema> {
+        self.input.schema()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalPlan>> {
+        vec![Arc::clone(&self.input)]
+    }
+
+    fn execute(&self) -> Result<BatchIterator> {
+        let mut runs = vec![];
+        let mut buffered = vec![];
+        let mut buffered_size = 0;
+        for batch in self.input.execute()? {
+            let batch = batch?;
+            buffered_size += batch
+                .columns()
+                .iter()
+                .map(|c| c.get_array_memory_size())
+                .sum::<usize>();
+            buffered.push(batch);
+            if buffered_size > self.memory_limit {
+                let sorted = self.sort_batches(&buffered)?;
+                runs.push(self.spill(&sorted)?);
+                buffered.clear();
+                buffered_size = 0;
+            }
+        }
+
+        let sorted = self.sort_batches(&buffered)?;
+        let in_memory = Box::new(
+            (0..sorted.num_rows())
+                .step_by(BATCH_SIZE)
+                .map(move |start| {
+                    Ok(sorted.slice(start, BATCH_SIZE.min(sorted.num_rows() - start)))
+                }),
+        );
+        if runs.is_empty() {
+            return Ok(in_memory);
+        }
+        runs.push(in_memory);
+        Ok(Box::new(MergeStream::try_new(
+            runs,
+            self.expr.clone(),
+            self.schema(),
+        )?))
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "SortExec: {}",
+            self.expr
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+
+    fn output_ordering(&self) -> Vec<String> {
+        sort_ordering(&self.schema(), &self.expr)
+    }
+}
+
+impl SortExec {
+    pub fn new(
+        input: Arc<dyn PhysicalPlan>,
+        expr: Vec<PhysicalSortExpression>,
+        memory_limit: usize,
+    ) -> SortExec {
+        SortExec {
+            input,
+            expr,
+            memory_limit,
+            spills: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// The number of sorted runs that were written to disk.
+    pub fn spills(&self) -> usize {
+        self.spills.load(atomic::Ordering::Relaxed)
+    }
+
+    /// Sorts the rows of `batches` into a single batch.
+    fn sort_batches(&self, batches: &[RecordBatch]) -> Result<RecordBatch> {
+        let batch = concat_batches(self.schema(), batches)?;
+        if batch.num_rows() == 0 {
+            return Ok(batch);
+        }
+        let columns = self
+            .expr
+            .iter()
+            .map(|e| {
+                Ok(SortColumn {
+                    values: e.expr.evaluate(&batch)?,
+                    options: Some(e.options),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let indices = lexsort_to_indices(&columns, None)?;
+        let columns = batch
+            .columns()
+            .iter()
+            .map(|c| take(c.as_ref(), &indices, None))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(RecordBatch::try_new(self.schema(), columns)?)
+    }
+
+    /// Writes a sorted batch to a temporary file, returning the batches read back from it.
+    /// The file is deleted once they have all been read.
+    fn spill(&self, sorted: &RecordBatch) -> Result<BatchIterator> {
+        let mut writer = StreamWriter::try_new(tempfile::tempfile()?, &self.schema())?;
+        for start in (0..sorted.num_rows()).step_by(BATCH_SIZE) {
+            writer.write(&sorted.slice(start, BATCH_SIZE.min(sorted.num_rows() - start)))?;
+        }
+        let mut file = writer.into_inner()?;
+        file.seek(SeekFrom::Start(0))?;
+        self.spills.fetch_add(1, atomic::Ordering::Relaxed);
+        let reader = StreamReader::try_new(BufReader::new(file))?;
+        Ok(Box::new(
+            reader.map(|batch| batch.map_err(QrustError::from)),
+        ))
+    }
+}
+
+/// A sorted run being merged, positioned at its smallest row that hasn't been output.
+struct RunCursor {
+    batches: BatchIterator,
+    /// The batch holding the current row, or `None` once the run is exhausted.
+    batch: Option<RecordBatch>,
+    /// The values of the sort expressions for `batch`.
+    keys: Vec<ArrayRef>,
+    row: usize,
+}
+
+impl RunCursor {
+    fn try_new(batches: BatchIterator, expr: &[PhysicalSortExpression]) -> Result<RunCursor> {
+        let mut cursor = RunCursor {
+            batches,
+            batch: None,
+            keys: vec![],
+            row: 0,
+        };
+        cursor.next_batch(expr)?;
+        Ok(cursor)
+    }
+
+    fn advance(&mut self, expr: &[PhysicalSortExpression]) -> Result<()> {
+        self.row += 1;
+        match &self.batch {
+            Some(batch) if self.row < batch.num_rows() => Ok(()),
+            _ => self.next_batch(expr),
+        }
+    }
+
+    fn next_batch(&mut self, expr: &[PhysicalSortExpression]) -> Result<()> {
+        self.batch = None;
+        self.row = 0;
+        for batch in self.batches.by_ref() {
+            let batch = batch?;
+            if batch.num_rows() > 0 {
+                self.keys = expr
+                    .iter()
+                    .map(|e| e.expr.evaluate(&batch))
+                    .collect::<Result<Vec<_>>>()?;
+                self.batch = Some(batch);
+                return Ok(());
+            }
+        }
+        Ok(())
+    }
+
+    /// The values of the sort expressions for the current row.
+    fn key(&self) -> Result<Vec<ScalarValue>> {
+        self.keys
+            .iter()
+            .map(|k| ScalarValue::try_from_array(k, self.row))
+            .collect()
+    }
+}
+
+/// The current row of a run, as kept by the heap of a merge. Rows are ordered by their
+/// sort key, then by their run, so that ties go to the earlier run, which holds the
+/// earlier input rows.
+struct MergeRow {
+    key: Vec<ScalarValue>,
+    run: usize,
+    expr: Arc<[PhysicalSortExpression]>,
+}
+
+impl Ord for MergeRow {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_keys(&self.key, &other.key, &self.expr).then(self.run.cmp(&other.run))
+    }
+}
+
+impl PartialOrd for MergeRow {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for MergeRow {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for MergeRow {}
+
+/// Merges sorted runs by repeatedly taking the smallest row at the front of any of
+/// them, which a heap of the current row of each run finds.
+struct MergeStream {
+    runs: Vec<RunCursor>,
+    /// The current row of every run that isn't exhausted, smallest first.
+    heap: BinaryHeap<Reverse<MergeRow>>,
+    expr: Arc<[PhysicalSortExpression]>,
+    schema: Arc<Schema>,
+}
+
+impl Iterator for MergeStream {
+    type Item = Result<RecordBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.merge_batch() {
+            Ok(batch) => batch.map(Ok),
+            Err(e) => {
+                self.runs.clear();
+                self.heap.clear();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl MergeStream {
+    fn try_new(
+        runs: Vec<BatchIterator>,
+        expr: Vec<PhysicalSortExpression>,
+        schema: Arc<Schema>,
+    ) -> Result<MergeStream> {
+        let runs = runs
+            .into_iter()
+            .map(|run| RunCursor::try_new(run, &expr))
+            .collect::<Result<Vec<_>>>()?;
+        let mut stream = MergeStream {
+            runs,
+            heap: BinaryHeap::new(),
+            expr: expr.into(),
+            schema,
+        };
+        for run in 0..stream.runs.len() {
+            stream.push(run)?;
+        }
+        Ok(stream)
+    }
+
+    /// Adds the current row of the run `run` to the heap, unless the run is exhausted.
+    fn push(&mut self, run: usize) -> Result<()> {
+        let cursor = &self.runs[run];
+        if cursor.batch.is_some() {
+            self.heap.push(Reverse(MergeRow {
+                key: cursor.key()?,
+                run,
+                expr: Arc::clone(&self.expr),
+            }));
+        }
+        Ok(())
+    }
+
+    /// Merges up to `BATCH_SIZE` rows, or returns `None` once every run is exhausted.
+    fn merge_batch(&mut self) -> Result<Option<RecordBatch>> {
+        // Consecutive rows of the same batch are taken as one slice: the index of the
+        // run, its batch, the first row and the number of rows.
+        let mut slices: Vec<(usize, RecordBatch, usize, usize)> = vec![];
+        let mut rows = 0;
+        while rows < BATCH_SIZE {
+            let i = match self.heap.pop() {
+                Some(Reverse(smallest)) => smallest.run,
+                None => break,
+            };
+            let run = &mut self.runs[i];
+            let row = run.row;
+            match slices.last_mut() {
+                Some((last, _, start, len)) if *last == i && *start + *len == row => *len += 1,
+                _ => slices.push((i, run.batch.clone().unwrap(), row, 1)),
+            }
+            run.advance(&self.expr)?;
+            self.push(i)?;
+            rows += 1;
+        }
+        if slices.is_empty() {
+            return Ok(None);
+        }
+
+        let columns = (0..self.schema.fields().len())
+            .map(|c| {
+                let arrays: Vec<ArrayRef> = slices
+                    .iter()
+                    .map(|(_, batch, start, len)| batch.column(c).slice(*start, *len))
+                    .collect();
+                concat(&arrays.iter().map(|a| a.as_ref()).collect::<Vec<_>>())
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(Some(RecordBatch::try_new(
+            Arc::clone(&self.schema),
+            columns,
+        )?))
+    }
+}
+
+/// The columns of `schema` that rows sorted on `expr` are in ascending order of.
+pub(super) fn sort_ordering(schema: &Schema, expr: &[PhysicalSortExpression]) -> Vec<String> {
+    expr.iter()
+        .map_while(|e| match e.expr.column_index() {
+            Some(index) if !e.options.descending => Some(schema.field(index).name().clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Compares the sort keys of two rows.
+pub(super) fn compare_keys(
+    l: &[ScalarValue],
+    r: &[ScalarValue],
+    expr: &[PhysicalSortExpression],
+) -> Ordering {
+    for ((l, r), e) in l.iter().zip(r).zip(expr) {
+        let ordering = match (l.is_null(), r.is_null()) {
+            (true, true) => Ordering::Equal,
+            (true, false) if e.options.nulls_first => Ordering::Less,
+            (true, false) => Ordering::Greater,
+            (false, true) if e.options.nulls_first => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) if e.options.descending => r.partial_cmp(l).unwrap_or(Ordering::Equal),
+            (false, false) => l.partial_cmp(r).unwrap_or(Ordering::Equal),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data_source::{CsvReadOptions, DataSource};
+    use crate::core::data_type::ArrowType;
+    use crate::core::execution_context::ExecutionContext;
+    use crate::core::helper::*;
+    use crate::physical_plan::collect;
+    use crate::physical_plan::expression::ColumnExpression;
+    use crate::physical_plan::scan::ScanExec;
+    use crate::test_util::{rows, write_csv, CountingSource};
+    use arrow::array::Int64Array;
+
+    #[test]
+    fn sort_in_memory_and_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let lines: Vec<String> = (0..3000)
+            .map(|i| match i % 7 {
+                0 => format!(",{}", i),
+                _ => format!("{},{}", (i * 37) % 101, i),
+            })
+            .collect();
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        let ctx = ExecutionContext::new();
+        ctx.register_csv(
+            "t",
+            &write_csv(&dir, "t.csv", &lines),
+            CsvReadOptions::new()
+                .schema(schema(vec![
+                    ("a", ArrowType::Int64Type, true),
+                    ("b", ArrowType::Int64Type, false),
+                ]))
+                .batch_size(100),
+        )
+        .unwrap();
+        let df = ctx
+            .table("t")
+            .unwrap()
+            .sort(vec![desc(col("a")).nulls_first(), asc(col("b"))]);
+
+        let in_memory = df.collect().unwrap();
+        assert_eq!(in_memory.len(), 3);
+        let sorted = rows(&in_memory);
+        assert_eq!(sorted[..3], [",0", ",7", ",14"]);
+        assert_eq!(sorted[sorted.len() - 2..], ["0,2727", "0,2929"]);
+
+        // Every few batches are spilled as a sorted run, and the runs are merged.
+        let plan = ctx.create_physical_plan(df.plan()).unwrap();
+        let sort = |memory_limit| {
+            SortExec::new(
+                Arc::clone(&plan.children()[0]),
+                vec![
+                    PhysicalSortExpression::new(
+                        Arc::new(ColumnExpression::new(0)),
+                        SortOptions {
+                            descending: true,
+                            nulls_first: true,
+                        },
+                    ),
+                    PhysicalSortExpression::new(
+                        Arc::new(ColumnExpression::new(1)),
+                        SortOptions::default(),
+                    ),
+                ],
+                memory_limit,
+            )
+        };
+        let spilling = Arc::new(sort(4000));
+        let merged = collect(Arc::clone(&spilling) as Arc<dyn PhysicalPlan>).unwrap();
+        assert!(spilling.spills() > 1, "spilled {} runs", spilling.spills());
+        assert!(merged.iter().all(|b| b.num_rows() <= BATCH_SIZE));
+        assert_eq!(rows(&merged), sorted);
+    }
+
+    #[test]
+    fn reports_its_ordering() {
+        let source = Arc::new(CountingSource::new(3));
+        let scan = Arc::new(ScanExec::new(
+            "counting".to_owned(),
+            source.schema(),
+            source,
+            vec![],
+            vec![],
+        ));
+        let by = |index, descending| {
+            PhysicalSortExpression::new(
+                Arc::new(ColumnExpression::new(index)),
+                SortOptions {
+                    descending,
+                    nulls_first: false,
+                },
+            )
+        };
+        let sort = SortExec::new(scan.clone(), vec![by(0, false)], 0);
+        assert_eq!(sort.output_ordering(), ["id"]);
+        assert_eq!(
+            PhysicalPlan::to_string(&sort),
+            "SortExec: #0 ASC NULLS LAST"
+        );
+        let sort = SortExec::new(scan, vec![by(0, true)], 0);
+        assert!(sort.output_ordering().is_empty());
+
+        // Nothing is buffered beyond the limit, so every batch becomes its own run.
+        let batches = collect(Arc::new(sort) as Arc<dyn PhysicalPlan>).unwrap();
+        let ids = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(ids.values(), &[2, 1, 0]);
+    }
+}
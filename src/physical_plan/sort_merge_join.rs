@@ -0,0 +1,587 @@
+use arrow::array::{Array, ArrayRef};
+use arrow::compute::concat;
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
+use std::cmp::Ordering;
+use std::ops::Range;
+use std::sync::Arc;
+
+use super::hash_join::{join_rows, joined_batch, row_key, take_rows, JoinFilter};
+use super::{BatchIterator, PhysicalPlan};
+use crate::core::error::{QrustError, Result};
+use crate::core::scalar_value::ScalarValue;
+use crate::logical_plan::join::JoinType;
+
+/// The number of rows the join collects before it produces a batch.
+const BATCH_SIZE: usize = 1024;
+
+/// Joins two inputs that are both sorted on their keys by walking through them side by
+/// side. Only the rows that share the key at the front of both inputs are held in
+/// memory, so the memory used doesn't depend on the size of either input.
+pub struct SortMergeJoinExec {
+    left: Arc<dyn PhysicalPlan>,
+    right: Arc<dyn PhysicalPlan>,
+    join_type: JoinType,
+    /// Pairs of key column indices, the first into the left input and the second into
+    /// the right input. Both inputs are sorted on their keys in this order.
+    on: Vec<(usize, usize)>,
+    /// Pairs of rows only match if it is true.
+    filter: Option<JoinFilter>,
+    schema: Arc<Schema>,
+}
+
+impl PhysicalPlan for SortMergeJoinExec {
+    fn schema(&self) -> Arc<Schema> {
+        Arc::clone(&self.schema)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalPlan>> {
+        vec![Arc::clone(&self.left), Arc::clone(&self.right)]
+    }
+
+    fn execute(&self) -> Result<BatchIterator> {
+        let left_schema = self.left.schema();
+        let right_schema = self.right.schema();
+        let left_keys = self.on.iter().map(|(l, _)| *l).collect();
+        let right_keys = self.on.iter().map(|(_, r)| *r).collect();
+        Ok(Box::new(MergeStream {
+            left: SortedInput::new("left", self.left.execute()?, left_schema, left_keys),
+            right: SortedInput::new("right", self.right.execute()?, right_schema, right_keys),
+            join_type: self.join_type,
+            filter: self.filter.clone(),
+            schema: Arc::clone(&self.schema),
+            left_rows: vec![],
+            right_rows: vec![],
+            done: false,
+        }))
+    }
+
+    fn to_string(&self) -> String {
+        let mut s = format!(
+            "SortMergeJoinExec: joinType={}, on=[{}]",
+            self.join_type,
+            self.on
+                .iter()
+                .map(|(l, r)| format!("#{}=#{}", l, r))
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+        if let Some(filter) = &self.filter {
+            s.push_str(&format!(", filter={}", filter));
+        }
+        s
+    }
+
+    /// Rows come out in the order of the keys of the side whose rows are all kept.
+    fn output_ordering(&self) -> Vec<String> {
+        let offset = match self.join_type {
+            JoinType::Inner | JoinType::Left | JoinType::Semi | JoinType::Anti => 0,
+            JoinType::Right => self.left.schema().fields().len(),
+            JoinType::Full => return vec![],
+        };
+        self.on
+            .iter()
+            .map(|(l, r)| {
+                let index = if offset == 0 { *l } else { offset + r };
+                self.schema.field(index).name().clone()
+            })
+            .collect()
+    }
+}
+
+impl SortMergeJoinExec {
+    pub fn new(
+        left: Arc<dyn PhysicalPlan>,
+        right: Arc<dyn PhysicalPlan>,
+        join_type: JoinType,
+        on: Vec<(usize, usize)>,
+        filter: Option<JoinFilter>,
+        schema: Arc<Schema>,
+    ) -> SortMergeJoinExec {
+        SortMergeJoinExec {
+            left,
+            right,
+            join_type,
+            on,
+            filter,
+            schema,
+        }
+    }
+}
+
+/// One input of the join, read a batch at a time. Batches are kept for as long as
+/// rows may still be taken from them.
+struct SortedInput {
+    side: &'static str,
+    input: BatchIterator,
+    schema: Arc<Schema>,
+    keys: Vec<usize>,
+    batches: Vec<RecordBatch>,
+    /// The number of rows in `batches`.
+    rows: usize,
+    /// The next row to join, as an index into the rows of `batches`.
+    cursor: usize,
+    exhausted: bool,
+    /// The largest key seen so far, to check that the input is sorted.
+    last_key: Option<Vec<ScalarValue>>,
+}
+
+impl SortedInput {
+    fn new(
+        side: &'static str,
+        input: BatchIterator,
+        schema: Arc<Schema>,
+        keys: Vec<usize>,
+    ) -> SortedInput {
+        SortedInput {
+            side,
+            input,
+            schema,
+            keys,
+            batches: vec![],
+            rows: 0,
+            cursor: 0,
+            exhausted: false,
+            last_key: None,
+        }
+    }
+
+    /// Reads batches until `row` has been read. Returns false if the input ends first.
+    fn has_row(&mut self, row: usize) -> Result<bool> {
+        while row >= self.rows {
+            if self.exhausted {
+                return Ok(false);
+            }
+            match self.input.next() {
+                Some(batch) => {
+                    let batch = batch?;
+                    self.rows += batch.num_rows();
+                    self.batches.push(batch);
+                }
+                None => self.exhausted = true,
+            }
+        }
+        Ok(true)
+    }
+
+    /// Returns the key of a row that has been read, or `None` if it has a null.
+    fn key(&mut self, mut row: usize) -> Result<Option<Vec<ScalarValue>>> {
+        let mut batches = self.batches.iter();
+        let batch = loop {
+            let batch = batches.next().unwrap();
+            if row < batch.num_rows() {
+                break batch;
+            }
+            row -= batch.num_rows();
+        };
+        let key = row_key(batch, &self.keys, row)?;
+        if let Some(key) = &key {
+            if self.last_key.as_ref().is_some_and(|last| last > key) {
+                return Err(QrustError::Execution(format!(
+                    "The {} input of SortMergeJoinExec is not sorted on its keys",
+                    self.side
+                )));
+            }
+            self.last_key = Some(key.clone());
+        }
+        Ok(key)
+    }
+
+    /// Returns the end of the run of rows from the cursor on that have `key`.
+    fn group_end(&mut self, key: &[ScalarValue]) -> Result<usize> {
+        let mut end = self.cursor + 1;
+        while self.has_row(end)? && self.key(end)?.as_deref() == Some(key) {
+            end += 1;
+        }
+        Ok(end)
+    }
+
+    /// Whether the cursor has moved past the first batch, which can then be released.
+    fn past_first_batch(&self) -> bool {
+        self.batches
+            .first()
+            .is_some_and(|batch| self.cursor >= batch.num_rows())
+    }
+
+    /// Drops the batches before the cursor. Rows taken before can't be referred to
+    /// anymore.
+    fn release(&mut self) {
+        while self.past_first_batch() {
+            let batch = self.batches.remove(0);
+            self.cursor -= batch.num_rows();
+            self.rows -= batch.num_rows();
+        }
+    }
+
+    /// Takes the given rows of the batches read, where `None` stands for a row of nulls.
+    fn take(&self, rows: &[Option<u32>]) -> Result<Vec<ArrayRef>> {
+        let batch = match self.batches.as_slice() {
+            _ if rows.iter().all(Option::is_none) => {
+                RecordBatch::new_empty(Arc::clone(&self.schema))
+            }
+            [] => RecordBatch::new_empty(Arc::clone(&self.schema)),
+            [batch] => batch.clone(),
+            batches => {
+                let columns = (0..self.schema.fields().len())
+                    .map(|i| {
+                        let arrays: Vec<&dyn Array> =
+                            batches.iter().map(|b| b.column(i).as_ref()).collect();
+                        concat(&arrays)
+                    })
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                RecordBatch::try_new(Arc::clone(&self.schema), columns)?
+            }
+        };
+        take_rows(&batch, rows)
+    }
+}
+
+/// Merges the two inputs, collecting the rows of the output as pairs of rows of the
+/// inputs until there are enough of them for a batch.
+struct MergeStream {
+    left: SortedInput,
+    right: SortedInput,
+    join_type: JoinType,
+    filter: Option<JoinFilter>,
+    schema: Arc<Schema>,
+    left_rows: Vec<Option<u32>>,
+    right_rows: Vec<Option<u32>>,
+    done: bool,
+}
+
+impl Iterator for MergeStream {
+    type Item = Result<RecordBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.next_batch() {
+            Ok(Some(batch)) => Some(Ok(batch)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl MergeStream {
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        loop {
+            if self.left_rows.is_empty() {
+                self.left.release();
+                self.right.release();
+            } else if self.left_rows.len() >= BATCH_SIZE
+                || self.left.past_first_batch()
+                || self.right.past_first_batch()
+            {
+                // Producing the output lets the batches before the cursors go.
+                break;
+            }
+            if !self.step()? {
+                break;
+            }
+        }
+        if self.left_rows.is_empty() {
+            return Ok(None);
+        }
+        let left_rows = std::mem::take(&mut self.left_rows);
+        let right_rows = std::mem::take(&mut self.right_rows);
+        Ok(Some(joined_batch(
+            &self.schema,
+            self.left.take(&left_rows)?,
+            self.right.take(&right_rows)?,
+        )?))
+    }
+
+    /// Joins the rows at the front of the inputs. Returns false once both have ended.
+    fn step(&mut self) -> Result<bool> {
+        let has_left = self.left.has_row(self.left.cursor)?;
+        let has_right = self.right.has_row(self.right.cursor)?;
+        // Keys are read even when the other input has ended, to check the order.
+        let left_key = if has_left {
+            Some(self.left.key(self.left.cursor)?)
+        } else {
+            None
+        };
+        let right_key = if has_right {
+            Some(self.right.key(self.right.cursor)?)
+        } else {
+            None
+        };
+        let ordering = match (left_key, right_key) {
+            (None, None) => return Ok(false),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            // Rows with a null key never match.
+            (Some(None), _) => Ordering::Less,
+            (_, Some(None)) => Ordering::Greater,
+            (Some(Some(l)), Some(Some(r))) => l.partial_cmp(&r).unwrap_or(Ordering::Less),
+        };
+
+        match ordering {
+            Ordering::Less => {
+                let row = self.left.cursor as u32;
+                self.left.cursor += 1;
+                if matches!(
+                    self.join_type,
+                    JoinType::Left | JoinType::Full | JoinType::Anti
+                ) {
+                    self.push(Some(row), None);
+                }
+            }
+            Ordering::Greater => {
+                let row = self.right.cursor as u32;
+                self.right.cursor += 1;
+                if matches!(self.join_type, JoinType::Right | JoinType::Full) {
+                    self.push(None, Some(row));
+                }
+            }
+            Ordering::Equal => {
+                let key = self.left.key(self.left.cursor)?.unwrap();
+                let left_end = self.left.group_end(&key)?;
+                let right_end = self.right.group_end(&key)?;
+                self.join_group(self.left.cursor..left_end, self.right.cursor..right_end)?;
+                self.left.cursor = left_end;
+                self.right.cursor = right_end;
+            }
+        }
+        Ok(true)
+    }
+
+    /// Joins every row of one input that has a key with every row of the other input
+    /// that has the same key.
+    fn join_group(&mut self, left: Range<usize>, right: Range<usize>) -> Result<()> {
+        let (mut left_rows, mut right_rows): (Vec<u32>, Vec<u32>) = left
+            .clone()
+            .flat_map(|l| right.clone().map(move |r| (l as u32, r as u32)))
+            .unzip();
+        if let Some(filter) = &self.filter {
+            filter.filter_pairs(&mut left_rows, &mut right_rows, |left_rows, right_rows| {
+                joined_batch(
+                    filter.schema(),
+                    self.left.take(left_rows)?,
+                    self.right.take(right_rows)?,
+                )
+            })?;
+        }
+
+        let left_preserved = matches!(self.join_type, JoinType::Left | JoinType::Full);
+        let (left_out, right_out) = join_rows(
+            self.join_type,
+            left.start as u32..left.end as u32,
+            &left_rows,
+            &right_rows,
+            left_preserved,
+        );
+        self.left_rows.extend(left_out);
+        self.right_rows.extend(right_out);
+        if matches!(self.join_type, JoinType::Right | JoinType::Full) {
+            let mut right_matched = vec![false; right.len()];
+            for &r in &right_rows {
+                right_matched[r as usize - right.start] = true;
+            }
+            for r in right.clone() {
+                if !right_matched[r - right.start] {
+                    self.push(None, Some(r as u32));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, left: Option<u32>, right: Option<u32>) {
+        self.left_rows.push(left);
+        self.right_rows.push(right);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data_source::{CsvReadOptions, DataSource};
+    use crate::core::data_type::ArrowType;
+    use crate::core::execution_context::ExecutionContext;
+    use crate::core::helper::*;
+    use crate::logical_plan::expression::LogicalExpression;
+    use crate::logical_plan::join::Join;
+    use crate::logical_plan::scan::Scan;
+    use crate::logical_plan::subquery_alias::SubqueryAlias;
+    use crate::logical_plan::LogicalPlan;
+    use crate::physical_plan::collect;
+    use crate::physical_plan::planner::PhysicalPlanner;
+    use crate::test_util::{csv_context, rows, CountingSource};
+
+    fn context(dir: &tempfile::TempDir, left: &[&str], right: &[&str]) -> ExecutionContext {
+        let options = |columns: Vec<(&str, ArrowType, bool)>, key: &str| {
+            CsvReadOptions::new()
+                .schema(schema(columns))
+                .sorted_by(&[key])
+                .batch_size(2)
+        };
+        csv_context(
+            dir,
+            vec![
+                (
+                    "l",
+                    options(
+                        vec![
+                            ("id", ArrowType::Int64Type, true),
+                            ("name", ArrowType::StringType, false),
+                        ],
+                        "id",
+                    ),
+                    left,
+                ),
+                (
+                    "r",
+                    options(
+                        vec![
+                            ("rid", ArrowType::Int64Type, true),
+                            ("value", ArrowType::Int64Type, false),
+                        ],
+                        "rid",
+                    ),
+                    right,
+                ),
+            ],
+        )
+    }
+
+    fn join(
+        ctx: &ExecutionContext,
+        join_type: JoinType,
+        filter: Option<Arc<dyn LogicalExpression>>,
+    ) -> Result<Vec<String>> {
+        let join = Arc::new(Join::try_new(
+            ctx.table("l")?.plan(),
+            ctx.table("r")?.plan(),
+            join_type,
+            vec![("id".to_owned(), "rid".to_owned())],
+            filter,
+        )?) as Arc<dyn LogicalPlan>;
+        let plan = PhysicalPlanner::new().create_physical_plan(&join)?;
+        assert!(plan.to_string().starts_with("SortMergeJoinExec"));
+        Ok(rows(&collect(plan)?))
+    }
+
+    #[test]
+    fn join_types() {
+        let dir = tempfile::tempdir().unwrap();
+        // Keys repeat on both sides, and groups of equal keys span batches.
+        let ctx = context(
+            &dir,
+            &["1,a", "2,b", "2,c", "2,d", "4,e", ",f"],
+            &["0,0", "2,20", "2,21", "3,30", "4,40", "4,41", ",99"],
+        );
+        let inner = [
+            "2,b,2,20", "2,b,2,21", "2,c,2,20", "2,c,2,21", "2,d,2,20", "2,d,2,21", "4,e,4,40",
+            "4,e,4,41",
+        ];
+
+        assert_eq!(join(&ctx, JoinType::Inner, None).unwrap(), inner);
+        let mut left = vec!["1,a,,"];
+        left.extend(inner);
+        left.push(",f,,");
+        assert_eq!(join(&ctx, JoinType::Left, None).unwrap(), left);
+        let mut right = vec![",,0,0"];
+        right.extend(&inner[..6]);
+        right.extend([",,3,30", "4,e,4,40", "4,e,4,41", ",,,99"]);
+        assert_eq!(join(&ctx, JoinType::Right, None).unwrap(), right);
+        let full = [
+            ",,0,0", "1,a,,", "2,b,2,20", "2,b,2,21", "2,c,2,20", "2,c,2,21", "2,d,2,20",
+            "2,d,2,21", ",,3,30", "4,e,4,40", "4,e,4,41", ",f,,", ",,,99",
+        ];
+        assert_eq!(join(&ctx, JoinType::Full, None).unwrap(), full);
+        assert_eq!(
+            join(&ctx, JoinType::Semi, None).unwrap(),
+            ["2,b", "2,c", "2,d", "4,e"]
+        );
+        assert_eq!(join(&ctx, JoinType::Anti, None).unwrap(), ["1,a", ",f"]);
+    }
+
+    #[test]
+    fn filters_pairs_of_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = context(&dir, &["1,a", "2,b", "2,c"], &["2,20", "2,21", "3,30"]);
+        let filter = || Some(gt(col("value"), lit(&20)) as Arc<dyn LogicalExpression>);
+        assert_eq!(
+            join(&ctx, JoinType::Full, filter()).unwrap(),
+            ["1,a,,", "2,b,2,21", "2,c,2,21", ",,2,20", ",,3,30"]
+        );
+        assert_eq!(
+            join(&ctx, JoinType::Anti, Some(gt(col("value"), lit(&21)) as _)).unwrap(),
+            ["1,a", "2,b", "2,c"]
+        );
+    }
+
+    #[test]
+    fn unsorted_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = context(&dir, &["1,a", "3,b", "2,c"], &["1,10", "2,20"]);
+        assert_eq!(
+            join(&ctx, JoinType::Inner, None).unwrap_err().to_string(),
+            "Execution error: The left input of SortMergeJoinExec is not sorted on its keys"
+        );
+    }
+
+    /// A source of sorted ids that declares its order.
+    struct SortedSource(CountingSource);
+
+    impl DataSource for SortedSource {
+        fn schema(&self) -> Arc<Schema> {
+            self.0.schema()
+        }
+
+        fn scan(
+            &self,
+            projection: Vec<String>,
+            filters: Vec<Arc<dyn LogicalExpression>>,
+        ) -> Result<BatchIterator> {
+            self.0.scan(projection, filters)
+        }
+
+        fn output_ordering(&self) -> Vec<String> {
+            vec!["id".to_owned()]
+        }
+    }
+
+    #[test]
+    fn streams_both_inputs() {
+        let left = Arc::new(SortedSource(CountingSource::new(10_000)));
+        let right = Arc::new(SortedSource(CountingSource::new(10_000)));
+        // The sides are aliased, as both have an id column.
+        let scan = |name: &str, source: &Arc<SortedSource>| {
+            let scan = Scan::new(
+                name.to_owned(),
+                source.schema(),
+                Arc::clone(source) as Arc<dyn DataSource>,
+                vec![],
+            );
+            Arc::new(SubqueryAlias::new(Arc::new(scan), name.to_owned())) as Arc<dyn LogicalPlan>
+        };
+        let join = Arc::new(
+            Join::try_new(
+                scan("left", &left),
+                scan("right", &right),
+                JoinType::Inner,
+                vec![("id".to_owned(), "id".to_owned())],
+                None,
+            )
+            .unwrap(),
+        ) as Arc<dyn LogicalPlan>;
+        let plan = PhysicalPlanner::new().create_physical_plan(&join).unwrap();
+        assert_eq!(plan.output_ordering(), vec!["left.id"]);
+
+        let mut batches = plan.execute().unwrap();
+        let first = batches.next().unwrap().unwrap().num_rows();
+        assert!(left.0.pulled() <= 2 && right.0.pulled() <= 2);
+        let rest: usize = batches.map(|b| b.unwrap().num_rows()).sum();
+        assert_eq!(first + rest, 10_000);
+    }
+}
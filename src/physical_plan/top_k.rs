@@ -0,0 +1,218 @@
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+
+use super::sort::{compare_keys, sort_ordering, PhysicalSortExpression};
+use super::{BatchIterator, PhysicalPlan};
+use crate::core::error::Result;
+use crate::core::scalar_value::ScalarValue;
+
+/// Produces the first `fetch` rows its input would have once sorted, without sorting all
+/// of it. The best rows seen so far are kept in a heap whose top is the worst of them, so
+/// memory use is bounded by `fetch` rather than by the size of the input.
+pub struct TopKExec {
+    input: Arc<dyn PhysicalPlan>,
+    expr: Arc<[PhysicalSortExpression]>,
+    fetch: usize,
+}
+
+impl PhysicalPlan for TopKExec {
+    fn schema(&self) -> Arc<Schema> {
+        self.input.schema()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalPlan>> {
+        vec![Arc::clone(&self.input)]
+    }
+
+    fn execute(&self) -> Result<BatchIterator> {
+        if self.fetch == 0 {
+            return Ok(Box::new(std::iter::empty()));
+        }
+        let mut heap = BinaryHeap::new();
+        let mut seq = 0;
+        for batch in self.input.execute()? {
+            let batch = batch?;
+            let keys = self
+                .expr
+                .iter()
+                .map(|e| e.evaluate(&batch))
+                .collect::<Result<Vec<_>>>()?;
+            for row in 0..batch.num_rows() {
+                let key = keys
+                    .iter()
+                    .map(|k| ScalarValue::try_from_array(k, row))
+                    .collect::<Result<Vec<_>>>()?;
+                // Rows tied with the worst one kept lose to it, as they come later.
+                let full = heap.len() == self.fetch;
+                if full
+                    && heap.peek().is_some_and(|worst: &TopKRow| {
+                        compare_keys(&key, &worst.key, &self.expr) != Ordering::Less
+                    })
+                {
+                    continue;
+                }
+                let values = batch
+                    .columns()
+                    .iter()
+                    .map(|c| ScalarValue::try_from_array(c, row))
+                    .collect::<Result<Vec<_>>>()?;
+                heap.push(TopKRow {
+                    key,
+                    seq,
+                    values,
+                    expr: Arc::clone(&self.expr),
+                });
+                seq += 1;
+                if full {
+                    heap.pop();
+                }
+            }
+        }
+        if heap.is_empty() {
+            return Ok(Box::new(std::iter::empty()));
+        }
+
+        let rows = heap.into_sorted_vec();
+        let schema = self.schema();
+        let columns = schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let values = rows.iter().map(|r| r.values[i].clone()).collect::<Vec<_>>();
+                ScalarValue::iter_to_array(&values, field.data_type())
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let batch = RecordBatch::try_new(schema, columns)?;
+        Ok(Box::new(std::iter::once(Ok(batch))))
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "TopKExec: fetch={}, {}",
+            self.fetch,
+            self.expr
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+
+    fn output_ordering(&self) -> Vec<String> {
+        sort_ordering(&self.schema(), &self.expr)
+    }
+}
+
+impl TopKExec {
+    pub fn new(
+        input: Arc<dyn PhysicalPlan>,
+        expr: Vec<PhysicalSortExpression>,
+        fetch: usize,
+    ) -> TopKExec {
+        TopKExec {
+            input,
+            expr: expr.into(),
+            fetch,
+        }
+    }
+}
+
+/// A row kept by the heap. Rows are ordered by their sort key, then by the order they
+/// were read in, which keeps the output stable.
+struct TopKRow {
+    key: Vec<ScalarValue>,
+    seq: usize,
+    values: Vec<ScalarValue>,
+    expr: Arc<[PhysicalSortExpression]>,
+}
+
+impl Ord for TopKRow {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_keys(&self.key, &other.key, &self.expr).then(self.seq.cmp(&other.seq))
+    }
+}
+
+impl PartialOrd for TopKRow {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for TopKRow {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for TopKRow {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data_source::CsvReadOptions;
+    use crate::core::data_type::ArrowType;
+    use crate::core::execution_context::ExecutionContext;
+    use crate::core::helper::*;
+    use crate::physical_plan::collect;
+    use crate::physical_plan::expression::ColumnExpression;
+    use crate::physical_plan::sort::SortExec;
+    use crate::test_util::{rows, write_csv};
+    use arrow::compute::SortOptions;
+
+    #[test]
+    fn matches_a_full_sort() {
+        let dir = tempfile::tempdir().unwrap();
+        let lines = (0..500)
+            .map(|i| {
+                if i % 7 == 0 {
+                    format!(",{}", i)
+                } else {
+                    format!("{},{}", (i * 37) % 11, i)
+                }
+            })
+            .collect::<Vec<String>>();
+        let lines = lines.iter().map(|l| l.as_str()).collect::<Vec<&str>>();
+        let path = write_csv(&dir, "t.csv", &lines);
+        let ctx = ExecutionContext::new();
+        let options = CsvReadOptions::new()
+            .schema(schema(vec![
+                ("a", ArrowType::Int64Type, true),
+                ("b", ArrowType::Int64Type, false),
+            ]))
+            .batch_size(64);
+        ctx.register_csv("t", &path, options).unwrap();
+        let scan = || {
+            ctx.create_physical_plan(ctx.table("t").unwrap().plan())
+                .unwrap()
+        };
+
+        for (descending, nulls_first) in [(false, false), (true, false), (false, true)] {
+            // The second key makes the order total, so ties cannot differ.
+            let expr = vec![
+                PhysicalSortExpression::new(
+                    Arc::new(ColumnExpression::new(0)),
+                    SortOptions {
+                        descending,
+                        nulls_first,
+                    },
+                ),
+                PhysicalSortExpression::new(
+                    Arc::new(ColumnExpression::new(1)),
+                    SortOptions::default(),
+                ),
+            ];
+            let sorted =
+                rows(&collect(Arc::new(SortExec::new(scan(), expr.clone(), 1 << 20))).unwrap());
+            // The heap only grows with the rows it keeps, so any limit fits.
+            for fetch in [0, 1, 10, 100, 1000, usize::MAX] {
+                let top_k = TopKExec::new(scan(), expr.clone(), fetch);
+                let expected = &sorted[..fetch.min(sorted.len())];
+                assert_eq!(rows(&collect(Arc::new(top_k)).unwrap()), expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,462 @@
+use std::fmt;
+
+use super::parser::RESERVED;
+
+/// A single parsed SQL statement.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    Query(Box<Query>),
+    /// `CREATE EXTERNAL TABLE name (columns) STORED AS file_type [WITH HEADER ROW]
+    /// [DELIMITER 'c'] LOCATION 'path'`
+    CreateExternalTable {
+        name: ObjectName,
+        columns: Vec<ColumnDef>,
+        file_type: String,
+        has_header: bool,
+        delimiter: Option<char>,
+        location: String,
+        if_not_exists: bool,
+    },
+    /// `DROP TABLE [IF EXISTS] name`
+    DropTable {
+        name: ObjectName,
+        if_exists: bool,
+    },
+    /// `EXPLAIN [ANALYZE] query`
+    Explain {
+        analyze: bool,
+        query: Box<Query>,
+    },
+    /// `SHOW TABLES`
+    ShowTables,
+    /// `DESCRIBE name`
+    Describe {
+        name: ObjectName,
+    },
+}
+
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Statement::Query(query) => write!(f, "{}", query),
+            Statement::CreateExternalTable {
+                name,
+                columns,
+                file_type,
+                has_header,
+                delimiter,
+                location,
+                if_not_exists,
+            } => {
+                write!(f, "CREATE EXTERNAL TABLE ")?;
+                if *if_not_exists {
+                    write!(f, "IF NOT EXISTS ")?;
+                }
+                write!(
+                    f,
+                    "{} ({}) STORED AS {}",
+                    name,
+                    display_list(columns),
+                    file_type
+                )?;
+                if *has_header {
+                    write!(f, " WITH HEADER ROW")?;
+                }
+                if let Some(delimiter) = delimiter {
+                    write!(f, " DELIMITER '{}'", delimiter)?;
+                }
+                write!(f, " LOCATION '{}'", location.replace('\'', "''"))
+            }
+            Statement::DropTable { name, if_exists } => {
+                write!(f, "DROP TABLE ")?;
+                if *if_exists {
+                    write!(f, "IF EXISTS ")?;
+                }
+                write!(f, "{}", name)
+            }
+            Statement::Explain { analyze, query } => {
+                write!(f, "EXPLAIN ")?;
+                if *analyze {
+                    write!(f, "ANALYZE ")?;
+                }
+                write!(f, "{}", query)
+            }
+            Statement::ShowTables => write!(f, "SHOW TABLES"),
+            Statement::Describe { name } => write!(f, "DESCRIBE {}", name),
+        }
+    }
+}
+
+/// A column in a `CREATE TABLE` statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnDef {
+    pub name: String,
+    pub data_type: DataType,
+    pub nullable: bool,
+}
+
+impl fmt::Display for ColumnDef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", Ident(&self.name), self.data_type)?;
+        if !self.nullable {
+            write!(f, " NOT NULL")?;
+        }
+        Ok(())
+    }
+}
+
+/// The SQL column types Qrust understands.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DataType {
+    BigInt,
+    Int,
+    Varchar,
+    Text,
+}
+
+impl fmt::Display for DataType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DataType::BigInt => "BIGINT",
+            DataType::Int => "INT",
+            DataType::Varchar => "VARCHAR",
+            DataType::Text => "TEXT",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A `SELECT` together with the common table expressions it can refer to and the
+/// clauses that order and limit its results.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub with: Vec<Cte>,
+    pub body: Select,
+    pub order_by: Vec<OrderByExpr>,
+    pub limit: Option<Expr>,
+    pub offset: Option<Expr>,
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.with.is_empty() {
+            write!(f, "WITH {} ", display_list(&self.with))?;
+        }
+        write!(f, "{}", self.body)?;
+        if !self.order_by.is_empty() {
+            write!(f, " ORDER BY {}", display_list(&self.order_by))?;
+        }
+        if let Some(limit) = &self.limit {
+            write!(f, " LIMIT {}", limit)?;
+        }
+        if let Some(offset) = &self.offset {
+            write!(f, " OFFSET {}", offset)?;
+        }
+        Ok(())
+    }
+}
+
+/// A named query in a `WITH` clause, e.g. `totals AS (SELECT ...)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cte {
+    pub name: String,
+    pub query: Query,
+}
+
+impl fmt::Display for Cte {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} AS ({})", Ident(&self.name), self.query)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Select {
+    pub projection: Vec<SelectItem>,
+    pub from: Option<TableReference>,
+    pub selection: Option<Expr>,
+    pub group_by: Vec<Expr>,
+    pub having: Option<Expr>,
+}
+
+impl fmt::Display for Select {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SELECT {}", display_list(&self.projection))?;
+        if let Some(from) = &self.from {
+            write!(f, " FROM {}", from)?;
+        }
+        if let Some(selection) = &self.selection {
+            write!(f, " WHERE {}", selection)?;
+        }
+        if !self.group_by.is_empty() {
+            write!(f, " GROUP BY {}", display_list(&self.group_by))?;
+        }
+        if let Some(having) = &self.having {
+            write!(f, " HAVING {}", having)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectItem {
+    /// `*`
+    Wildcard,
+    Expr {
+        expr: Expr,
+        alias: Option<String>,
+    },
+}
+
+impl fmt::Display for SelectItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SelectItem::Wildcard => write!(f, "*"),
+            SelectItem::Expr { expr, alias: None } => write!(f, "{}", expr),
+            SelectItem::Expr {
+                expr,
+                alias: Some(alias),
+            } => write!(f, "{} AS {}", expr, Ident(alias)),
+        }
+    }
+}
+
+/// A relation in a `FROM` clause.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TableReference {
+    /// A table or common table expression, e.g. `analytics.events AS e`.
+    Table {
+        name: ObjectName,
+        alias: Option<String>,
+    },
+    /// A subquery, e.g. `(SELECT ...) AS t`. Unlike tables, subqueries must be named.
+    Derived { subquery: Box<Query>, alias: String },
+    /// Two relations joined on a condition, e.g. `users u LEFT JOIN orders o ON u.id = o.uid`.
+    /// A `CROSS JOIN` has no condition.
+    Join {
+        left: Box<TableReference>,
+        right: Box<TableReference>,
+        operator: JoinOperator,
+        on: Option<Expr>,
+    },
+}
+
+impl fmt::Display for TableReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableReference::Table { name, alias } => {
+                write!(f, "{}", name)?;
+                if let Some(alias) = alias {
+                    write!(f, " AS {}", Ident(alias))?;
+                }
+                Ok(())
+            }
+            TableReference::Derived { subquery, alias } => {
+                write!(f, "({}) AS {}", subquery, Ident(alias))
+            }
+            TableReference::Join {
+                left,
+                right,
+                operator,
+                on,
+            } => {
+                write!(f, "{} {} {}", left, operator, right)?;
+                match on {
+                    Some(on) => write!(f, " ON {}", on),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JoinOperator {
+    Inner,
+    Left,
+    Right,
+    Full,
+    Cross,
+}
+
+impl fmt::Display for JoinOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JoinOperator::Inner => write!(f, "JOIN"),
+            JoinOperator::Left => write!(f, "LEFT JOIN"),
+            JoinOperator::Right => write!(f, "RIGHT JOIN"),
+            JoinOperator::Full => write!(f, "FULL JOIN"),
+            JoinOperator::Cross => write!(f, "CROSS JOIN"),
+        }
+    }
+}
+
+/// A possibly qualified name, e.g. `events` or `analytics.events`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectName(pub Vec<String>);
+
+impl fmt::Display for ObjectName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self.0.iter().map(|p| Ident(p).to_string()).collect();
+        write!(f, "{}", parts.join("."))
+    }
+}
+
+/// Displays an identifier, in double quotes unless it can be read back without them.
+struct Ident<'a>(&'a str);
+
+impl fmt::Display for Ident<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut chars = self.0.chars();
+        let is_bare = chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+            && chars.all(|c| c.is_alphanumeric() || c == '_')
+            && !RESERVED.iter().any(|k| self.0.eq_ignore_ascii_case(k));
+        if is_bare {
+            write!(f, "{}", self.0)
+        } else {
+            write!(f, "\"{}\"", self.0.replace('"', "\"\""))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderByExpr {
+    pub expr: Expr,
+    pub asc: bool,
+    /// Whether nulls come first, or `None` if the query doesn't say.
+    pub nulls_first: Option<bool>,
+}
+
+impl fmt::Display for OrderByExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.expr, if self.asc { "ASC" } else { "DESC" })?;
+        match self.nulls_first {
+            Some(true) => write!(f, " NULLS FIRST"),
+            Some(false) => write!(f, " NULLS LAST"),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// A column reference, possibly qualified by a table name.
+    Identifier(ObjectName),
+    Integer(i64),
+    String(String),
+    Boolean(bool),
+    /// A parameter of a prepared statement, including the `$`, e.g. `$1` or `$id`.
+    Placeholder(String),
+    BinaryOp {
+        left: Box<Expr>,
+        op: BinaryOperator,
+        right: Box<Expr>,
+    },
+    Not(Box<Expr>),
+    Negative(Box<Expr>),
+    /// A function call such as `sum(a)`.
+    Function {
+        name: String,
+        args: Vec<Expr>,
+    },
+    /// An expression in parentheses.
+    Nested(Box<Expr>),
+    /// `expr [NOT] IN (subquery)`
+    InSubquery {
+        expr: Box<Expr>,
+        subquery: Box<Query>,
+        negated: bool,
+    },
+    /// `expr [NOT] BETWEEN low AND high`
+    Between {
+        expr: Box<Expr>,
+        negated: bool,
+        low: Box<Expr>,
+        high: Box<Expr>,
+    },
+    /// `EXISTS (subquery)`
+    Exists(Box<Query>),
+    /// A subquery used as a value, e.g. `(SELECT max(a) FROM t)`.
+    Subquery(Box<Query>),
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Identifier(name) => write!(f, "{}", name),
+            Expr::Integer(i) => write!(f, "{}", i),
+            Expr::String(s) => write!(f, "'{}'", s.replace('\'', "''")),
+            Expr::Boolean(b) => write!(f, "{}", if *b { "TRUE" } else { "FALSE" }),
+            Expr::Placeholder(p) => write!(f, "{}", p),
+            Expr::BinaryOp { left, op, right } => write!(f, "{} {} {}", left, op, right),
+            Expr::Not(expr) => write!(f, "NOT {}", expr),
+            Expr::Negative(expr) => write!(f, "-{}", expr),
+            Expr::Function { name, args } => write!(f, "{}({})", name, display_list(args)),
+            Expr::Nested(expr) => write!(f, "({})", expr),
+            Expr::InSubquery {
+                expr,
+                subquery,
+                negated,
+            } => {
+                let not = if *negated { "NOT " } else { "" };
+                write!(f, "{} {}IN ({})", expr, not, subquery)
+            }
+            Expr::Between {
+                expr,
+                negated,
+                low,
+                high,
+            } => {
+                let not = if *negated { "NOT " } else { "" };
+                write!(f, "{} {}BETWEEN {} AND {}", expr, not, low, high)
+            }
+            Expr::Exists(subquery) => write!(f, "EXISTS ({})", subquery),
+            Expr::Subquery(subquery) => write!(f, "({})", subquery),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOperator {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    And,
+    Or,
+    Plus,
+    Minus,
+    Multiply,
+    Divide,
+    Modulo,
+}
+
+impl fmt::Display for BinaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            BinaryOperator::Eq => "=",
+            BinaryOperator::NotEq => "<>",
+            BinaryOperator::Lt => "<",
+            BinaryOperator::LtEq => "<=",
+            BinaryOperator::Gt => ">",
+            BinaryOperator::GtEq => ">=",
+            BinaryOperator::And => "AND",
+            BinaryOperator::Or => "OR",
+            BinaryOperator::Plus => "+",
+            BinaryOperator::Minus => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Modulo => "%",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+fn display_list<T: fmt::Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(|i| i.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
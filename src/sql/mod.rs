@@ -0,0 +1,9 @@
+//! A hand-written SQL front end: a tokenizer and a recursive-descent parser that
+//! produce the syntax tree in `ast`, and an unparser that turns logical plans back
+//! into SQL.
+
+pub mod ast;
+pub mod parser;
+pub mod planner;
+pub mod tokenizer;
+pub mod unparser;
@@ -0,0 +1,1057 @@
+use super::ast::*;
+use super::tokenizer::{Location, Token, Tokenizer};
+use crate::core::error::{QrustError, Result};
+
+/// Keywords that can't be used as bare identifiers or aliases.
+pub(crate) const RESERVED: &[&str] = &[
+    "SELECT", "FROM", "WHERE", "GROUP", "BY", "HAVING", "ORDER", "LIMIT", "AS", "AND", "OR", "NOT",
+    "ASC", "DESC", "TRUE", "FALSE", "WITH", "IN", "EXISTS", "JOIN", "INNER", "LEFT", "RIGHT",
+    "FULL", "OUTER", "CROSS", "ON", "BETWEEN", "OFFSET",
+];
+
+// Operator precedence, from loosest to tightest binding.
+const OR_PRECEDENCE: u8 = 5;
+const AND_PRECEDENCE: u8 = 10;
+const NOT_PRECEDENCE: u8 = 15;
+const COMPARISON_PRECEDENCE: u8 = 20;
+const PLUS_MINUS_PRECEDENCE: u8 = 30;
+const MUL_DIV_MOD_PRECEDENCE: u8 = 40;
+const UNARY_MINUS_PRECEDENCE: u8 = 50;
+
+/// A recursive-descent parser that turns SQL text into `Statement`s.
+pub struct Parser {
+    tokens: Vec<(Token, Location)>,
+    index: usize,
+}
+
+impl Parser {
+    pub fn new(sql: &str) -> Result<Parser> {
+        Ok(Parser {
+            tokens: Tokenizer::new(sql).tokenize()?,
+            index: 0,
+        })
+    }
+
+    /// Parses every statement in `sql`. Statements are separated by semicolons.
+    pub fn parse_sql(sql: &str) -> Result<Vec<Statement>> {
+        let mut parser = Parser::new(sql)?;
+        let mut statements = vec![];
+        loop {
+            while parser.consume(&Token::Semicolon) {}
+            if parser.peek() == &Token::Eof {
+                return Ok(statements);
+            }
+            statements.push(parser.parse_statement()?);
+            if parser.peek() != &Token::Eof {
+                parser.expect(&Token::Semicolon)?;
+            }
+        }
+    }
+
+    pub fn parse_statement(&mut self) -> Result<Statement> {
+        if self.peek().is_keyword("SELECT") || self.peek().is_keyword("WITH") {
+            return Ok(Statement::Query(Box::new(self.parse_query()?)));
+        }
+        if self.consume_keyword("CREATE") {
+            return self.parse_create();
+        }
+        if self.consume_keyword("DROP") {
+            return self.parse_drop();
+        }
+        if self.consume_keyword("EXPLAIN") {
+            let analyze = self.consume_keyword("ANALYZE");
+            return Ok(Statement::Explain {
+                analyze,
+                query: Box::new(self.parse_query()?),
+            });
+        }
+        if self.consume_keyword("SHOW") {
+            self.expect_keyword("TABLES")?;
+            return Ok(Statement::ShowTables);
+        }
+        if self.consume_keyword("DESCRIBE") {
+            let name = self.parse_object_name()?;
+            return Ok(Statement::Describe { name });
+        }
+        self.expected("a statement")
+    }
+
+    fn parse_create(&mut self) -> Result<Statement> {
+        self.expect_keyword("EXTERNAL")?;
+        self.expect_keyword("TABLE")?;
+        let if_not_exists = if self.consume_keyword("IF") {
+            self.expect_keyword("NOT")?;
+            self.expect_keyword("EXISTS")?;
+            true
+        } else {
+            false
+        };
+        let name = self.parse_object_name()?;
+
+        self.expect(&Token::LParen)?;
+        let mut columns = vec![self.parse_column_def()?];
+        while self.consume(&Token::Comma) {
+            columns.push(self.parse_column_def()?);
+        }
+        self.expect(&Token::RParen)?;
+
+        self.expect_keyword("STORED")?;
+        self.expect_keyword("AS")?;
+        let file_type = self.parse_identifier()?.to_uppercase();
+
+        let mut has_header = false;
+        let mut delimiter = None;
+        loop {
+            if self.consume_keyword("WITH") {
+                self.expect_keyword("HEADER")?;
+                self.consume_keyword("ROW");
+                has_header = true;
+            } else if self.consume_keyword("DELIMITER") {
+                let location = self.location();
+                let s = self.parse_string()?;
+                let mut chars = s.chars();
+                delimiter = match (chars.next(), chars.next()) {
+                    (Some(c), None) => Some(c),
+                    _ => {
+                        return Err(parser_error(
+                            "Expected a single character delimiter",
+                            location,
+                        ))
+                    }
+                };
+            } else {
+                break;
+            }
+        }
+
+        self.expect_keyword("LOCATION")?;
+        let location = self.parse_string()?;
+        Ok(Statement::CreateExternalTable {
+            name,
+            columns,
+            file_type,
+            has_header,
+            delimiter,
+            location,
+            if_not_exists,
+        })
+    }
+
+    fn parse_column_def(&mut self) -> Result<ColumnDef> {
+        let name = self.parse_identifier()?;
+        let data_type = self.parse_data_type()?;
+        let nullable = if self.consume_keyword("NOT") {
+            self.expect_keyword("NULL")?;
+            false
+        } else {
+            self.consume_keyword("NULL");
+            true
+        };
+        Ok(ColumnDef {
+            name,
+            data_type,
+            nullable,
+        })
+    }
+
+    fn parse_data_type(&mut self) -> Result<DataType> {
+        let token = self.peek();
+        let data_type = if token.is_keyword("BIGINT") {
+            DataType::BigInt
+        } else if token.is_keyword("INT") || token.is_keyword("INTEGER") {
+            DataType::Int
+        } else if token.is_keyword("VARCHAR") {
+            DataType::Varchar
+        } else if token.is_keyword("TEXT") || token.is_keyword("STRING") {
+            DataType::Text
+        } else {
+            return self.expected("a column type");
+        };
+        self.next();
+        // The length of a VARCHAR doesn't change how it is stored.
+        if data_type == DataType::Varchar && self.consume(&Token::LParen) {
+            match self.next() {
+                Token::Number(_) => {}
+                _ => return self.expected("a length"),
+            }
+            self.expect(&Token::RParen)?;
+        }
+        Ok(data_type)
+    }
+
+    fn parse_drop(&mut self) -> Result<Statement> {
+        self.expect_keyword("TABLE")?;
+        let if_exists = if self.consume_keyword("IF") {
+            self.expect_keyword("EXISTS")?;
+            true
+        } else {
+            false
+        };
+        let name = self.parse_object_name()?;
+        Ok(Statement::DropTable { name, if_exists })
+    }
+
+    fn parse_string(&mut self) -> Result<String> {
+        match self.peek().clone() {
+            Token::String(s) => {
+                self.next();
+                Ok(s)
+            }
+            _ => self.expected("a string"),
+        }
+    }
+
+    pub fn parse_query(&mut self) -> Result<Query> {
+        let mut with = vec![];
+        if self.consume_keyword("WITH") {
+            loop {
+                let name = self.parse_identifier()?;
+                self.expect_keyword("AS")?;
+                self.expect(&Token::LParen)?;
+                let query = self.parse_query()?;
+                self.expect(&Token::RParen)?;
+                with.push(Cte { name, query });
+                if !self.consume(&Token::Comma) {
+                    break;
+                }
+            }
+        }
+        let body = self.parse_select()?;
+        let mut order_by = vec![];
+        if self.consume_keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            loop {
+                let expr = self.parse_expr()?;
+                let asc = if self.consume_keyword("DESC") {
+                    false
+                } else {
+                    self.consume_keyword("ASC");
+                    true
+                };
+                let nulls_first = if self.consume_keyword("NULLS") {
+                    if self.consume_keyword("FIRST") {
+                        Some(true)
+                    } else {
+                        self.expect_keyword("LAST")?;
+                        Some(false)
+                    }
+                } else {
+                    None
+                };
+                order_by.push(OrderByExpr {
+                    expr,
+                    asc,
+                    nulls_first,
+                });
+                if !self.consume(&Token::Comma) {
+                    break;
+                }
+            }
+        }
+        let limit = if self.consume_keyword("LIMIT") {
+            Some(self.parse_expr()?)
+        } else {
+            None
+        };
+        let offset = if self.consume_keyword("OFFSET") {
+            Some(self.parse_expr()?)
+        } else {
+            None
+        };
+        Ok(Query {
+            with,
+            body,
+            order_by,
+            limit,
+            offset,
+        })
+    }
+
+    fn parse_select(&mut self) -> Result<Select> {
+        self.expect_keyword("SELECT")?;
+        let mut projection = vec![self.parse_select_item()?];
+        while self.consume(&Token::Comma) {
+            projection.push(self.parse_select_item()?);
+        }
+        let from = if self.consume_keyword("FROM") {
+            Some(self.parse_table_reference()?)
+        } else {
+            None
+        };
+        let selection = if self.consume_keyword("WHERE") {
+            Some(self.parse_expr()?)
+        } else {
+            None
+        };
+        let mut group_by = vec![];
+        if self.consume_keyword("GROUP") {
+            self.expect_keyword("BY")?;
+            group_by = self.parse_expr_list()?;
+        }
+        let having = if self.consume_keyword("HAVING") {
+            Some(self.parse_expr()?)
+        } else {
+            None
+        };
+        Ok(Select {
+            projection,
+            from,
+            selection,
+            group_by,
+            having,
+        })
+    }
+
+    fn parse_select_item(&mut self) -> Result<SelectItem> {
+        if self.consume(&Token::Mul) {
+            return Ok(SelectItem::Wildcard);
+        }
+        let expr = self.parse_expr()?;
+        let alias = self.parse_optional_alias()?;
+        Ok(SelectItem::Expr { expr, alias })
+    }
+
+    fn parse_table_reference(&mut self) -> Result<TableReference> {
+        let mut table = self.parse_table_factor()?;
+        loop {
+            let operator = if self.consume_keyword("JOIN") {
+                JoinOperator::Inner
+            } else if self.consume_keyword("INNER") {
+                self.expect_keyword("JOIN")?;
+                JoinOperator::Inner
+            } else if self.consume_keyword("LEFT") {
+                self.consume_keyword("OUTER");
+                self.expect_keyword("JOIN")?;
+                JoinOperator::Left
+            } else if self.consume_keyword("RIGHT") {
+                self.consume_keyword("OUTER");
+                self.expect_keyword("JOIN")?;
+                JoinOperator::Right
+            } else if self.consume_keyword("FULL") {
+                self.consume_keyword("OUTER");
+                self.expect_keyword("JOIN")?;
+                JoinOperator::Full
+            } else if self.consume_keyword("CROSS") {
+                self.expect_keyword("JOIN")?;
+                JoinOperator::Cross
+            } else {
+                return Ok(table);
+            };
+            let right = self.parse_table_factor()?;
+            let on = if operator == JoinOperator::Cross {
+                None
+            } else {
+                self.expect_keyword("ON")?;
+                Some(self.parse_expr()?)
+            };
+            table = TableReference::Join {
+                left: Box::new(table),
+                right: Box::new(right),
+                operator,
+                on,
+            };
+        }
+    }
+
+    /// Parses a single table or subquery of a `FROM` clause.
+    fn parse_table_factor(&mut self) -> Result<TableReference> {
+        if self.consume(&Token::LParen) {
+            let subquery = Box::new(self.parse_query()?);
+            self.expect(&Token::RParen)?;
+            return match self.parse_optional_alias()? {
+                Some(alias) => Ok(TableReference::Derived { subquery, alias }),
+                None => self.expected("an alias for the subquery"),
+            };
+        }
+        let name = self.parse_object_name()?;
+        let alias = self.parse_optional_alias()?;
+        Ok(TableReference::Table { name, alias })
+    }
+
+    /// Parses `AS alias`, or a bare alias that isn't a reserved keyword.
+    fn parse_optional_alias(&mut self) -> Result<Option<String>> {
+        if self.consume_keyword("AS") {
+            return Ok(Some(self.parse_identifier()?));
+        }
+        if self.peek_identifier() {
+            return Ok(Some(self.parse_identifier()?));
+        }
+        Ok(None)
+    }
+
+    fn parse_object_name(&mut self) -> Result<ObjectName> {
+        let mut parts = vec![self.parse_identifier()?];
+        while self.consume(&Token::Period) {
+            parts.push(self.parse_identifier()?);
+        }
+        Ok(ObjectName(parts))
+    }
+
+    fn parse_identifier(&mut self) -> Result<String> {
+        if !self.peek_identifier() {
+            return self.expected("an identifier");
+        }
+        match self.next() {
+            Token::Word { value, .. } => Ok(value),
+            _ => unreachable!(),
+        }
+    }
+
+    fn peek_identifier(&self) -> bool {
+        match self.peek() {
+            Token::Word { quoted: true, .. } => true,
+            Token::Word { value, .. } => !RESERVED.iter().any(|k| value.eq_ignore_ascii_case(k)),
+            _ => false,
+        }
+    }
+
+    fn parse_expr_list(&mut self) -> Result<Vec<Expr>> {
+        let mut exprs = vec![self.parse_expr()?];
+        while self.consume(&Token::Comma) {
+            exprs.push(self.parse_expr()?);
+        }
+        Ok(exprs)
+    }
+
+    pub fn parse_expr(&mut self) -> Result<Expr> {
+        self.parse_subexpr(0)
+    }
+
+    /// Parses an expression whose operators all bind tighter than `precedence`.
+    fn parse_subexpr(&mut self, precedence: u8) -> Result<Expr> {
+        let mut expr = self.parse_prefix()?;
+        loop {
+            if let Some(negated) = self.peek_in() {
+                if COMPARISON_PRECEDENCE <= precedence {
+                    return Ok(expr);
+                }
+                if negated {
+                    self.next();
+                }
+                self.next();
+                expr = Expr::InSubquery {
+                    expr: Box::new(expr),
+                    subquery: Box::new(self.parse_parenthesized_query()?),
+                    negated,
+                };
+                continue;
+            }
+            if let Some(negated) = self.peek_between() {
+                if COMPARISON_PRECEDENCE <= precedence {
+                    return Ok(expr);
+                }
+                if negated {
+                    self.next();
+                }
+                self.next();
+                // The bounds can't contain AND, which separates them.
+                let low = self.parse_subexpr(COMPARISON_PRECEDENCE)?;
+                self.expect_keyword("AND")?;
+                let high = self.parse_subexpr(COMPARISON_PRECEDENCE)?;
+                expr = Expr::Between {
+                    expr: Box::new(expr),
+                    negated,
+                    low: Box::new(low),
+                    high: Box::new(high),
+                };
+                continue;
+            }
+
+            let (op, next_precedence) = match self.peek_binary_operator() {
+                Some(op) => op,
+                None => return Ok(expr),
+            };
+            if next_precedence <= precedence {
+                return Ok(expr);
+            }
+            self.next();
+            let right = self.parse_subexpr(next_precedence)?;
+            expr = Expr::BinaryOp {
+                left: Box::new(expr),
+                op,
+                right: Box::new(right),
+            };
+        }
+    }
+
+    /// Returns whether the next tokens are `IN` or `NOT IN`, and which of the two.
+    fn peek_in(&self) -> Option<bool> {
+        self.peek_negatable("IN")
+    }
+
+    /// Returns whether the next tokens are `BETWEEN` or `NOT BETWEEN`, and which of the two.
+    fn peek_between(&self) -> Option<bool> {
+        self.peek_negatable("BETWEEN")
+    }
+
+    fn peek_negatable(&self, keyword: &str) -> Option<bool> {
+        if self.peek().is_keyword(keyword) {
+            return Some(false);
+        }
+        let next = &self.tokens[(self.index + 1).min(self.tokens.len() - 1)].0;
+        if self.peek().is_keyword("NOT") && next.is_keyword(keyword) {
+            return Some(true);
+        }
+        None
+    }
+
+    fn parse_parenthesized_query(&mut self) -> Result<Query> {
+        self.expect(&Token::LParen)?;
+        let query = self.parse_query()?;
+        self.expect(&Token::RParen)?;
+        Ok(query)
+    }
+
+    fn peek_binary_operator(&self) -> Option<(BinaryOperator, u8)> {
+        let token = self.peek();
+        let op = match token {
+            Token::Eq => (BinaryOperator::Eq, COMPARISON_PRECEDENCE),
+            Token::Neq => (BinaryOperator::NotEq, COMPARISON_PRECEDENCE),
+            Token::Lt => (BinaryOperator::Lt, COMPARISON_PRECEDENCE),
+            Token::LtEq => (BinaryOperator::LtEq, COMPARISON_PRECEDENCE),
+            Token::Gt => (BinaryOperator::Gt, COMPARISON_PRECEDENCE),
+            Token::GtEq => (BinaryOperator::GtEq, COMPARISON_PRECEDENCE),
+            Token::Plus => (BinaryOperator::Plus, PLUS_MINUS_PRECEDENCE),
+            Token::Minus => (BinaryOperator::Minus, PLUS_MINUS_PRECEDENCE),
+            Token::Mul => (BinaryOperator::Multiply, MUL_DIV_MOD_PRECEDENCE),
+            Token::Div => (BinaryOperator::Divide, MUL_DIV_MOD_PRECEDENCE),
+            Token::Mod => (BinaryOperator::Modulo, MUL_DIV_MOD_PRECEDENCE),
+            _ if token.is_keyword("AND") => (BinaryOperator::And, AND_PRECEDENCE),
+            _ if token.is_keyword("OR") => (BinaryOperator::Or, OR_PRECEDENCE),
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    fn parse_prefix(&mut self) -> Result<Expr> {
+        if self.consume_keyword("NOT") {
+            return Ok(Expr::Not(Box::new(self.parse_subexpr(NOT_PRECEDENCE)?)));
+        }
+        if self.consume_keyword("TRUE") {
+            return Ok(Expr::Boolean(true));
+        }
+        if self.consume_keyword("FALSE") {
+            return Ok(Expr::Boolean(false));
+        }
+        if self.consume_keyword("EXISTS") {
+            return Ok(Expr::Exists(Box::new(self.parse_parenthesized_query()?)));
+        }
+        if self.peek_identifier() {
+            let name = self.parse_object_name()?;
+            if name.0.len() == 1 && self.consume(&Token::LParen) {
+                let args = if self.consume(&Token::RParen) {
+                    vec![]
+                } else {
+                    let args = self.parse_expr_list()?;
+                    self.expect(&Token::RParen)?;
+                    args
+                };
+                // Function names are case insensitive.
+                return Ok(Expr::Function {
+                    name: name.0[0].to_lowercase(),
+                    args,
+                });
+            }
+            return Ok(Expr::Identifier(name));
+        }
+
+        match self.peek().clone() {
+            Token::Number(n) => {
+                let location = self.location();
+                self.next();
+                n.parse::<i64>()
+                    .map(Expr::Integer)
+                    .map_err(|_| parser_error(&format!("Integer {} is out of range", n), location))
+            }
+            Token::String(s) => {
+                self.next();
+                Ok(Expr::String(s))
+            }
+            Token::Placeholder(p) => {
+                self.next();
+                Ok(Expr::Placeholder(format!("${}", p)))
+            }
+            Token::Minus => {
+                self.next();
+                Ok(Expr::Negative(Box::new(
+                    self.parse_subexpr(UNARY_MINUS_PRECEDENCE)?,
+                )))
+            }
+            Token::LParen => {
+                let next = &self.tokens[self.index + 1].0;
+                if next.is_keyword("SELECT") || next.is_keyword("WITH") {
+                    return Ok(Expr::Subquery(Box::new(self.parse_parenthesized_query()?)));
+                }
+                self.next();
+                let expr = self.parse_expr()?;
+                self.expect(&Token::RParen)?;
+                Ok(Expr::Nested(Box::new(expr)))
+            }
+            _ => self.expected("an expression"),
+        }
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.index].0
+    }
+
+    fn location(&self) -> Location {
+        self.tokens[self.index].1
+    }
+
+    /// Returns the current token and advances, stopping at the end of the input.
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.index].0.clone();
+        if token != Token::Eof {
+            self.index += 1;
+        }
+        token
+    }
+
+    fn consume(&mut self, token: &Token) -> bool {
+        if self.peek() == token {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn consume_keyword(&mut self, keyword: &str) -> bool {
+        if self.peek().is_keyword(keyword) {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token) -> Result<()> {
+        if self.consume(token) {
+            Ok(())
+        } else {
+            self.expected(&token.to_string())
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        if self.consume_keyword(keyword) {
+            Ok(())
+        } else {
+            self.expected(keyword)
+        }
+    }
+
+    fn expected<T>(&self, expected: &str) -> Result<T> {
+        Err(parser_error(
+            &format!("Expected {} but found {}", expected, self.peek()),
+            self.location(),
+        ))
+    }
+}
+
+fn parser_error(message: &str, location: Location) -> QrustError {
+    QrustError::Parse {
+        message: message.to_owned(),
+        line: location.line,
+        column: location.column,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(sql: &str) -> Statement {
+        let mut statements = Parser::parse_sql(sql).unwrap();
+        assert_eq!(statements.len(), 1);
+        statements.remove(0)
+    }
+
+    fn parse_expr(sql: &str) -> Expr {
+        Parser::new(sql).unwrap().parse_expr().unwrap()
+    }
+
+    fn ident(name: &str) -> Box<Expr> {
+        Box::new(Expr::Identifier(ObjectName(vec![name.to_owned()])))
+    }
+
+    #[test]
+    fn parse_full_query() {
+        let sql = "SELECT region, SUM(amount) AS total, max(price) p FROM analytics.sales s \
+                   WHERE amount > 10 GROUP BY region HAVING sum(amount) > 100 \
+                   ORDER BY total DESC, region LIMIT 5";
+        assert_eq!(
+            parse(sql).to_string(),
+            "SELECT region, sum(amount) AS total, max(price) AS p FROM analytics.sales AS s \
+             WHERE amount > 10 GROUP BY region HAVING sum(amount) > 100 \
+             ORDER BY total DESC, region ASC LIMIT 5"
+        );
+        assert_eq!(
+            parse("SELECT a FROM t ORDER BY a nulls first, b DESC NULLS LAST LIMIT 5 offset 10")
+                .to_string(),
+            "SELECT a FROM t ORDER BY a ASC NULLS FIRST, b DESC NULLS LAST LIMIT 5 OFFSET 10"
+        );
+    }
+
+    #[test]
+    fn parse_wildcard_and_keywords_in_any_case() {
+        let query = match parse("select * from t where not a = 'x';") {
+            Statement::Query(query) => query,
+            _ => unreachable!(),
+        };
+        assert_eq!(query.body.projection, vec![SelectItem::Wildcard]);
+        assert_eq!(
+            query.body.from,
+            Some(TableReference::Table {
+                name: ObjectName(vec!["t".to_owned()]),
+                alias: None
+            })
+        );
+        assert_eq!(
+            query.body.selection,
+            Some(Expr::Not(Box::new(Expr::BinaryOp {
+                left: ident("a"),
+                op: BinaryOperator::Eq,
+                right: Box::new(Expr::String("x".to_owned())),
+            })))
+        );
+    }
+
+    #[test]
+    fn operator_precedence() {
+        assert_eq!(
+            parse_expr("a + b * c % 2 - d"),
+            Expr::BinaryOp {
+                left: Box::new(Expr::BinaryOp {
+                    left: ident("a"),
+                    op: BinaryOperator::Plus,
+                    right: Box::new(Expr::BinaryOp {
+                        left: Box::new(Expr::BinaryOp {
+                            left: ident("b"),
+                            op: BinaryOperator::Multiply,
+                            right: ident("c"),
+                        }),
+                        op: BinaryOperator::Modulo,
+                        right: Box::new(Expr::Integer(2)),
+                    }),
+                }),
+                op: BinaryOperator::Minus,
+                right: ident("d"),
+            }
+        );
+
+        assert_eq!(
+            parse_expr("a = 1 OR b > 2 AND NOT c < -3"),
+            Expr::BinaryOp {
+                left: Box::new(Expr::BinaryOp {
+                    left: ident("a"),
+                    op: BinaryOperator::Eq,
+                    right: Box::new(Expr::Integer(1)),
+                }),
+                op: BinaryOperator::Or,
+                right: Box::new(Expr::BinaryOp {
+                    left: Box::new(Expr::BinaryOp {
+                        left: ident("b"),
+                        op: BinaryOperator::Gt,
+                        right: Box::new(Expr::Integer(2)),
+                    }),
+                    op: BinaryOperator::And,
+                    right: Box::new(Expr::Not(Box::new(Expr::BinaryOp {
+                        left: ident("c"),
+                        op: BinaryOperator::Lt,
+                        right: Box::new(Expr::Negative(Box::new(Expr::Integer(3)))),
+                    }))),
+                }),
+            }
+        );
+
+        assert_eq!(
+            parse_expr("(a + b) * c").to_string(),
+            "(a + b) * c".to_owned()
+        );
+    }
+
+    #[test]
+    fn parse_placeholders() {
+        assert_eq!(
+            parse_expr("a = $1 AND b > $min_b"),
+            Expr::BinaryOp {
+                left: Box::new(Expr::BinaryOp {
+                    left: ident("a"),
+                    op: BinaryOperator::Eq,
+                    right: Box::new(Expr::Placeholder("$1".to_owned())),
+                }),
+                op: BinaryOperator::And,
+                right: Box::new(Expr::BinaryOp {
+                    left: ident("b"),
+                    op: BinaryOperator::Gt,
+                    right: Box::new(Expr::Placeholder("$min_b".to_owned())),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_ddl() {
+        assert_eq!(
+            parse(
+                "create external table if not exists analytics.events (\n\
+                   id BIGINT NOT NULL, count INTEGER, name VARCHAR(20) NULL, note text\n\
+                 ) stored as csv with header delimiter '|' location '/data/events.csv'"
+            ),
+            Statement::CreateExternalTable {
+                name: ObjectName(vec!["analytics".to_owned(), "events".to_owned()]),
+                columns: vec![
+                    ColumnDef {
+                        name: "id".to_owned(),
+                        data_type: DataType::BigInt,
+                        nullable: false,
+                    },
+                    ColumnDef {
+                        name: "count".to_owned(),
+                        data_type: DataType::Int,
+                        nullable: true,
+                    },
+                    ColumnDef {
+                        name: "name".to_owned(),
+                        data_type: DataType::Varchar,
+                        nullable: true,
+                    },
+                    ColumnDef {
+                        name: "note".to_owned(),
+                        data_type: DataType::Text,
+                        nullable: true,
+                    },
+                ],
+                file_type: "CSV".to_owned(),
+                has_header: true,
+                delimiter: Some('|'),
+                location: "/data/events.csv".to_owned(),
+                if_not_exists: true,
+            }
+        );
+        assert_eq!(
+            parse("CREATE EXTERNAL TABLE t (a BIGINT) STORED AS CSV LOCATION 'a.csv'").to_string(),
+            "CREATE EXTERNAL TABLE t (a BIGINT) STORED AS CSV LOCATION 'a.csv'"
+        );
+
+        assert_eq!(
+            parse("DROP TABLE IF EXISTS t").to_string(),
+            "DROP TABLE IF EXISTS t"
+        );
+
+        let err = Parser::parse_sql("CREATE EXTERNAL TABLE t (a FLOAT) STORED AS CSV LOCATION 'x'")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parse error at line 1, column 28: Expected a column type but found FLOAT"
+        );
+        let err = Parser::parse_sql("CREATE EXTERNAL TABLE t (a INT) STORED AS CSV").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parse error at line 1, column 46: Expected LOCATION but found end of input"
+        );
+    }
+
+    #[test]
+    fn parse_explain() {
+        match parse("EXPLAIN ANALYZE SELECT a FROM t") {
+            Statement::Explain { analyze, query } => {
+                assert!(analyze);
+                assert_eq!(query.to_string(), "SELECT a FROM t");
+            }
+            other => panic!("Expected EXPLAIN but found {}", other),
+        }
+        assert_eq!(
+            parse("explain select a from t").to_string(),
+            "EXPLAIN SELECT a FROM t"
+        );
+
+        let err = Parser::parse_sql("EXPLAIN DROP TABLE t").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parse error at line 1, column 9: Expected SELECT but found DROP"
+        );
+    }
+
+    #[test]
+    fn parse_show_and_describe() {
+        assert_eq!(parse("show tables").to_string(), "SHOW TABLES");
+        assert_eq!(
+            parse("DESCRIBE analytics.events").to_string(),
+            "DESCRIBE analytics.events"
+        );
+        let err = Parser::parse_sql("SHOW t").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parse error at line 1, column 6: Expected TABLES but found t"
+        );
+    }
+
+    #[test]
+    fn parse_ctes_and_derived_tables() {
+        let sql = "WITH a AS (SELECT x FROM t), b AS (SELECT x FROM a) \
+                   SELECT s.x FROM (SELECT x FROM b) AS s";
+        let query = match parse(sql) {
+            Statement::Query(query) => query,
+            _ => unreachable!(),
+        };
+        assert_eq!(query.with.len(), 2);
+        assert_eq!(query.with[1].name, "b");
+        match query.body.from.as_ref().unwrap() {
+            TableReference::Derived { subquery, alias } => {
+                assert_eq!(subquery.to_string(), "SELECT x FROM b");
+                assert_eq!(alias, "s");
+            }
+            other => panic!("Expected a derived table but found {}", other),
+        }
+        assert_eq!(
+            query.to_string(),
+            "WITH a AS (SELECT x FROM t), b AS (SELECT x FROM a) \
+             SELECT s.x FROM (SELECT x FROM b) AS s"
+        );
+
+        let err = Parser::parse_sql("SELECT x FROM (SELECT x FROM t)").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parse error at line 1, column 32: Expected an alias for the subquery but found end of input"
+        );
+    }
+
+    #[test]
+    fn parse_joins() {
+        let sql = "SELECT * FROM users u INNER JOIN orders o ON u.id = o.uid \
+                   left outer join (SELECT uid FROM refunds) r ON o.uid = r.uid \
+                   RIGHT JOIN a ON a.x = u.x FULL OUTER JOIN b ON b.x = u.x";
+        assert_eq!(
+            parse(sql).to_string(),
+            "SELECT * FROM users AS u JOIN orders AS o ON u.id = o.uid \
+             LEFT JOIN (SELECT uid FROM refunds) AS r ON o.uid = r.uid \
+             RIGHT JOIN a ON a.x = u.x FULL JOIN b ON b.x = u.x"
+        );
+        assert_eq!(
+            parse("SELECT * FROM a cross join b JOIN c ON c.x BETWEEN a.x AND b.x + 1").to_string(),
+            "SELECT * FROM a CROSS JOIN b JOIN c ON c.x BETWEEN a.x AND b.x + 1"
+        );
+        // The AND after the upper bound combines the BETWEEN with what follows.
+        assert_eq!(
+            parse_expr("x NOT BETWEEN 1 AND 2 AND y"),
+            Expr::BinaryOp {
+                left: Box::new(Expr::Between {
+                    expr: ident("x"),
+                    negated: true,
+                    low: Box::new(Expr::Integer(1)),
+                    high: Box::new(Expr::Integer(2)),
+                }),
+                op: BinaryOperator::And,
+                right: ident("y"),
+            }
+        );
+
+        let err = Parser::parse_sql("SELECT * FROM a JOIN b").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parse error at line 1, column 23: Expected ON but found end of input"
+        );
+    }
+
+    #[test]
+    fn quotes_identifiers_when_displayed() {
+        let sql = "SELECT \"sum(a)\" AS \"Total\", \"select\" FROM \"my table\" AS t";
+        assert_eq!(
+            parse(sql).to_string(),
+            "SELECT \"sum(a)\" AS Total, \"select\" FROM \"my table\" AS t"
+        );
+    }
+
+    #[test]
+    fn multiple_statements() {
+        let statements = Parser::parse_sql("SELECT 1; SELECT 2;").unwrap();
+        assert_eq!(statements.len(), 2);
+    }
+
+    #[test]
+    fn errors_report_location() {
+        let err = Parser::parse_sql("SELECT a,\nFROM t").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parse error at line 2, column 1: Expected an expression but found FROM"
+        );
+
+        let err = Parser::parse_sql("SELECT (a + 1 FROM t").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parse error at line 1, column 15: Expected ) but found FROM"
+        );
+
+        let err = Parser::parse_sql("SELECT a FROM t GROUP a").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parse error at line 1, column 23: Expected BY but found a"
+        );
+
+        let err = Parser::parse_sql("UPDATE t").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parse error at line 1, column 1: Expected a statement but found UPDATE"
+        );
+
+        let err = Parser::parse_sql("SELECT a b c").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parse error at line 1, column 12: Expected ; but found c"
+        );
+    }
+
+    #[test]
+    fn parse_subquery_expressions() {
+        let query = match parse(
+            "SELECT a, (SELECT max(b) FROM u) FROM t \
+             WHERE a NOT IN (SELECT a FROM u) AND NOT EXISTS (SELECT b FROM u WHERE b = t.a)",
+        ) {
+            Statement::Query(query) => query,
+            _ => unreachable!(),
+        };
+        match &query.body.projection[1] {
+            SelectItem::Expr {
+                expr: Expr::Subquery(subquery),
+                ..
+            } => assert_eq!(subquery.to_string(), "SELECT max(b) FROM u"),
+            other => panic!("Expected a scalar subquery but found {}", other),
+        }
+        match query.body.selection.as_ref().unwrap() {
+            Expr::BinaryOp { left, right, .. } => {
+                assert!(matches!(
+                    left.as_ref(),
+                    Expr::InSubquery { negated: true, .. }
+                ));
+                assert!(
+                    matches!(right.as_ref(), Expr::Not(e) if matches!(e.as_ref(), Expr::Exists(_)))
+                );
+            }
+            other => panic!("Expected AND but found {}", other),
+        }
+        assert_eq!(
+            query.to_string(),
+            "SELECT a, (SELECT max(b) FROM u) FROM t \
+             WHERE a NOT IN (SELECT a FROM u) AND NOT EXISTS (SELECT b FROM u WHERE b = t.a)"
+        );
+
+        // IN binds like a comparison, so tighter than NOT.
+        assert_eq!(
+            parse("SELECT a FROM t WHERE NOT a + 1 IN (SELECT b FROM u)").to_string(),
+            "SELECT a FROM t WHERE NOT a + 1 IN (SELECT b FROM u)"
+        );
+        assert!(matches!(
+            parse("SELECT a FROM t WHERE NOT a IN (SELECT b FROM u)"),
+            Statement::Query(q) if matches!(q.body.selection, Some(Expr::Not(_)))
+        ));
+
+        let err = Parser::parse_sql("SELECT a FROM t WHERE a IN (1, 2)").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parse error at line 1, column 29: Expected SELECT but found 1"
+        );
+    }
+}
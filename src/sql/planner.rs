@@ -0,0 +1,1157 @@
+use arrow::datatypes::{DataType as ArrowDataType, Field, Schema};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::ast::*;
+use super::parser::Parser;
+use crate::core::catalog::ResolvedTableReference;
+use crate::core::data_source::DataSource;
+use crate::core::data_type::ArrowType;
+use crate::core::error::{QrustError, Result};
+use crate::core::information_schema::INFORMATION_SCHEMA;
+use crate::logical_plan::aggregate::Aggregate;
+use crate::logical_plan::ddl::{CreateExternalTable, DropTable};
+use crate::logical_plan::empty_relation::EmptyRelation;
+use crate::logical_plan::explain::Explain;
+use crate::logical_plan::expression::{
+    AggregateExpression, AliasExpression, BooleanExpression, ColumnExpression, ExistsExpression,
+    InSubqueryExpression, LiteralBooleanExpression, LiteralI64Expression, LiteralStringExpression,
+    LogicalExpression, MathExpression, NotExpression, OuterColumnExpression, PlaceholderExpression,
+    ScalarSubqueryExpression, SortExpression,
+};
+use crate::logical_plan::join::{Join, JoinType};
+use crate::logical_plan::limit::Limit;
+use crate::logical_plan::projection::Projection;
+use crate::logical_plan::scan::Scan;
+use crate::logical_plan::selection::Selection;
+use crate::logical_plan::sort::Sort;
+use crate::logical_plan::subquery_alias::{self, field_name, relation_name, SubqueryAlias};
+use crate::logical_plan::LogicalPlan;
+
+/// Gives the SQL planner access to the tables a query can refer to.
+pub trait ContextProvider {
+    /// Looks up a table by its possibly qualified name.
+    fn get_table_source(&self, name: &[String]) -> Option<Arc<dyn DataSource>>;
+}
+
+/// Converts parsed SQL statements into logical plans.
+pub struct SqlPlanner<'a> {
+    provider: &'a dyn ContextProvider,
+}
+
+/// State that is visible to a query and every subquery nested inside it.
+#[derive(Default, Clone)]
+struct PlannerContext {
+    /// The plans of the common table expressions in scope, by name.
+    ctes: HashMap<String, Arc<dyn LogicalPlan>>,
+    /// The relation of the query that encloses a subquery expression. Columns that
+    /// can't be found in the subquery itself are looked up here.
+    outer: Option<Arc<OuterRelation>>,
+}
+
+struct OuterRelation {
+    schema: Arc<Schema>,
+    qualifiers: Vec<String>,
+}
+
+/// The relation that expressions in a `SELECT` are resolved against.
+struct Scope<'a> {
+    context: &'a PlannerContext,
+    schema: Arc<Schema>,
+    /// Names that columns of the relation may be qualified with, e.g. a table alias.
+    qualifiers: Vec<String>,
+    /// Maps the SQL text of grouping and aggregate expressions to the columns that hold
+    /// their values once the input has been aggregated.
+    aggregated: Option<HashMap<String, String>>,
+}
+
+impl<'a> SqlPlanner<'a> {
+    pub fn new(provider: &'a dyn ContextProvider) -> SqlPlanner<'a> {
+        SqlPlanner { provider }
+    }
+
+    pub fn statement_to_plan(&self, statement: &Statement) -> Result<Arc<dyn LogicalPlan>> {
+        match statement {
+            Statement::Query(query) => self.query_to_plan(query),
+            Statement::CreateExternalTable {
+                name,
+                columns,
+                file_type,
+                has_header,
+                delimiter,
+                location,
+                if_not_exists,
+            } => {
+                if file_type != "CSV" {
+                    return Err(QrustError::NotImplemented(format!(
+                        "Unsupported file type {}",
+                        file_type
+                    )));
+                }
+                let delimiter = match delimiter {
+                    None => b',',
+                    Some(c) if c.is_ascii() => *c as u8,
+                    Some(c) => {
+                        return Err(QrustError::Plan(format!(
+                            "Delimiter {} is not an ASCII character",
+                            c
+                        )))
+                    }
+                };
+                Ok(Arc::new(CreateExternalTable::new(
+                    name.to_string(),
+                    columns_to_schema(columns)?,
+                    location.clone(),
+                    *has_header,
+                    delimiter,
+                    *if_not_exists,
+                )))
+            }
+            Statement::DropTable { name, if_exists } => {
+                Ok(Arc::new(DropTable::new(name.to_string(), *if_exists)))
+            }
+            Statement::Explain { analyze, query } => {
+                Ok(Arc::new(Explain::new(self.query_to_plan(query)?, *analyze)))
+            }
+            // Both are answered by querying information_schema.
+            Statement::ShowTables => self.sql_to_plan(&format!(
+                "SELECT table_catalog, table_schema, table_name, table_type FROM {}.tables",
+                INFORMATION_SCHEMA
+            )),
+            Statement::Describe { name } => {
+                if self.provider.get_table_source(&name.0).is_none() {
+                    return Err(QrustError::Plan(format!("Table not found: {}", name)));
+                }
+                let reference = ResolvedTableReference::try_from_parts(&name.0)?;
+                self.sql_to_plan(&format!(
+                    "SELECT column_name, data_type, is_nullable FROM {}.{}.columns \
+                     WHERE table_schema = {} AND table_name = {}",
+                    quote_identifier(&reference.catalog),
+                    INFORMATION_SCHEMA,
+                    quote_string(&reference.schema),
+                    quote_string(&reference.table)
+                ))
+            }
+        }
+    }
+
+    /// Plans a query written by the planner itself.
+    fn sql_to_plan(&self, sql: &str) -> Result<Arc<dyn LogicalPlan>> {
+        match Parser::parse_sql(sql)?.as_slice() {
+            [statement] => self.statement_to_plan(statement),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn query_to_plan(&self, query: &Query) -> Result<Arc<dyn LogicalPlan>> {
+        self.plan_query(query, &PlannerContext::default())
+    }
+
+    fn plan_query(&self, query: &Query, outer: &PlannerContext) -> Result<Arc<dyn LogicalPlan>> {
+        // Each common table expression can refer to the ones defined before it, and
+        // shadows tables and outer common table expressions of the same name.
+        let mut context = outer.clone();
+        for (i, cte) in query.with.iter().enumerate() {
+            if query.with[..i].iter().any(|c| c.name == cte.name) {
+                return Err(QrustError::Plan(format!(
+                    "WITH query name {} is specified more than once",
+                    cte.name
+                )));
+            }
+            let plan = self.plan_query(&cte.query, &context)?;
+            context.ctes.insert(cte.name.clone(), plan);
+        }
+
+        let plan = self.select_to_plan(&query.body, &query.order_by, &context)?;
+        let skip = match &query.offset {
+            Some(offset) => row_count(offset, "OFFSET")?,
+            None => 0,
+        };
+        let fetch = query
+            .limit
+            .as_ref()
+            .map(|limit| row_count(limit, "LIMIT"))
+            .transpose()?;
+        if skip == 0 && fetch.is_none() {
+            return Ok(plan);
+        }
+        Ok(Arc::new(Limit::new(plan, skip, fetch)))
+    }
+
+    fn select_to_plan(
+        &self,
+        select: &Select,
+        order_by: &[OrderByExpr],
+        context: &PlannerContext,
+    ) -> Result<Arc<dyn LogicalPlan>> {
+        let from = select.from.as_ref().ok_or_else(|| {
+            QrustError::NotImplemented("SELECT without FROM is not supported yet".to_owned())
+        })?;
+        let (mut plan, qualifiers) = self.table_to_plan(from, context)?;
+        let mut scope = Scope {
+            context,
+            schema: plan.schema(),
+            qualifiers,
+            aggregated: None,
+        };
+
+        if let Some(selection) = &select.selection {
+            let expr = self.sql_to_expr(selection, &scope)?;
+            plan = Arc::new(Selection::new(plan, expr));
+        }
+
+        let mut aggregates = vec![];
+        for item in &select.projection {
+            if let SelectItem::Expr { expr, .. } = item {
+                find_aggregates(expr, &mut aggregates)?;
+            }
+        }
+        if let Some(having) = &select.having {
+            find_aggregates(having, &mut aggregates)?;
+        }
+
+        if !select.group_by.is_empty() || !aggregates.is_empty() {
+            let (aggregate, aggregated) =
+                self.aggregate_to_plan(plan, &select.group_by, &aggregates, &scope)?;
+            plan = aggregate;
+            scope = Scope {
+                context,
+                schema: plan.schema(),
+                qualifiers: scope.qualifiers,
+                aggregated: Some(aggregated),
+            };
+        } else if select.having.is_some() {
+            return Err(QrustError::Plan(
+                "HAVING requires GROUP BY or an aggregate function".to_owned(),
+            ));
+        }
+
+        if let Some(having) = &select.having {
+            let expr = self.sql_to_expr(having, &scope)?;
+            plan = Arc::new(Selection::new(plan, expr));
+        }
+
+        let projected = self.projection_to_plan(Arc::clone(&plan), &select.projection, &scope)?;
+        if order_by.is_empty() {
+            return Ok(projected);
+        }
+        // ORDER BY refers to the output columns, or failing that to the input of the
+        // projection, which can be sorted instead as projecting keeps the order of rows.
+        let output = Scope {
+            context,
+            schema: projected.schema(),
+            qualifiers: scope.qualifiers.clone(),
+            aggregated: None,
+        };
+        match self.order_by_to_exprs(order_by, &output) {
+            Ok(expr) => Ok(Arc::new(Sort::new(projected, expr))),
+            Err(e) if projected.as_any().is::<Projection>() => {
+                let expr = self.order_by_to_exprs(order_by, &scope).map_err(|_| e)?;
+                Ok(projected.with_new_children(vec![Arc::new(Sort::new(plan, expr))]))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn order_by_to_exprs(
+        &self,
+        order_by: &[OrderByExpr],
+        scope: &Scope,
+    ) -> Result<Vec<Arc<SortExpression>>> {
+        order_by
+            .iter()
+            .map(|o| {
+                let expr = self.sql_to_expr(&o.expr, scope)?;
+                Ok(Arc::new(SortExpression::new(
+                    expr,
+                    o.asc,
+                    o.nulls_first.unwrap_or(false),
+                )))
+            })
+            .collect()
+    }
+
+    /// Plans a relation in a `FROM` clause, returning the names its columns may be
+    /// qualified with.
+    fn table_to_plan(
+        &self,
+        table: &TableReference,
+        context: &PlannerContext,
+    ) -> Result<(Arc<dyn LogicalPlan>, Vec<String>)> {
+        let (name, alias) = match table {
+            TableReference::Table { name, alias } => (name, alias),
+            TableReference::Derived { subquery, alias } => {
+                let plan = self.plan_query(subquery, context)?;
+                let plan = Arc::new(SubqueryAlias::new(plan, alias.clone()));
+                return Ok((plan, vec![alias.clone()]));
+            }
+            TableReference::Join {
+                left,
+                right,
+                operator,
+                on,
+            } => return self.join_to_plan(left, right, *operator, on.as_ref(), context),
+        };
+
+        if let [cte_name] = name.0.as_slice() {
+            if let Some(cte) = context.ctes.get(cte_name) {
+                let alias = alias.as_ref().unwrap_or(cte_name);
+                let plan = Arc::new(SubqueryAlias::new(Arc::clone(cte), alias.clone()));
+                return Ok((plan, vec![alias.clone()]));
+            }
+        }
+
+        let source = self
+            .provider
+            .get_table_source(&name.0)
+            .ok_or_else(|| QrustError::Plan(format!("Table not found: {}", name)))?;
+        let scan = Scan::new(name.to_string(), source.schema(), source, vec![]);
+        let qualifiers = match alias {
+            Some(alias) => vec![alias.clone()],
+            None => vec![name.0.last().unwrap().clone()],
+        };
+        Ok((Arc::new(scan), qualifiers))
+    }
+
+    /// Plans a join on the columns its condition compares for equality. Other conditions
+    /// are kept as the filter of the join and evaluated on each pair of rows.
+    fn join_to_plan(
+        &self,
+        left: &TableReference,
+        right: &TableReference,
+        operator: JoinOperator,
+        on: Option<&Expr>,
+        context: &PlannerContext,
+    ) -> Result<(Arc<dyn LogicalPlan>, Vec<String>)> {
+        let (left, left_qualifiers) = self.table_to_plan(left, context)?;
+        let (right, right_qualifiers) = self.table_to_plan(right, context)?;
+        let left = with_relation_name(left, &left_qualifiers);
+        let right = with_relation_name(right, &right_qualifiers);
+        let (left_schema, right_schema) = (left.schema(), right.schema());
+
+        let mut conditions = vec![];
+        if let Some(on) = on {
+            split_conjunction(on, &mut conditions);
+        }
+        let mut keys = vec![];
+        let mut filters = vec![];
+        for condition in conditions {
+            let key = match condition {
+                Expr::BinaryOp {
+                    left: l,
+                    op: BinaryOperator::Eq,
+                    right: r,
+                } => match (l.as_ref(), r.as_ref()) {
+                    (Expr::Identifier(l), Expr::Identifier(r)) => {
+                        let resolve = |l, r| {
+                            Some((
+                                resolve_column(l, &left_schema, &left_qualifiers)?,
+                                resolve_column(r, &right_schema, &right_qualifiers)?,
+                            ))
+                        };
+                        resolve(l, r).or_else(|| resolve(r, l))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            };
+            match key {
+                Some(key) => keys.push(key),
+                None if *condition == Expr::Boolean(true) => {}
+                None => filters.push(condition),
+            }
+        }
+
+        let join_type = match operator {
+            // A cross join is an inner join that every pair of rows matches.
+            JoinOperator::Inner | JoinOperator::Cross => JoinType::Inner,
+            JoinOperator::Left => JoinType::Left,
+            JoinOperator::Right => JoinType::Right,
+            JoinOperator::Full => JoinType::Full,
+        };
+        // The filter refers to the columns of the joined rows, which are only known once
+        // the join is planned.
+        let join = Join::try_new(
+            Arc::clone(&left),
+            Arc::clone(&right),
+            join_type,
+            keys.clone(),
+            None,
+        )?;
+        let mut qualifiers = left_qualifiers;
+        qualifiers.extend(right_qualifiers);
+        let scope = Scope {
+            context,
+            schema: join.joined_schema(),
+            qualifiers,
+            aggregated: None,
+        };
+        let mut filter: Option<Arc<dyn LogicalExpression>> = None;
+        for condition in filters {
+            let expr = self.sql_to_expr(condition, &scope)?;
+            filter = Some(match filter {
+                Some(filter) => Arc::new(BooleanExpression::and(filter, expr)),
+                None => expr,
+            });
+        }
+        let plan: Arc<dyn LogicalPlan> = match filter {
+            Some(filter) => Arc::new(Join::try_new(left, right, join_type, keys, Some(filter))?),
+            None => Arc::new(join),
+        };
+        Ok((plan, scope.qualifiers))
+    }
+
+    fn aggregate_to_plan(
+        &self,
+        input: Arc<dyn LogicalPlan>,
+        group_by: &[Expr],
+        aggregates: &[Expr],
+        scope: &Scope,
+    ) -> Result<(Arc<dyn LogicalPlan>, HashMap<String, String>)> {
+        let mut groupexpr = vec![];
+        for expr in group_by {
+            let logical = self.sql_to_expr(expr, scope)?;
+            // Grouping on anything but a column is named after its SQL text.
+            if logical
+                .as_any()
+                .downcast_ref::<ColumnExpression>()
+                .is_some()
+            {
+                groupexpr.push(logical);
+            } else {
+                groupexpr.push(Arc::new(AliasExpression::new(logical, expr.to_string())) as _);
+            }
+        }
+
+        let mut aggregateexpr = vec![];
+        for expr in aggregates {
+            let (name, args) = match expr {
+                Expr::Function { name, args } => (name, args),
+                _ => unreachable!(),
+            };
+            if args.len() != 1 {
+                return Err(QrustError::Plan(format!(
+                    "{} expects exactly one argument",
+                    name
+                )));
+            }
+            let arg = self.sql_to_expr(&args[0], scope)?;
+            let aggregate = match name.to_lowercase().as_str() {
+                "sum" => AggregateExpression::sum(arg),
+                "min" => AggregateExpression::min(arg),
+                "max" => AggregateExpression::max(arg),
+                "avg" => AggregateExpression::avg(arg),
+                _ => unreachable!(),
+            };
+            aggregateexpr.push(Arc::new(aggregate.with_alias(expr.to_string())));
+        }
+
+        let aggregate = Arc::new(Aggregate::new(input, groupexpr, aggregateexpr));
+        let schema = aggregate.schema();
+        let aggregated = group_by
+            .iter()
+            .chain(aggregates)
+            .zip(schema.fields())
+            .map(|(e, f)| (e.to_string(), f.name().clone()))
+            .collect();
+        Ok((aggregate, aggregated))
+    }
+
+    fn projection_to_plan(
+        &self,
+        input: Arc<dyn LogicalPlan>,
+        projection: &[SelectItem],
+        scope: &Scope,
+    ) -> Result<Arc<dyn LogicalPlan>> {
+        let mut expr: Vec<Arc<dyn LogicalExpression>> = vec![];
+        for item in projection {
+            match item {
+                SelectItem::Wildcard => {
+                    for field in scope.schema.fields() {
+                        expr.push(Arc::new(ColumnExpression::new(field.name().clone())));
+                    }
+                }
+                SelectItem::Expr { expr: e, alias } => {
+                    let logical = self.sql_to_expr(e, scope)?;
+                    expr.push(match alias {
+                        Some(alias) => Arc::new(AliasExpression::new(logical, alias.clone())),
+                        None => logical,
+                    });
+                }
+            }
+        }
+
+        // A projection that returns its input unchanged is left out, like it would be
+        // when building the same query with a `DataFrame`.
+        let fields = scope.schema.fields();
+        let is_identity = expr.len() == fields.len()
+            && expr.iter().zip(fields).all(|(e, f)| {
+                e.as_any()
+                    .downcast_ref::<ColumnExpression>()
+                    .is_some_and(|c| c.name() == f.name())
+            });
+        if is_identity {
+            return Ok(input);
+        }
+        Ok(Arc::new(Projection::new(input, expr)))
+    }
+
+    fn sql_to_expr(&self, expr: &Expr, scope: &Scope) -> Result<Arc<dyn LogicalExpression>> {
+        if let Some(aggregated) = &scope.aggregated {
+            if let Some(name) = aggregated.get(&expr.to_string()) {
+                return Ok(Arc::new(ColumnExpression::new(name.clone())));
+            }
+        }
+
+        Ok(match expr {
+            Expr::Identifier(name) => {
+                if scope.aggregated.is_some() {
+                    return Err(QrustError::Plan(format!(
+                        "Column {} must appear in the GROUP BY clause or be used in an aggregate function",
+                        name
+                    )));
+                }
+                if let Some(column) = resolve_column(name, &scope.schema, &scope.qualifiers) {
+                    return Ok(Arc::new(ColumnExpression::new(column)));
+                }
+                let outer = scope.context.outer.as_ref().and_then(|outer| {
+                    resolve_column(name, &outer.schema, &outer.qualifiers)
+                        .map(|column| outer.schema.field_with_name(&column).unwrap().clone())
+                });
+                match outer {
+                    Some(field) => Arc::new(OuterColumnExpression::new(field)),
+                    None => return Err(QrustError::Plan(format!("No field named {}", name))),
+                }
+            }
+            Expr::Integer(i) => Arc::new(LiteralI64Expression::new(*i)),
+            Expr::String(s) => Arc::new(LiteralStringExpression::new(s.clone())),
+            Expr::Boolean(b) => Arc::new(LiteralBooleanExpression::new(*b)),
+            Expr::Placeholder(id) => Arc::new(PlaceholderExpression::new(id.clone(), None)),
+            Expr::BinaryOp { left, op, right } => {
+                let l = self.sql_to_expr(left, scope)?;
+                let r = self.sql_to_expr(right, scope)?;
+                // A parameter takes the type of the value it is combined with.
+                let (l, r) = match op {
+                    BinaryOperator::And | BinaryOperator::Or => (
+                        with_placeholder_type(l, &ArrowDataType::Boolean),
+                        with_placeholder_type(r, &ArrowDataType::Boolean),
+                    ),
+                    _ => {
+                        let input: Arc<dyn LogicalPlan> =
+                            Arc::new(EmptyRelation::new(Arc::clone(&scope.schema)));
+                        let l_type = l.to_field(Arc::clone(&input)).data_type().clone();
+                        let r_type = r.to_field(input).data_type().clone();
+                        (
+                            with_placeholder_type(l, &r_type),
+                            with_placeholder_type(r, &l_type),
+                        )
+                    }
+                };
+                match op {
+                    BinaryOperator::Eq => Arc::new(BooleanExpression::eq(l, r)),
+                    BinaryOperator::NotEq => Arc::new(BooleanExpression::neq(l, r)),
+                    BinaryOperator::Lt => Arc::new(BooleanExpression::lt(l, r)),
+                    BinaryOperator::LtEq => Arc::new(BooleanExpression::lteq(l, r)),
+                    BinaryOperator::Gt => Arc::new(BooleanExpression::gt(l, r)),
+                    BinaryOperator::GtEq => Arc::new(BooleanExpression::gteq(l, r)),
+                    BinaryOperator::And => Arc::new(BooleanExpression::and(l, r)),
+                    BinaryOperator::Or => Arc::new(BooleanExpression::or(l, r)),
+                    BinaryOperator::Plus => Arc::new(MathExpression::add(l, r)),
+                    BinaryOperator::Minus => Arc::new(MathExpression::subtract(l, r)),
+                    BinaryOperator::Multiply => Arc::new(MathExpression::multiply(l, r)),
+                    BinaryOperator::Divide => Arc::new(MathExpression::divide(l, r)),
+                    BinaryOperator::Modulo => Arc::new(MathExpression::modulus(l, r)),
+                }
+            }
+            // Negated subquery tests are kept as single expressions, which is the form
+            // that they are decorrelated from.
+            Expr::Not(inner) => match inner.as_ref() {
+                Expr::Exists(subquery) => Arc::new(ExistsExpression::new(
+                    self.subquery_to_plan(subquery, scope)?,
+                    true,
+                )),
+                Expr::InSubquery {
+                    expr,
+                    subquery,
+                    negated,
+                } => self.in_subquery_to_expr(expr, subquery, !negated, scope)?,
+                _ => Arc::new(NotExpression::new(with_placeholder_type(
+                    self.sql_to_expr(inner, scope)?,
+                    &ArrowDataType::Boolean,
+                ))),
+            },
+            Expr::Negative(expr) => match expr.as_ref() {
+                Expr::Integer(i) => Arc::new(LiteralI64Expression::new(-i)),
+                _ => Arc::new(MathExpression::subtract(
+                    Arc::new(LiteralI64Expression::new(0)),
+                    with_placeholder_type(self.sql_to_expr(expr, scope)?, &ArrowDataType::Int64),
+                )),
+            },
+            Expr::Function { name, .. } => {
+                if is_aggregate_function(name) {
+                    return Err(QrustError::Plan(format!(
+                        "Aggregate function {} is not allowed in {}",
+                        expr,
+                        if scope.aggregated.is_some() {
+                            "this context"
+                        } else {
+                            "WHERE or GROUP BY"
+                        }
+                    )));
+                }
+                return Err(QrustError::NotImplemented(format!(
+                    "Unsupported function {}",
+                    name
+                )));
+            }
+            Expr::Nested(expr) => self.sql_to_expr(expr, scope)?,
+            Expr::InSubquery {
+                expr,
+                subquery,
+                negated,
+            } => self.in_subquery_to_expr(expr, subquery, *negated, scope)?,
+            Expr::Between {
+                expr,
+                negated,
+                low,
+                high,
+            } => {
+                // Planned as the comparisons with both bounds that it stands for.
+                let (lower, upper, combine) = if *negated {
+                    (BinaryOperator::Lt, BinaryOperator::Gt, BinaryOperator::Or)
+                } else {
+                    (
+                        BinaryOperator::GtEq,
+                        BinaryOperator::LtEq,
+                        BinaryOperator::And,
+                    )
+                };
+                let compare = |op, bound: &Expr| Expr::BinaryOp {
+                    left: expr.clone(),
+                    op,
+                    right: Box::new(bound.clone()),
+                };
+                let comparisons = Expr::BinaryOp {
+                    left: Box::new(compare(lower, low)),
+                    op: combine,
+                    right: Box::new(compare(upper, high)),
+                };
+                self.sql_to_expr(&comparisons, scope)?
+            }
+            Expr::Exists(subquery) => Arc::new(ExistsExpression::new(
+                self.subquery_to_plan(subquery, scope)?,
+                false,
+            )),
+            Expr::Subquery(subquery) => {
+                let plan = self.subquery_to_plan(subquery, scope)?;
+                expect_single_column(&plan, expr)?;
+                Arc::new(ScalarSubqueryExpression::new(plan))
+            }
+        })
+    }
+
+    fn in_subquery_to_expr(
+        &self,
+        expr: &Expr,
+        subquery: &Query,
+        negated: bool,
+        scope: &Scope,
+    ) -> Result<Arc<dyn LogicalExpression>> {
+        let plan = self.subquery_to_plan(subquery, scope)?;
+        expect_single_column(&plan, subquery)?;
+        Ok(Arc::new(InSubqueryExpression::new(
+            self.sql_to_expr(expr, scope)?,
+            plan,
+            negated,
+        )))
+    }
+
+    /// Plans a subquery used in an expression, which may refer to the columns of
+    /// `scope`.
+    fn subquery_to_plan(&self, subquery: &Query, scope: &Scope) -> Result<Arc<dyn LogicalPlan>> {
+        let context = PlannerContext {
+            ctes: scope.context.ctes.clone(),
+            outer: Some(Arc::new(OuterRelation {
+                schema: Arc::clone(&scope.schema),
+                qualifiers: scope.qualifiers.clone(),
+            })),
+        };
+        self.plan_query(subquery, &context)
+    }
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn quote_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Gives `expr` the type `data_type` if it is a parameter whose type isn't known yet.
+/// Any other expression is returned unchanged.
+fn with_placeholder_type(
+    expr: Arc<dyn LogicalExpression>,
+    data_type: &ArrowDataType,
+) -> Arc<dyn LogicalExpression> {
+    match expr.as_any().downcast_ref::<PlaceholderExpression>() {
+        Some(p) if p.data_type().is_none() && *data_type != ArrowDataType::Null => Arc::new(
+            PlaceholderExpression::new(p.id().to_owned(), Some(data_type.clone())),
+        ),
+        _ => expr,
+    }
+}
+
+fn expect_single_column(plan: &Arc<dyn LogicalPlan>, sql: &dyn std::fmt::Display) -> Result<()> {
+    let columns = plan.schema().fields().len();
+    if columns != 1 {
+        return Err(QrustError::Plan(format!(
+            "Subquery must return exactly one column but returns {}: {}",
+            columns, sql
+        )));
+    }
+    Ok(())
+}
+
+/// Builds the schema of a table from its column definitions.
+fn columns_to_schema(columns: &[ColumnDef]) -> Result<Arc<Schema>> {
+    let mut fields: Vec<Field> = vec![];
+    for column in columns {
+        if fields.iter().any(|f| f.name() == &column.name) {
+            return Err(QrustError::Plan(format!(
+                "Column {} is defined more than once",
+                column.name
+            )));
+        }
+        let data_type = match column.data_type {
+            DataType::BigInt | DataType::Int => ArrowType::Int64Type,
+            DataType::Varchar | DataType::Text => ArrowType::StringType,
+        };
+        fields.push(Field::new(&column.name, data_type.into(), column.nullable));
+    }
+    Ok(Arc::new(Schema::new(fields)))
+}
+
+/// Collects the conditions of `expr` that are combined with `AND`.
+fn split_conjunction<'e>(expr: &'e Expr, conditions: &mut Vec<&'e Expr>) {
+    match expr {
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::And,
+            right,
+        } => {
+            split_conjunction(left, conditions);
+            split_conjunction(right, conditions);
+        }
+        Expr::Nested(expr) => split_conjunction(expr, conditions),
+        _ => conditions.push(expr),
+    }
+}
+
+/// Finds the name of the field of a relation that `name` refers to. Columns of a table
+/// that isn't aliased have no relation in the schema, and are qualified with a name in
+/// `qualifiers` instead.
+fn resolve_column(name: &ObjectName, schema: &Schema, qualifiers: &[String]) -> Option<String> {
+    let has_field = |name: &str| schema.fields().iter().any(|field| field.name() == name);
+    match name.0.as_slice() {
+        [column] => has_field(column).then(|| column.clone()),
+        [qualifier, column] => {
+            // A column that both sides of a join have is named after its side, and other
+            // columns of an alias belong to its relation.
+            field_name(schema, &format!("{}.{}", qualifier, column)).or_else(|| {
+                let unqualified = schema
+                    .fields()
+                    .iter()
+                    .any(|f| f.name() == column && subquery_alias::qualifier(f).is_none());
+                (qualifiers.contains(qualifier) && unqualified).then(|| column.clone())
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Puts a side of a join under the name its columns are qualified with, so that the join
+/// can tell apart the columns both sides have.
+fn with_relation_name(plan: Arc<dyn LogicalPlan>, qualifiers: &[String]) -> Arc<dyn LogicalPlan> {
+    match qualifiers {
+        [name] if relation_name(&plan.schema()).as_ref() != Some(name) => {
+            Arc::new(SubqueryAlias::new(plan, name.clone()))
+        }
+        _ => plan,
+    }
+}
+
+fn is_aggregate_function(name: &str) -> bool {
+    matches!(name.to_lowercase().as_str(), "sum" | "min" | "max" | "avg")
+}
+
+/// The number of rows in a `LIMIT` or `OFFSET` clause.
+fn row_count(expr: &Expr, clause: &str) -> Result<usize> {
+    match expr {
+        Expr::Integer(n) if *n >= 0 => Ok(*n as usize),
+        _ => Err(QrustError::Plan(format!(
+            "{} must be a non-negative integer, got {}",
+            clause, expr
+        ))),
+    }
+}
+
+/// Collects the distinct aggregate function calls in `expr`.
+fn find_aggregates(expr: &Expr, aggregates: &mut Vec<Expr>) -> Result<()> {
+    match expr {
+        Expr::Function { name, args } if is_aggregate_function(name) => {
+            let mut nested = vec![];
+            for arg in args {
+                find_aggregates(arg, &mut nested)?;
+            }
+            if !nested.is_empty() {
+                return Err(QrustError::Plan(format!(
+                    "Aggregate functions can't be nested: {}",
+                    expr
+                )));
+            }
+            if !aggregates.iter().any(|a| a.to_string() == expr.to_string()) {
+                aggregates.push(expr.clone());
+            }
+        }
+        Expr::Function { args, .. } => {
+            for arg in args {
+                find_aggregates(arg, aggregates)?;
+            }
+        }
+        Expr::BinaryOp { left, right, .. } => {
+            find_aggregates(left, aggregates)?;
+            find_aggregates(right, aggregates)?;
+        }
+        Expr::Not(expr)
+        | Expr::Negative(expr)
+        | Expr::Nested(expr)
+        | Expr::InSubquery { expr, .. } => {
+            find_aggregates(expr, aggregates)?;
+        }
+        Expr::Between {
+            expr, low, high, ..
+        } => {
+            find_aggregates(expr, aggregates)?;
+            find_aggregates(low, aggregates)?;
+            find_aggregates(high, aggregates)?;
+        }
+        // Aggregates inside a subquery belong to the subquery.
+        Expr::Exists(_) | Expr::Subquery(_) => {}
+        Expr::Identifier(_)
+        | Expr::Integer(_)
+        | Expr::String(_)
+        | Expr::Boolean(_)
+        | Expr::Placeholder(_) => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data_source::CsvDataSource;
+    use crate::core::data_type::ArrowType;
+    use crate::core::helper::schema;
+
+    struct TestProvider {}
+
+    impl ContextProvider for TestProvider {
+        fn get_table_source(&self, name: &[String]) -> Option<Arc<dyn DataSource>> {
+            if name != ["sales".to_owned()] {
+                return None;
+            }
+            let schema = schema(vec![
+                ("region", ArrowType::StringType, false),
+                ("product", ArrowType::StringType, false),
+                ("amount", ArrowType::Int64Type, false),
+            ]);
+            Some(Arc::new(CsvDataSource::new(schema, "sales.csv".to_owned())))
+        }
+    }
+
+    fn plan(sql: &str) -> Result<String> {
+        let statement = Parser::parse_sql(sql)?.remove(0);
+        Ok(SqlPlanner::new(&TestProvider {})
+            .statement_to_plan(&statement)?
+            .format())
+    }
+
+    fn plan_err(sql: &str) -> String {
+        plan(sql).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn select_where() {
+        assert_eq!(
+            plan("SELECT s.region, amount * 2 AS double FROM sales s WHERE amount > 10 AND NOT product = 'x'")
+                .unwrap(),
+            "Projection: region, amount * 2 AS double
+  Filter: amount>10 AND NOT product='x'
+    Scan: sales, projection=None
+"
+        );
+        assert_eq!(
+            plan("SELECT * FROM sales WHERE amount < -1").unwrap(),
+            "Filter: amount<-1\n  Scan: sales, projection=None\n"
+        );
+    }
+
+    #[test]
+    fn group_by_having() {
+        assert_eq!(
+            plan("SELECT region, SUM(amount), avg(amount) FROM sales GROUP BY region").unwrap(),
+            "Aggregate: groupExpr=region, aggregateExpr=sum(amount), avg(amount)
+  Scan: sales, projection=None
+"
+        );
+        assert_eq!(
+            plan(
+                "SELECT amount % 2 AS parity, max(amount) - min(amount) AS spread FROM sales \
+                 GROUP BY amount % 2 HAVING sum(amount) > 10"
+            )
+            .unwrap(),
+            "Projection: amount % 2 AS parity, max(amount) - min(amount) AS spread
+  Filter: sum(amount)>10
+    Aggregate: groupExpr=amount % 2 AS amount % 2, aggregateExpr=max(amount), min(amount), sum(amount)
+      Scan: sales, projection=None
+"
+        );
+        assert_eq!(
+            plan("SELECT sum(amount) FROM sales").unwrap(),
+            "Aggregate: groupExpr=, aggregateExpr=sum(amount)\n  Scan: sales, projection=None\n"
+        );
+    }
+
+    #[test]
+    fn planning_errors() {
+        assert_eq!(
+            plan_err("SELECT a FROM missing"),
+            "Planning error: Table not found: missing"
+        );
+        assert_eq!(
+            plan_err("SELECT price FROM sales"),
+            "Planning error: No field named price"
+        );
+        assert_eq!(
+            plan_err("SELECT x.region FROM sales"),
+            "Planning error: No field named x.region"
+        );
+        assert_eq!(
+            plan_err("SELECT product, sum(amount) FROM sales GROUP BY region"),
+            "Planning error: Column product must appear in the GROUP BY clause or be used in an aggregate function"
+        );
+        assert_eq!(
+            plan_err("SELECT region FROM sales WHERE sum(amount) > 1"),
+            "Planning error: Aggregate function sum(amount) is not allowed in WHERE or GROUP BY"
+        );
+        assert_eq!(
+            plan_err("SELECT sum(max(amount)) FROM sales"),
+            "Planning error: Aggregate functions can't be nested: sum(max(amount))"
+        );
+        assert_eq!(
+            plan_err("SELECT region FROM sales HAVING region = 'a'"),
+            "Planning error: HAVING requires GROUP BY or an aggregate function"
+        );
+        assert_eq!(
+            plan_err("SELECT region FROM sales LIMIT -1"),
+            "Planning error: LIMIT must be a non-negative integer, got -1"
+        );
+        assert_eq!(
+            plan_err("SELECT region FROM sales ORDER BY price"),
+            "Planning error: No field named price"
+        );
+    }
+
+    #[test]
+    fn order_by_limit_offset() {
+        assert_eq!(
+            plan("SELECT region, amount * 2 AS double FROM sales ORDER BY double DESC, region NULLS FIRST LIMIT 10 OFFSET 5")
+                .unwrap(),
+            "Limit: skip=5, fetch=10
+  Sort: double DESC NULLS LAST, region ASC NULLS FIRST
+    Projection: region, amount * 2 AS double
+      Scan: sales, projection=None
+"
+        );
+        // Columns that are not selected are sorted on before projecting.
+        assert_eq!(
+            plan("SELECT region FROM sales s ORDER BY s.amount OFFSET 3").unwrap(),
+            "Limit: skip=3, fetch=None
+  Projection: region
+    Sort: amount ASC NULLS LAST
+      Scan: sales, projection=None
+"
+        );
+        assert_eq!(
+            plan(
+                "SELECT region, sum(amount) AS total FROM sales GROUP BY region \
+                 ORDER BY sum(amount) DESC LIMIT 1"
+            )
+            .unwrap(),
+            "Limit: skip=0, fetch=1
+  Projection: region, sum(amount) AS total
+    Sort: sum(amount) DESC NULLS LAST
+      Aggregate: groupExpr=region, aggregateExpr=sum(amount)
+        Scan: sales, projection=None
+"
+        );
+        assert_eq!(
+            plan("SELECT * FROM sales ORDER BY amount").unwrap(),
+            "Sort: amount ASC NULLS LAST\n  Scan: sales, projection=None\n"
+        );
+    }
+
+    #[test]
+    fn ctes_and_derived_tables() {
+        assert_eq!(
+            plan(
+                "WITH totals AS (SELECT region, sum(amount) AS total FROM sales GROUP BY region), \
+                 big AS (SELECT region FROM totals WHERE total > 100) \
+                 SELECT b.region FROM big b"
+            )
+            .unwrap(),
+            "SubqueryAlias: b
+  Projection: region
+    Filter: total>100
+      SubqueryAlias: totals
+        Projection: region, sum(amount) AS total
+          Aggregate: groupExpr=region, aggregateExpr=sum(amount)
+            Scan: sales, projection=None
+"
+        );
+        assert_eq!(
+            plan("SELECT t.double FROM (SELECT amount * 2 AS double FROM sales) AS t").unwrap(),
+            "SubqueryAlias: t
+  Projection: amount * 2 AS double
+    Scan: sales, projection=None
+"
+        );
+        // A common table expression shadows a table of the same name.
+        assert_eq!(
+            plan("WITH sales AS (SELECT region FROM sales) SELECT * FROM sales").unwrap(),
+            "SubqueryAlias: sales\n  Projection: region\n    Scan: sales, projection=None\n"
+        );
+
+        assert_eq!(
+            plan_err("SELECT sales.region FROM (SELECT region FROM sales) AS t"),
+            "Planning error: No field named sales.region"
+        );
+        assert_eq!(
+            plan_err("SELECT amount FROM (SELECT region FROM sales) AS t"),
+            "Planning error: No field named amount"
+        );
+        assert_eq!(
+            plan_err(
+                "WITH a AS (SELECT region FROM b), b AS (SELECT region FROM sales) SELECT * FROM a"
+            ),
+            "Planning error: Table not found: b"
+        );
+        assert_eq!(
+            plan_err("WITH a AS (SELECT region FROM sales), a AS (SELECT region FROM sales) SELECT * FROM a"),
+            "Planning error: WITH query name a is specified more than once"
+        );
+    }
+
+    #[test]
+    fn joins() {
+        assert_eq!(
+            plan(
+                "SELECT s.product, t.total FROM sales s \
+                 JOIN (SELECT region AS r, sum(amount) AS total FROM sales GROUP BY region) t \
+                 ON t.r = s.region AND t.total > s.amount"
+            )
+            .unwrap(),
+            "Projection: product, total
+  Join: joinType=Inner, on=[region=r], filter=total>amount
+    SubqueryAlias: s
+      Scan: sales, projection=None
+    SubqueryAlias: t
+      Projection: region AS r, sum(amount) AS total
+        Aggregate: groupExpr=region, aggregateExpr=sum(amount)
+          Scan: sales, projection=None
+"
+        );
+        assert_eq!(
+            plan("SELECT * FROM sales LEFT JOIN (SELECT region AS r FROM sales) t ON region = r")
+                .unwrap(),
+            "Join: joinType=Left, on=[region=r]
+  SubqueryAlias: sales
+    Scan: sales, projection=None
+  SubqueryAlias: t
+    Projection: region AS r
+      Scan: sales, projection=None
+"
+        );
+
+        assert_eq!(
+            plan("SELECT * FROM sales a JOIN (SELECT region AS r FROM sales) b ON TRUE AND a.amount > 1")
+                .unwrap(),
+            "Join: joinType=Inner, on=[], filter=amount>1
+  SubqueryAlias: a
+    Scan: sales, projection=None
+  SubqueryAlias: b
+    Projection: region AS r
+      Scan: sales, projection=None
+"
+        );
+        assert_eq!(
+            plan(
+                "SELECT * FROM sales a RIGHT JOIN (SELECT region AS r FROM sales) b \
+                 ON region = r AND r = 'x'"
+            )
+            .unwrap(),
+            "Join: joinType=Right, on=[region=r], filter=r='x'
+  SubqueryAlias: a
+    Scan: sales, projection=None
+  SubqueryAlias: b
+    Projection: region AS r
+      Scan: sales, projection=None
+"
+        );
+
+        // Columns that both sides have are qualified with the name of their side.
+        assert_eq!(
+            plan(
+                "SELECT a.product, b.region FROM sales a FULL JOIN sales b \
+                 ON a.region = b.region AND a.amount < b.amount"
+            )
+            .unwrap(),
+            "Projection: a.product, b.region
+  Join: joinType=Full, on=[region=region], filter=a.amount<b.amount
+    SubqueryAlias: a
+      Scan: sales, projection=None
+    SubqueryAlias: b
+      Scan: sales, projection=None
+"
+        );
+        // Range conditions become the filter of a join without keys.
+        assert_eq!(
+            plan(
+                "SELECT * FROM sales a CROSS JOIN (SELECT amount AS low FROM sales) b \
+                 JOIN (SELECT amount AS high FROM sales) c \
+                 ON a.amount NOT BETWEEN b.low AND c.high"
+            )
+            .unwrap(),
+            "Join: joinType=Inner, on=[], filter=amount<low OR amount>high
+  Join: joinType=Inner, on=[]
+    SubqueryAlias: a
+      Scan: sales, projection=None
+    SubqueryAlias: b
+      Projection: amount AS low
+        Scan: sales, projection=None
+  SubqueryAlias: c
+    Projection: amount AS high
+      Scan: sales, projection=None
+"
+        );
+        assert_eq!(
+            plan_err("SELECT region FROM sales a JOIN sales b ON a.region = b.region"),
+            "Planning error: No field named region"
+        );
+        assert_eq!(
+            plan_err("SELECT * FROM sales JOIN sales ON sales.region = sales.region"),
+            "Planning error: Both sides of the join have a column named region"
+        );
+    }
+}
@@ -0,0 +1,392 @@
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::core::error::{QrustError, Result};
+
+/// The position of a token in the SQL text. Lines and columns start at 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    /// A keyword or identifier. `quoted` is set for identifiers written in double quotes,
+    /// which are never treated as keywords.
+    Word {
+        value: String,
+        quoted: bool,
+    },
+    Number(String),
+    /// A string literal written in single quotes, without the quotes.
+    String(String),
+    /// A parameter of a prepared statement, either positional like `$1` or named like
+    /// `$id`. Holds the text after the `$`.
+    Placeholder(String),
+    Comma,
+    Period,
+    Semicolon,
+    LParen,
+    RParen,
+    Eq,
+    Neq,
+    Lt,
+    Gt,
+    LtEq,
+    GtEq,
+    Plus,
+    Minus,
+    Mul,
+    Div,
+    Mod,
+    Eof,
+}
+
+impl Token {
+    /// Returns true if this is the unquoted keyword `keyword`, ignoring case.
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        match self {
+            Token::Word {
+                value,
+                quoted: false,
+            } => value.eq_ignore_ascii_case(keyword),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word {
+                value,
+                quoted: false,
+            } => write!(f, "{}", value),
+            Token::Word {
+                value,
+                quoted: true,
+            } => write!(f, "\"{}\"", value),
+            Token::Number(n) => write!(f, "{}", n),
+            Token::String(s) => write!(f, "'{}'", s),
+            Token::Placeholder(p) => write!(f, "${}", p),
+            Token::Comma => write!(f, ","),
+            Token::Period => write!(f, "."),
+            Token::Semicolon => write!(f, ";"),
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+            Token::Eq => write!(f, "="),
+            Token::Neq => write!(f, "!="),
+            Token::Lt => write!(f, "<"),
+            Token::Gt => write!(f, ">"),
+            Token::LtEq => write!(f, "<="),
+            Token::GtEq => write!(f, ">="),
+            Token::Plus => write!(f, "+"),
+            Token::Minus => write!(f, "-"),
+            Token::Mul => write!(f, "*"),
+            Token::Div => write!(f, "/"),
+            Token::Mod => write!(f, "%"),
+            Token::Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+/// Splits SQL text into tokens, remembering where each token starts.
+pub struct Tokenizer<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(sql: &'a str) -> Tokenizer<'a> {
+        Tokenizer {
+            chars: sql.chars().peekable(),
+            line: 1,
+            column: 1,
+        }
+    }
+
+    /// Tokenizes the whole input. The last token is always `Token::Eof`.
+    pub fn tokenize(&mut self) -> Result<Vec<(Token, Location)>> {
+        let mut tokens = vec![];
+        loop {
+            self.skip_whitespace_and_comments();
+            let location = self.location();
+            let token = self.next_token(location)?;
+            let done = token == Token::Eof;
+            tokens.push((token, location));
+            if done {
+                return Ok(tokens);
+            }
+        }
+    }
+
+    fn location(&self) -> Location {
+        Location {
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        loop {
+            match self.chars.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.next_char();
+                }
+                Some('-') => {
+                    // `--` starts a comment that runs to the end of the line.
+                    let mut lookahead = self.chars.clone();
+                    lookahead.next();
+                    if lookahead.next() != Some('-') {
+                        return;
+                    }
+                    while let Some(c) = self.next_char() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn next_token(&mut self, location: Location) -> Result<Token> {
+        let c = match self.next_char() {
+            Some(c) => c,
+            None => return Ok(Token::Eof),
+        };
+        let token = match c {
+            ',' => Token::Comma,
+            '.' => Token::Period,
+            ';' => Token::Semicolon,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '*' => Token::Mul,
+            '/' => Token::Div,
+            '%' => Token::Mod,
+            '=' => Token::Eq,
+            '!' => match self.chars.peek() {
+                Some('=') => {
+                    self.next_char();
+                    Token::Neq
+                }
+                _ => return Err(tokenizer_error("Expected = after !", location)),
+            },
+            '<' => match self.chars.peek() {
+                Some('=') => {
+                    self.next_char();
+                    Token::LtEq
+                }
+                Some('>') => {
+                    self.next_char();
+                    Token::Neq
+                }
+                _ => Token::Lt,
+            },
+            '>' => match self.chars.peek() {
+                Some('=') => {
+                    self.next_char();
+                    Token::GtEq
+                }
+                _ => Token::Gt,
+            },
+            '\'' => Token::String(self.quoted('\'', location)?),
+            '$' => {
+                let id = self.take_while(|c| c.is_alphanumeric() || c == '_');
+                if id.is_empty() {
+                    return Err(tokenizer_error(
+                        "Expected a parameter number or name after $",
+                        location,
+                    ));
+                }
+                Token::Placeholder(id)
+            }
+            '"' => Token::Word {
+                value: self.quoted('"', location)?,
+                quoted: true,
+            },
+            c if c.is_ascii_digit() => {
+                let mut number = c.to_string();
+                number.push_str(&self.take_while(|c| c.is_ascii_digit()));
+                Token::Number(number)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut word = c.to_string();
+                word.push_str(&self.take_while(|c| c.is_alphanumeric() || c == '_'));
+                Token::Word {
+                    value: word,
+                    quoted: false,
+                }
+            }
+            c => {
+                return Err(tokenizer_error(
+                    &format!("Unexpected character {}", c),
+                    location,
+                ))
+            }
+        };
+        Ok(token)
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let mut s = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if !predicate(c) {
+                break;
+            }
+            s.push(c);
+            self.next_char();
+        }
+        s
+    }
+
+    /// Reads the rest of a quoted string. A doubled quote stands for the quote itself.
+    fn quoted(&mut self, quote: char, location: Location) -> Result<String> {
+        let mut s = String::new();
+        loop {
+            match self.next_char() {
+                Some(c) if c == quote => {
+                    if self.chars.peek() == Some(&quote) {
+                        self.next_char();
+                        s.push(quote);
+                    } else {
+                        return Ok(s);
+                    }
+                }
+                Some(c) => s.push(c),
+                None => return Err(tokenizer_error("Unterminated quoted string", location)),
+            }
+        }
+    }
+}
+
+fn tokenizer_error(message: &str, location: Location) -> QrustError {
+    QrustError::Parse {
+        message: message.to_owned(),
+        line: location.line,
+        column: location.column,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(sql: &str) -> Vec<Token> {
+        Tokenizer::new(sql)
+            .tokenize()
+            .unwrap()
+            .into_iter()
+            .map(|(t, _)| t)
+            .collect()
+    }
+
+    fn word(value: &str) -> Token {
+        Token::Word {
+            value: value.to_owned(),
+            quoted: false,
+        }
+    }
+
+    #[test]
+    fn tokenize_select() {
+        assert_eq!(
+            tokens("SELECT a, b+1 FROM t WHERE c <> 'it''s' -- trailing\n AND d>=10;"),
+            vec![
+                word("SELECT"),
+                word("a"),
+                Token::Comma,
+                word("b"),
+                Token::Plus,
+                Token::Number("1".to_owned()),
+                word("FROM"),
+                word("t"),
+                word("WHERE"),
+                word("c"),
+                Token::Neq,
+                Token::String("it's".to_owned()),
+                word("AND"),
+                word("d"),
+                Token::GtEq,
+                Token::Number("10".to_owned()),
+                Token::Semicolon,
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn quoted_identifiers() {
+        let t = tokens("\"Select\"");
+        assert_eq!(
+            t[0],
+            Token::Word {
+                value: "Select".to_owned(),
+                quoted: true
+            }
+        );
+        assert!(!t[0].is_keyword("select"));
+        assert!(word("select").is_keyword("SELECT"));
+    }
+
+    #[test]
+    fn placeholders() {
+        assert_eq!(
+            tokens("a = $1 AND b = $name"),
+            vec![
+                word("a"),
+                Token::Eq,
+                Token::Placeholder("1".to_owned()),
+                word("AND"),
+                word("b"),
+                Token::Eq,
+                Token::Placeholder("name".to_owned()),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn locations() {
+        let t = Tokenizer::new("SELECT\n  a").tokenize().unwrap();
+        assert_eq!(t[1].1, Location { line: 2, column: 3 });
+    }
+
+    #[test]
+    fn errors() {
+        let err = Tokenizer::new("SELECT\n 'abc").tokenize().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parse error at line 2, column 2: Unterminated quoted string"
+        );
+
+        let err = Tokenizer::new("SELECT a # b").tokenize().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parse error at line 1, column 10: Unexpected character #"
+        );
+
+        let err = Tokenizer::new("SELECT $ FROM t").tokenize().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parse error at line 1, column 8: Expected a parameter number or name after $"
+        );
+    }
+}
@@ -0,0 +1,943 @@
+use arrow::datatypes::DataType as ArrowDataType;
+use std::sync::Arc;
+
+use super::ast::*;
+use crate::core::error::{QrustError, Result};
+use crate::logical_plan::aggregate::Aggregate;
+use crate::logical_plan::ddl::{CreateExternalTable, DropTable};
+use crate::logical_plan::explain::Explain;
+use crate::logical_plan::expression::{
+    AggregateExpression, AliasExpression, BinaryExpression, BooleanExpression, ColumnExpression,
+    ExistsExpression, InSubqueryExpression, LiteralBooleanExpression, LiteralI64Expression,
+    LiteralStringExpression, LogicalExpression, MathExpression, NotExpression,
+    OuterColumnExpression, PlaceholderExpression, ScalarSubqueryExpression,
+};
+use crate::logical_plan::join::{Join, JoinType};
+use crate::logical_plan::limit::Limit;
+use crate::logical_plan::projection::Projection;
+use crate::logical_plan::scan::Scan;
+use crate::logical_plan::selection::Selection;
+use crate::logical_plan::sort::Sort;
+use crate::logical_plan::subquery_alias::SubqueryAlias;
+use crate::logical_plan::LogicalPlan;
+use crate::optimizer::utils::exprlist_columns;
+
+// Operator precedence, from loosest to tightest binding, as the parser reads it.
+const OR_PRECEDENCE: u8 = 5;
+const AND_PRECEDENCE: u8 = 10;
+const NOT_PRECEDENCE: u8 = 15;
+const COMPARISON_PRECEDENCE: u8 = 20;
+const PLUS_MINUS_PRECEDENCE: u8 = 30;
+const MUL_DIV_MOD_PRECEDENCE: u8 = 40;
+
+/// Turns `plan` back into SQL that produces the same rows when planned again.
+pub fn plan_to_sql(plan: &Arc<dyn LogicalPlan>) -> Result<String> {
+    Ok(plan_to_statement(plan)?.to_string())
+}
+
+/// Turns `plan` back into a SQL statement. Queries become a single `SELECT`, nesting
+/// subqueries in the `FROM` clause where a plan applies operations in an order that
+/// one `SELECT` can't express.
+pub fn plan_to_statement(plan: &Arc<dyn LogicalPlan>) -> Result<Statement> {
+    let any = plan.as_any();
+    let mut unparser = Unparser::default();
+    if let Some(explain) = any.downcast_ref::<Explain>() {
+        Ok(Statement::Explain {
+            analyze: explain.analyze(),
+            query: Box::new(unparser.query(&explain.input())?),
+        })
+    } else if let Some(create) = any.downcast_ref::<CreateExternalTable>() {
+        let columns = create
+            .table_schema()
+            .fields()
+            .iter()
+            .map(|field| {
+                let data_type = match field.data_type() {
+                    ArrowDataType::Int64 => DataType::BigInt,
+                    ArrowDataType::Utf8 => DataType::Varchar,
+                    other => {
+                        return Err(QrustError::NotImplemented(format!(
+                            "Columns of type {} can't be created in SQL",
+                            other
+                        )))
+                    }
+                };
+                Ok(ColumnDef {
+                    name: field.name().clone(),
+                    data_type,
+                    nullable: field.is_nullable(),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Statement::CreateExternalTable {
+            name: object_name(create.name()),
+            columns,
+            file_type: "CSV".to_owned(),
+            has_header: create.has_header(),
+            delimiter: match create.delimiter() {
+                b',' => None,
+                delimiter => Some(delimiter as char),
+            },
+            location: create.location().to_owned(),
+            if_not_exists: create.if_not_exists(),
+        })
+    } else if let Some(drop) = any.downcast_ref::<DropTable>() {
+        Ok(Statement::DropTable {
+            name: object_name(drop.name()),
+            if_exists: drop.if_exists(),
+        })
+    } else {
+        Ok(Statement::Query(Box::new(unparser.query(plan)?)))
+    }
+}
+
+#[derive(Default)]
+struct Unparser {
+    /// The number of table aliases generated so far.
+    aliases: usize,
+    /// The alias of the relation that columns of an enclosing query are read from,
+    /// while unparsing a subquery expression.
+    outer: Option<String>,
+}
+
+/// A `SELECT` being built from the bottom of a plan up. Nodes are added to it for as
+/// long as a single `SELECT` can apply them in the order of the plan.
+struct SelectBuilder {
+    from: TableReference,
+    /// The alias of `from`, if subqueries may refer to its columns.
+    alias: Option<String>,
+    /// The SQL of the columns of `from` that are named after the relation they come
+    /// from, such as the columns both sides of a join have.
+    from_columns: Vec<(Expr, String)>,
+    /// The output columns and their names, or `None` for every column of `from`.
+    projection: Option<Vec<(Expr, String)>>,
+    selection: Option<Expr>,
+    group_by: Vec<Expr>,
+    aggregated: bool,
+    having: Option<Expr>,
+    order_by: Vec<OrderByExpr>,
+    /// The names of the output columns that `order_by` refers to.
+    sort_columns: Vec<String>,
+    /// The number of output rows skipped, and how many of the rest are kept.
+    skip: usize,
+    fetch: Option<usize>,
+}
+
+impl SelectBuilder {
+    fn new(from: TableReference) -> SelectBuilder {
+        SelectBuilder {
+            from,
+            alias: None,
+            from_columns: vec![],
+            projection: None,
+            selection: None,
+            group_by: vec![],
+            aggregated: false,
+            having: None,
+            order_by: vec![],
+            sort_columns: vec![],
+            skip: 0,
+            fetch: None,
+        }
+    }
+
+    /// Whether the output is `from` unchanged.
+    fn is_bare(&self) -> bool {
+        self.projection.is_none()
+            && self.selection.is_none()
+            && !self.aggregated
+            && self.order_by.is_empty()
+            && !self.is_limited()
+    }
+
+    /// Whether only some of the output rows are kept.
+    fn is_limited(&self) -> bool {
+        self.skip > 0 || self.fetch.is_some()
+    }
+
+    /// The SQL that computes the output column `name`.
+    fn column(&self, name: &str) -> Expr {
+        self.projection
+            .as_ref()
+            .and_then(|p| p.iter().find(|(_, n)| n == name))
+            .map(|(expr, _)| expr.clone())
+            .unwrap_or_else(|| self.input_column(name))
+    }
+
+    /// The SQL that reads the column `name` of `from`.
+    fn input_column(&self, name: &str) -> Expr {
+        self.from_columns
+            .iter()
+            .find(|(_, n)| n == name)
+            .map(|(expr, _)| expr.clone())
+            .unwrap_or_else(|| identifier(&[name]))
+    }
+
+    /// Whether ORDER BY still sorts on the same values once `columns` replace the
+    /// output columns. Its names refer to the output columns, or if one of them isn't
+    /// among those, all of them refer to the columns of `from` instead.
+    fn keeps_order(&self, columns: &[(Expr, String)]) -> bool {
+        let output = |name: &str| {
+            let find = |name: &str| columns.iter().find(|(_, n)| n == name).map(|(e, _)| e);
+            // A qualified name may also refer to the unqualified output column.
+            find(name).or_else(|| find(name.split_once('.')?.1))
+        };
+        let same_output = |name: &String| output(name) == Some(&self.column(name));
+        let same_input = |name: &String| {
+            let column = self.column(name);
+            column == self.input_column(name) && output(name).is_none_or(|e| *e == column)
+        };
+        self.sort_columns.iter().all(same_output) || self.sort_columns.iter().all(same_input)
+    }
+
+    /// Adds a condition on the output rows.
+    fn filter(&mut self, condition: Expr) {
+        let target = if self.aggregated {
+            &mut self.having
+        } else {
+            &mut self.selection
+        };
+        *target = Some(match target.take() {
+            Some(existing) => binary(existing, BinaryOperator::And, condition),
+            None => condition,
+        });
+    }
+
+    fn into_query(self) -> Query {
+        let projection = match self.projection {
+            None => vec![SelectItem::Wildcard],
+            Some(projection) => projection
+                .into_iter()
+                .map(|(expr, name)| {
+                    let alias = match &expr {
+                        Expr::Identifier(ObjectName(parts))
+                            if parts.last() == Some(&name) || parts.join(".") == name =>
+                        {
+                            None
+                        }
+                        _ => Some(name),
+                    };
+                    SelectItem::Expr { expr, alias }
+                })
+                .collect(),
+        };
+        Query {
+            with: vec![],
+            body: Select {
+                projection,
+                from: Some(self.from),
+                selection: self.selection,
+                group_by: self.group_by,
+                having: self.having,
+            },
+            order_by: self.order_by,
+            limit: self.fetch.map(|fetch| Expr::Integer(fetch as i64)),
+            offset: (self.skip > 0).then_some(Expr::Integer(self.skip as i64)),
+        }
+    }
+}
+
+impl Unparser {
+    fn query(&mut self, plan: &Arc<dyn LogicalPlan>) -> Result<Query> {
+        Ok(self.select(plan)?.into_query())
+    }
+
+    fn select(&mut self, plan: &Arc<dyn LogicalPlan>) -> Result<SelectBuilder> {
+        let any = plan.as_any();
+        if let Some(scan) = any.downcast_ref::<Scan>() {
+            let mut select = SelectBuilder::new(TableReference::Table {
+                name: object_name(scan.path()),
+                alias: None,
+            });
+            for filter in scan.filters() {
+                let condition = self.expr(filter, &select)?;
+                select.filter(condition);
+            }
+            if !scan.projection().is_empty() {
+                let schema = scan.schema();
+                let columns = schema.fields().iter().map(|f| f.name().clone());
+                select.projection = Some(columns.map(|c| (identifier(&[&c]), c)).collect());
+            }
+            Ok(select)
+        } else if let Some(projection) = any.downcast_ref::<Projection>() {
+            let mut select = self.select(&projection.input())?;
+            if projection.expr().iter().any(has_subquery) {
+                select = self.qualified(select);
+            }
+            let mut columns = self.projection_columns(projection, &select)?;
+            // ORDER BY names the output columns, which the projection replaces.
+            if !select.keeps_order(&columns) {
+                select = SelectBuilder::new(self.table(select, None).0);
+                columns = self.projection_columns(projection, &select)?;
+            }
+            select.projection = Some(columns);
+            Ok(select)
+        } else if let Some(selection) = any.downcast_ref::<Selection>() {
+            let mut select = self.select(&selection.input())?;
+            if select.is_limited() {
+                select = SelectBuilder::new(self.table(select, None).0);
+            }
+            if has_subquery(&selection.expr()) {
+                select = self.qualified(select);
+            }
+            let condition = self.expr(&selection.expr(), &select)?;
+            select.filter(condition);
+            Ok(select)
+        } else if let Some(aggregate) = any.downcast_ref::<Aggregate>() {
+            let mut select = self.select(&aggregate.input())?;
+            if select.aggregated || !select.order_by.is_empty() || select.is_limited() {
+                select = SelectBuilder::new(self.table(select, None).0);
+            }
+            let group_by = aggregate
+                .group_expr()
+                .iter()
+                .map(|e| self.expr(e, &select))
+                .collect::<Result<Vec<_>>>()?;
+            let mut columns = group_by.clone();
+            for expr in aggregate.aggregate_expr() {
+                columns.push(self.expr(&(Arc::clone(expr) as _), &select)?);
+            }
+            let schema = aggregate.schema();
+            let names = schema.fields().iter().map(|f| f.name().clone());
+            select.projection = Some(columns.into_iter().zip(names).collect());
+            select.group_by = group_by;
+            select.aggregated = true;
+            Ok(select)
+        } else if let Some(sort) = any.downcast_ref::<Sort>() {
+            let mut select = self.select(&sort.input())?;
+            if select.is_limited() {
+                select = SelectBuilder::new(self.table(select, None).0);
+            }
+            // The sort expressions are written in terms of the output columns, which
+            // ORDER BY can refer to by name.
+            let output = SelectBuilder {
+                alias: select.alias.clone(),
+                from_columns: select.from_columns.clone(),
+                ..SelectBuilder::new(select.from.clone())
+            };
+            let exprs: Vec<_> = sort.expr().iter().map(|e| e.expr()).collect();
+            select.sort_columns = exprlist_columns(&exprs).into_iter().collect();
+            select.order_by = sort
+                .expr()
+                .iter()
+                .map(|e| {
+                    Ok(OrderByExpr {
+                        expr: self.expr(&e.expr(), &output)?,
+                        asc: e.is_asc(),
+                        nulls_first: e.is_nulls_first().then_some(true),
+                    })
+                })
+                .collect::<Result<_>>()?;
+            select.fetch = sort.fetch();
+            Ok(select)
+        } else if let Some(limit) = any.downcast_ref::<Limit>() {
+            // Limits over a limited select are combined into one.
+            let mut select = self.select(&limit.input())?;
+            let fetch = select.fetch.map(|f| f.saturating_sub(limit.skip()));
+            select.skip += limit.skip();
+            select.fetch = match (fetch, limit.fetch()) {
+                (Some(l), Some(r)) => Some(l.min(r)),
+                (l, r) => l.or(r),
+            };
+            Ok(select)
+        } else if let Some(alias) = any.downcast_ref::<SubqueryAlias>() {
+            // Columns are never qualified with the alias, so it can be left out.
+            self.select(&alias.input())
+        } else if let Some(join) = any.downcast_ref::<Join>() {
+            self.join(join)
+        } else {
+            Err(QrustError::NotImplemented(format!(
+                "Can't express {} in SQL",
+                LogicalPlan::to_string(plan.as_ref())
+            )))
+        }
+    }
+
+    fn join(&mut self, join: &Join) -> Result<SelectBuilder> {
+        // Sides are aliased with the names the join qualifies their columns with, so that
+        // the columns keep their names when the SQL is planned again.
+        let (left_name, right_name) = match (join.left_relation(), join.right_relation()) {
+            (Some(l), Some(r)) if l != r => (Some(l), Some(r)),
+            _ => (None, None),
+        };
+        let left = self.select(&join.left())?;
+        let (left, left_alias) = self.table(left, left_name);
+        let right = self.select(&join.right())?;
+        let (right, right_alias) = self.table(right, right_name);
+        let mut on = join
+            .on()
+            .iter()
+            .map(|(l, r)| {
+                binary(
+                    identifier(&[&left_alias, l]),
+                    BinaryOperator::Eq,
+                    identifier(&[&right_alias, r]),
+                )
+            })
+            .reduce(|l, r| binary(l, BinaryOperator::And, r));
+        if let Some(filter) = join.filter() {
+            // The filter refers to the columns of the joined rows by their joined names.
+            let mut columns = vec![];
+            for field in join.left().schema().fields() {
+                let name = field.name();
+                columns.push((identifier(&[&left_alias, name]), join.left_column(name)));
+            }
+            for field in join.right().schema().fields() {
+                let name = field.name();
+                columns.push((identifier(&[&right_alias, name]), join.right_column(name)));
+            }
+            let joined = SelectBuilder {
+                projection: Some(columns),
+                ..SelectBuilder::new(right.clone())
+            };
+            let filter = self.expr(&filter, &joined)?;
+            on = Some(match on {
+                Some(on) => binary(on, BinaryOperator::And, filter),
+                None => filter,
+            });
+        }
+        let operator = match join.join_type() {
+            // Every pair of rows matches.
+            JoinType::Inner if on.is_none() => JoinOperator::Cross,
+            JoinType::Inner => JoinOperator::Inner,
+            JoinType::Left => JoinOperator::Left,
+            JoinType::Right => JoinOperator::Right,
+            JoinType::Full => JoinOperator::Full,
+            // Semi and anti joins are filters on the left side.
+            JoinType::Semi | JoinType::Anti => {
+                let mut subquery = SelectBuilder::new(right);
+                if let Some(on) = on {
+                    subquery.filter(on);
+                }
+                let exists = Expr::Exists(Box::new(subquery.into_query()));
+                let mut select = SelectBuilder::new(left);
+                select.filter(match join.join_type() {
+                    JoinType::Anti => Expr::Not(Box::new(exists)),
+                    _ => exists,
+                });
+                return Ok(select);
+            }
+        };
+        let on = match operator {
+            JoinOperator::Cross => None,
+            _ => Some(on.unwrap_or(Expr::Boolean(true))),
+        };
+        // The columns both sides have are named after their side, which is what its
+        // alias is if the sides have different names.
+        let mut from_columns = vec![];
+        if left_name.is_some() {
+            for field in join.left().schema().fields() {
+                let (name, joined) = (field.name(), join.left_column(field.name()));
+                if *name != joined {
+                    from_columns.push((identifier(&[&left_alias, name]), joined));
+                }
+            }
+            for field in join.right().schema().fields() {
+                let (name, joined) = (field.name(), join.right_column(field.name()));
+                if *name != joined {
+                    from_columns.push((identifier(&[&right_alias, name]), joined));
+                }
+            }
+        }
+        Ok(SelectBuilder {
+            from_columns,
+            ..SelectBuilder::new(TableReference::Join {
+                left: Box::new(left),
+                right: Box::new(right),
+                operator,
+                on,
+            })
+        })
+    }
+
+    /// Turns the expressions of `projection`, evaluated on the output of `select`, into
+    /// SQL with the names of the output columns.
+    fn projection_columns(
+        &mut self,
+        projection: &Projection,
+        select: &SelectBuilder,
+    ) -> Result<Vec<(Expr, String)>> {
+        let schema = projection.schema();
+        projection
+            .expr()
+            .iter()
+            .zip(schema.fields())
+            .map(|(expr, field)| Ok((self.expr(expr, select)?, field.name().clone())))
+            .collect()
+    }
+
+    /// Turns `select` into a relation of a `FROM` clause aliased with `name`, or a new
+    /// alias, which its columns can be qualified with.
+    fn table(&mut self, select: SelectBuilder, name: Option<&str>) -> (TableReference, String) {
+        let alias = match name {
+            Some(name) => name.to_owned(),
+            None => {
+                self.aliases += 1;
+                format!("t{}", self.aliases)
+            }
+        };
+        let table = match select.from {
+            TableReference::Table { name, .. } if select.is_bare() => TableReference::Table {
+                name,
+                alias: Some(alias.clone()),
+            },
+            TableReference::Derived { subquery, .. } if select.is_bare() => {
+                TableReference::Derived {
+                    subquery,
+                    alias: alias.clone(),
+                }
+            }
+            _ => TableReference::Derived {
+                subquery: Box::new(select.into_query()),
+                alias: alias.clone(),
+            },
+        };
+        (table, alias)
+    }
+
+    /// Reads the output of `select` from an aliased relation, so that subqueries can
+    /// refer to its columns.
+    fn qualified(&mut self, select: SelectBuilder) -> SelectBuilder {
+        let (table, alias) = self.table(select, None);
+        SelectBuilder {
+            alias: Some(alias),
+            ..SelectBuilder::new(table)
+        }
+    }
+
+    /// Unparses a subquery of an expression evaluated on the output of `select`.
+    fn subquery(
+        &mut self,
+        plan: &Arc<dyn LogicalPlan>,
+        select: &SelectBuilder,
+    ) -> Result<Box<Query>> {
+        let outer = std::mem::replace(&mut self.outer, select.alias.clone());
+        let query = self.query(plan);
+        self.outer = outer;
+        Ok(Box::new(query?))
+    }
+
+    /// Turns `expr`, which is evaluated on the output of `select`, into SQL.
+    fn expr(&mut self, expr: &Arc<dyn LogicalExpression>, select: &SelectBuilder) -> Result<Expr> {
+        let any = expr.as_any();
+        if let Some(column) = any.downcast_ref::<ColumnExpression>() {
+            Ok(select.column(column.name()))
+        } else if let Some(literal) = any.downcast_ref::<LiteralStringExpression>() {
+            Ok(Expr::String(literal.val().to_owned()))
+        } else if let Some(literal) = any.downcast_ref::<LiteralI64Expression>() {
+            Ok(Expr::Integer(literal.val()))
+        } else if let Some(literal) = any.downcast_ref::<LiteralBooleanExpression>() {
+            Ok(Expr::Boolean(literal.val()))
+        } else if let Some(placeholder) = any.downcast_ref::<PlaceholderExpression>() {
+            Ok(Expr::Placeholder(placeholder.id().to_owned()))
+        } else if let Some(alias) = any.downcast_ref::<AliasExpression>() {
+            // Aliases only name output columns, which the caller takes care of.
+            self.expr(&alias.expr(), select)
+        } else if let Some(not) = any.downcast_ref::<NotExpression>() {
+            let expr = self.expr(&not.expr(), select)?;
+            Ok(Expr::Not(Box::new(nested(expr, NOT_PRECEDENCE))))
+        } else if let Some(boolean) = any.downcast_ref::<BooleanExpression>() {
+            self.binary_expr(boolean, select)
+        } else if let Some(math) = any.downcast_ref::<MathExpression>() {
+            self.binary_expr(math, select)
+        } else if let Some(aggregate) = any.downcast_ref::<AggregateExpression>() {
+            Ok(Expr::Function {
+                name: aggregate.name().to_owned(),
+                args: vec![self.expr(&aggregate.expr(), select)?],
+            })
+        } else if let Some(column) = any.downcast_ref::<OuterColumnExpression>() {
+            match &self.outer {
+                Some(outer) => Ok(identifier(&[outer, column.name()])),
+                None => Err(QrustError::NotImplemented(format!(
+                    "Can't express {} outside of a subquery in SQL",
+                    expr.to_string()
+                ))),
+            }
+        } else if let Some(in_subquery) = any.downcast_ref::<InSubqueryExpression>() {
+            Ok(Expr::InSubquery {
+                expr: Box::new(nested(
+                    self.expr(&in_subquery.expr(), select)?,
+                    COMPARISON_PRECEDENCE + 1,
+                )),
+                subquery: self.subquery(&in_subquery.subquery(), select)?,
+                negated: in_subquery.negated(),
+            })
+        } else if let Some(exists) = any.downcast_ref::<ExistsExpression>() {
+            let expr = Expr::Exists(self.subquery(&exists.subquery(), select)?);
+            Ok(match exists.negated() {
+                true => Expr::Not(Box::new(expr)),
+                false => expr,
+            })
+        } else if let Some(subquery) = any.downcast_ref::<ScalarSubqueryExpression>() {
+            Ok(Expr::Subquery(self.subquery(&subquery.subquery(), select)?))
+        } else {
+            Err(QrustError::NotImplemented(format!(
+                "Can't express {} in SQL",
+                expr.to_string()
+            )))
+        }
+    }
+
+    fn binary_expr(&mut self, expr: &dyn BinaryExpression, select: &SelectBuilder) -> Result<Expr> {
+        let op = match expr.op().as_str() {
+            "=" => BinaryOperator::Eq,
+            "!=" => BinaryOperator::NotEq,
+            "<" => BinaryOperator::Lt,
+            "<=" => BinaryOperator::LtEq,
+            ">" => BinaryOperator::Gt,
+            ">=" => BinaryOperator::GtEq,
+            "AND" => BinaryOperator::And,
+            "OR" => BinaryOperator::Or,
+            "+" => BinaryOperator::Plus,
+            "-" => BinaryOperator::Minus,
+            "*" => BinaryOperator::Multiply,
+            "/" => BinaryOperator::Divide,
+            "%" => BinaryOperator::Modulo,
+            op => {
+                return Err(QrustError::NotImplemented(format!(
+                    "Can't express the operator {} in SQL",
+                    op
+                )))
+            }
+        };
+        let left = self.expr(&expr.l(), select)?;
+        let right = self.expr(&expr.r(), select)?;
+        Ok(binary(left, op, right))
+    }
+}
+
+fn precedence(op: BinaryOperator) -> u8 {
+    match op {
+        BinaryOperator::Or => OR_PRECEDENCE,
+        BinaryOperator::And => AND_PRECEDENCE,
+        BinaryOperator::Eq
+        | BinaryOperator::NotEq
+        | BinaryOperator::Lt
+        | BinaryOperator::LtEq
+        | BinaryOperator::Gt
+        | BinaryOperator::GtEq => COMPARISON_PRECEDENCE,
+        BinaryOperator::Plus | BinaryOperator::Minus => PLUS_MINUS_PRECEDENCE,
+        BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Modulo => {
+            MUL_DIV_MOD_PRECEDENCE
+        }
+    }
+}
+
+/// Puts `expr` in parentheses if it would otherwise be read as the operand of an
+/// operator that binds tighter than `min_precedence` allows.
+fn nested(expr: Expr, min_precedence: u8) -> Expr {
+    let expr_precedence = match &expr {
+        Expr::BinaryOp { op, .. } => precedence(*op),
+        Expr::Not(_) => NOT_PRECEDENCE,
+        Expr::InSubquery { .. } => COMPARISON_PRECEDENCE,
+        _ => u8::MAX,
+    };
+    if expr_precedence < min_precedence {
+        Expr::Nested(Box::new(expr))
+    } else {
+        expr
+    }
+}
+
+/// Combines two operands, adding parentheses where the parser would otherwise group
+/// them differently. Operators are read from left to right, so an operand on the right
+/// needs them even when its operator binds as tightly.
+fn binary(left: Expr, op: BinaryOperator, right: Expr) -> Expr {
+    let precedence = precedence(op);
+    Expr::BinaryOp {
+        left: Box::new(nested(left, precedence)),
+        op,
+        right: Box::new(nested(right, precedence + 1)),
+    }
+}
+
+fn identifier(parts: &[&str]) -> Expr {
+    Expr::Identifier(ObjectName(parts.iter().map(|p| (*p).to_owned()).collect()))
+}
+
+/// Splits a table name as it was given to the context, e.g. `analytics.events`.
+fn object_name(name: &str) -> ObjectName {
+    ObjectName(name.split('.').map(|p| p.to_owned()).collect())
+}
+
+fn has_subquery(expr: &Arc<dyn LogicalExpression>) -> bool {
+    let any = expr.as_any();
+    any.is::<InSubqueryExpression>()
+        || any.is::<ExistsExpression>()
+        || any.is::<ScalarSubqueryExpression>()
+        || expr.children().iter().any(has_subquery)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data_source::CsvReadOptions;
+    use crate::core::data_type::ArrowType;
+    use crate::core::execution_context::ExecutionContext;
+    use crate::core::helper::*;
+    use crate::logical_plan::empty_relation::EmptyRelation;
+    use crate::test_util::{csv_context, rows, sorted_rows};
+
+    fn context(dir: &tempfile::TempDir) -> ExecutionContext {
+        csv_context(
+            dir,
+            vec![
+                (
+                    "sales",
+                    CsvReadOptions::new().schema(schema(vec![
+                        ("id", ArrowType::Int64Type, false),
+                        ("region", ArrowType::StringType, false),
+                        ("amount", ArrowType::Int64Type, false),
+                    ])),
+                    &["1,east,10", "2,west,25", "3,east,40", "4,north,5"],
+                ),
+                (
+                    "regions",
+                    CsvReadOptions::new().schema(schema(vec![
+                        ("name", ArrowType::StringType, false),
+                        ("manager", ArrowType::StringType, false),
+                    ])),
+                    &["east,ann", "west,bob"],
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn dataframe_to_sql() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = context(&dir);
+        let df = ctx
+            .table("sales")
+            .unwrap()
+            .filter(or(
+                gt(col("amount"), lit(&10)),
+                not(eq(col("region"), lit(&"it's"))),
+            ))
+            .aggregate(
+                vec![col("region")],
+                vec![sum(multiply(add(col("amount"), lit(&1)), lit(&2)))],
+            )
+            .filter(gt(col("sum"), lit(&20)))
+            .select(vec![alias(col("sum"), "total"), col("region")]);
+        let sql = df.to_sql().unwrap();
+        assert_eq!(
+            sql,
+            "SELECT sum((amount + 1) * 2) AS total, region FROM sales \
+             WHERE amount > 10 OR NOT region = 'it''s' GROUP BY region \
+             HAVING sum((amount + 1) * 2) > 20"
+        );
+        assert_eq!(
+            sorted_rows(&ctx.sql(&sql).unwrap().collect().unwrap()),
+            sorted_rows(&df.collect().unwrap())
+        );
+
+        // Aggregating an aggregate needs a subquery.
+        let df = ctx
+            .table("sales")
+            .unwrap()
+            .aggregate(vec![col("region")], vec![max(col("amount"))])
+            .aggregate(vec![], vec![min(col("max"))]);
+        assert_eq!(
+            df.to_sql().unwrap(),
+            "SELECT min(max) AS min FROM (SELECT region, max(amount) AS max FROM sales GROUP BY region) AS t1"
+        );
+        assert_eq!(
+            sorted_rows(&ctx.sql(&df.to_sql().unwrap()).unwrap().collect().unwrap()),
+            vec!["5"]
+        );
+    }
+
+    #[test]
+    fn optimized_plans_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = context(&dir);
+        let queries = [
+            "SELECT id, amount - (amount - 1) AS one FROM sales WHERE region = 'east'",
+            "SELECT region, count FROM (SELECT region, sum(amount) AS count FROM sales \
+             GROUP BY region) AS t WHERE count > 10",
+            "SELECT id FROM sales WHERE region IN (SELECT name FROM regions)",
+            "SELECT id FROM sales s WHERE NOT EXISTS \
+             (SELECT * FROM regions r WHERE r.name = s.region)",
+            "SELECT id, amount - (SELECT max(amount) FROM sales) AS diff FROM sales",
+            "SELECT s.id, r.manager FROM sales s LEFT JOIN regions r ON s.region = r.name",
+            "SELECT r.manager, s.id FROM sales s RIGHT JOIN regions r \
+             ON s.region = r.name AND s.amount > 20",
+            "SELECT a.id, b.id FROM sales a FULL JOIN sales b \
+             ON a.region = b.region AND a.id < b.id",
+            "SELECT s.id, r.name FROM sales s CROSS JOIN regions r",
+            "SELECT a.id, b.id FROM sales a LEFT JOIN sales b \
+             ON b.amount BETWEEN a.amount - 5 AND a.amount + 5",
+        ];
+        for query in queries {
+            let plan = ctx.optimize(ctx.sql(query).unwrap().plan()).unwrap();
+            let sql = plan_to_sql(&plan).unwrap();
+            let expected = sorted_rows(&ctx.sql(query).unwrap().collect().unwrap());
+            let actual = match ctx.sql(&sql).and_then(|df| df.collect()) {
+                Ok(batches) => sorted_rows(&batches),
+                Err(e) => panic!("{} was unparsed as {}, which fails: {}", query, sql, e),
+            };
+            assert_eq!(actual, expected, "{} was unparsed as {}", query, sql);
+        }
+
+        let plan = ctx
+            .optimize(
+                ctx.sql("SELECT id FROM sales WHERE region IN (SELECT name FROM regions)")
+                    .unwrap()
+                    .plan(),
+            )
+            .unwrap();
+        assert_eq!(
+            plan_to_sql(&plan).unwrap(),
+            "SELECT id FROM (SELECT id, region FROM sales) AS t1 WHERE EXISTS \
+             (SELECT * FROM (SELECT name FROM regions) AS t2 WHERE t1.region = t2.name)"
+        );
+    }
+
+    #[test]
+    fn ordered_and_limited_queries() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = context(&dir);
+        let queries = [
+            "SELECT id, amount * 2 AS double FROM sales ORDER BY double DESC LIMIT 2 OFFSET 1",
+            "SELECT region FROM sales ORDER BY amount NULLS FIRST, id LIMIT 3",
+            "SELECT region, sum(amount) AS total FROM sales GROUP BY region \
+             ORDER BY total DESC LIMIT 2",
+            "SELECT id FROM (SELECT id, amount FROM sales ORDER BY amount DESC LIMIT 3) AS t \
+             WHERE id > 1 ORDER BY id",
+            "SELECT * FROM (SELECT * FROM sales LIMIT 3 OFFSET 1) AS t LIMIT 5 OFFSET 1",
+            "SELECT id AS amount FROM (SELECT id, amount FROM sales ORDER BY amount) AS t",
+            "SELECT max(id) FROM (SELECT id FROM sales ORDER BY amount LIMIT 3) AS t",
+        ];
+        for query in queries {
+            let plan = ctx.optimize(ctx.sql(query).unwrap().plan()).unwrap();
+            let sql = plan_to_sql(&plan).unwrap();
+            let expected = rows(&ctx.sql(query).unwrap().collect().unwrap());
+            let actual = match ctx.sql(&sql).and_then(|df| df.collect()) {
+                Ok(batches) => rows(&batches),
+                Err(e) => panic!("{} was unparsed as {}, which fails: {}", query, sql, e),
+            };
+            assert_eq!(actual, expected, "{} was unparsed as {}", query, sql);
+        }
+
+        let df = ctx
+            .table("sales")
+            .unwrap()
+            .sort(vec![desc(col("amount"))])
+            .limit(1, Some(2))
+            .limit(1, None)
+            .select(vec![col("id")]);
+        assert_eq!(
+            df.to_sql().unwrap(),
+            "SELECT id FROM sales ORDER BY amount DESC LIMIT 1 OFFSET 2"
+        );
+        assert_eq!(rows(&df.collect().unwrap()), vec!["1"]);
+
+        let df = ctx
+            .table("sales")
+            .unwrap()
+            .sort(vec![asc(col("amount"))])
+            .select(vec![col("region")])
+            .limit(1, Some(2));
+        assert_eq!(
+            df.to_sql().unwrap(),
+            "SELECT region FROM sales ORDER BY amount ASC LIMIT 2 OFFSET 1"
+        );
+        assert_eq!(
+            rows(&ctx.sql(&df.to_sql().unwrap()).unwrap().collect().unwrap()),
+            vec!["east", "west"]
+        );
+
+        // A projection that gives the sorted column another value needs a subquery.
+        let df = ctx
+            .table("sales")
+            .unwrap()
+            .sort(vec![asc(col("amount"))])
+            .select(vec![alias(col("id"), "amount")])
+            .limit(0, Some(2));
+        assert_eq!(
+            df.to_sql().unwrap(),
+            "SELECT id AS amount FROM (SELECT * FROM sales ORDER BY amount ASC) AS t1 LIMIT 2"
+        );
+    }
+
+    #[test]
+    fn qualified_join_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = context(&dir);
+        let query = "SELECT a.region, b.region FROM sales a JOIN sales b ON a.id = b.amount - 9 \
+                     WHERE a.region = 'east' ORDER BY b.region";
+        let sql = plan_to_sql(&ctx.sql(query).unwrap().plan()).unwrap();
+        assert_eq!(
+            sql,
+            "SELECT a.region, b.region FROM sales AS a JOIN sales AS b ON a.id = b.amount - 9 \
+             WHERE a.region = 'east' ORDER BY b.region ASC"
+        );
+        assert_eq!(
+            sorted_rows(&ctx.sql(&sql).unwrap().collect().unwrap()),
+            sorted_rows(&ctx.sql(query).unwrap().collect().unwrap())
+        );
+    }
+
+    #[test]
+    fn subqueries_refer_to_outer_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = context(&dir);
+        let plan = ctx
+            .sql(
+                "SELECT id FROM sales s WHERE amount > \
+                 (SELECT min(amount) FROM sales t WHERE t.region = s.region)",
+            )
+            .unwrap()
+            .plan();
+        assert_eq!(
+            plan_to_sql(&plan).unwrap(),
+            "SELECT id FROM sales AS t1 WHERE amount > \
+             (SELECT min(amount) AS \"min(amount)\" FROM sales WHERE region = t1.region)"
+        );
+        assert_eq!(
+            sorted_rows(
+                &ctx.sql(&plan_to_sql(&plan).unwrap())
+                    .unwrap()
+                    .collect()
+                    .unwrap()
+            ),
+            vec!["3"]
+        );
+    }
+
+    #[test]
+    fn statements() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = context(&dir);
+        let explain = ctx.sql("EXPLAIN SELECT * FROM regions").unwrap().plan();
+        assert_eq!(
+            plan_to_sql(&explain).unwrap(),
+            "EXPLAIN SELECT * FROM regions"
+        );
+
+        let create: Arc<dyn LogicalPlan> = Arc::new(CreateExternalTable::new(
+            "analytics.my events".to_owned(),
+            schema(vec![
+                ("id", ArrowType::Int64Type, false),
+                ("name", ArrowType::StringType, true),
+            ]),
+            "/data/events.csv".to_owned(),
+            true,
+            b'|',
+            false,
+        ));
+        assert_eq!(
+            plan_to_sql(&create).unwrap(),
+            "CREATE EXTERNAL TABLE analytics.\"my events\" (id BIGINT NOT NULL, name VARCHAR) \
+             STORED AS CSV WITH HEADER ROW DELIMITER '|' LOCATION '/data/events.csv'"
+        );
+
+        let empty: Arc<dyn LogicalPlan> =
+            Arc::new(EmptyRelation::new(ctx.table("regions").unwrap().schema()));
+        assert_eq!(
+            plan_to_sql(&empty).unwrap_err().to_string(),
+            "Not implemented: Can't express EmptyRelation in SQL"
+        );
+    }
+}
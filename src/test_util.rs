@@ -0,0 +1,107 @@
+//! Helpers shared by the unit tests.
+
+use std::fs::File;
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, Int64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use arrow::util::display::array_value_to_string;
+use tempfile::TempDir;
+
+use crate::core::data_source::{CsvReadOptions, DataSource};
+use crate::core::error::Result;
+use crate::core::execution_context::ExecutionContext;
+use crate::logical_plan::expression::LogicalExpression;
+use crate::physical_plan::BatchIterator;
+
+/// Writes `rows` to a CSV file called `name` inside `dir` and returns its path.
+pub fn write_csv(dir: &TempDir, name: &str, rows: &[&str]) -> String {
+    let path = dir.path().join(name);
+    let mut file = File::create(&path).unwrap();
+    for row in rows {
+        writeln!(file, "{}", row).unwrap();
+    }
+    path.into_os_string().into_string().unwrap()
+}
+
+/// Creates a context with a CSV table for each of `tables`, given as its name, the
+/// options it is read with and its rows, which are written to a file inside `dir`.
+pub fn csv_context(
+    dir: &TempDir,
+    tables: Vec<(&str, CsvReadOptions, &[&str])>,
+) -> ExecutionContext {
+    let ctx = ExecutionContext::new();
+    for (name, options, rows) in tables {
+        let path = write_csv(dir, &format!("{}.csv", name), rows);
+        ctx.register_csv(name, &path, options).unwrap();
+    }
+    ctx
+}
+
+/// Formats every row of `batches` as its values separated by commas.
+pub fn rows(batches: &[RecordBatch]) -> Vec<String> {
+    let mut rows = vec![];
+    for batch in batches {
+        for row in 0..batch.num_rows() {
+            let values = batch
+                .columns()
+                .iter()
+                .map(|c| array_value_to_string(c, row).unwrap())
+                .collect::<Vec<_>>();
+            rows.push(values.join(","));
+        }
+    }
+    rows
+}
+
+/// Like `rows`, but sorted so that results can be compared whatever their order.
+pub fn sorted_rows(batches: &[RecordBatch]) -> Vec<String> {
+    let mut rows = rows(batches);
+    rows.sort();
+    rows
+}
+
+/// A data source that produces `batches` single-row batches on demand and counts how
+/// many of them have been pulled.
+pub struct CountingSource {
+    schema: Arc<Schema>,
+    batches: usize,
+    pulled: Arc<AtomicUsize>,
+}
+
+impl CountingSource {
+    pub fn new(batches: usize) -> CountingSource {
+        CountingSource {
+            schema: Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, false)])),
+            batches,
+            pulled: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub fn pulled(&self) -> usize {
+        self.pulled.load(Ordering::SeqCst)
+    }
+}
+
+impl DataSource for CountingSource {
+    fn schema(&self) -> Arc<Schema> {
+        Arc::clone(&self.schema)
+    }
+
+    fn scan(
+        &self,
+        _projection: Vec<String>,
+        _filters: Vec<Arc<dyn LogicalExpression>>,
+    ) -> Result<BatchIterator> {
+        let schema = Arc::clone(&self.schema);
+        let pulled = Arc::clone(&self.pulled);
+        Ok(Box::new((0..self.batches).map(move |i| {
+            pulled.fetch_add(1, Ordering::SeqCst);
+            let column: ArrayRef = Arc::new(Int64Array::from(vec![i as i64]));
+            Ok(RecordBatch::try_new(Arc::clone(&schema), vec![column])?)
+        })))
+    }
+}